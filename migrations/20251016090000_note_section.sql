@@ -0,0 +1,2 @@
+-- Named sections (`## Work`) within a day.
+ALTER TABLE note ADD COLUMN section TEXT;
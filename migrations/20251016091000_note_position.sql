@@ -0,0 +1,2 @@
+-- Manual ordering of notes within a day.
+ALTER TABLE note ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- Markdown templates used to fill empty days, keyed by weekday or "default".
+CREATE table template (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    body TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Tags attached to notes.
+CREATE table note_tag (
+    note_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (note_id, tag),
+    FOREIGN KEY (note_id) REFERENCES note (id)
+);
@@ -0,0 +1,2 @@
+-- Optional due date of a note.
+ALTER TABLE note ADD COLUMN due DATE;
@@ -0,0 +1,6 @@
+-- Named queries run with `fh q <name>`.
+CREATE table saved_query (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    query TEXT NOT NULL
+);
@@ -0,0 +1,8 @@
+-- Free form key value metadata on notes.
+CREATE table note_meta (
+    note_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (note_id, key),
+    FOREIGN KEY (note_id) REFERENCES note (id)
+);
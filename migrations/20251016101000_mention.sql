@@ -0,0 +1,7 @@
+-- People mentioned with `@name` in notes.
+CREATE table mention (
+    note_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (note_id, name),
+    FOREIGN KEY (note_id) REFERENCES note (id)
+);
@@ -0,0 +1,7 @@
+-- Projects notes can be assigned to with `+project`.
+CREATE table project (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE
+);
+
+ALTER TABLE note ADD COLUMN project_key INTEGER REFERENCES project (id);
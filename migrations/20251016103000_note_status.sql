@@ -0,0 +1,6 @@
+-- Replace the completed flag with a status: todo, doing, done, blocked or cancelled.
+ALTER TABLE note ADD COLUMN status TEXT NOT NULL DEFAULT 'todo';
+
+UPDATE note SET status = 'done' WHERE completed = 1;
+
+ALTER TABLE note DROP COLUMN completed;
@@ -0,0 +1,2 @@
+-- Position of a note in its day's focus list.
+ALTER TABLE note ADD COLUMN focus_rank INTEGER;
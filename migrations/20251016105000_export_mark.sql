@@ -0,0 +1,5 @@
+-- High-water marks of incremental exports, the latest change each export has emitted.
+CREATE table export_mark (
+    name TEXT PRIMARY KEY NOT NULL,
+    changed_at TEXT NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Databases old days have been moved into by `fh archive`.
+CREATE table archive (
+    id INTEGER PRIMARY KEY NOT NULL,
+    path TEXT NOT NULL UNIQUE,
+    before DATE NOT NULL
+);
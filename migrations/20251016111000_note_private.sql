@@ -0,0 +1,2 @@
+-- Private notes are left out of exports and reports unless asked for.
+ALTER TABLE note ADD COLUMN private INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- Read-only html snapshots handed out by `fh share`, served until they expire.
+CREATE table share (
+    token TEXT PRIMARY KEY NOT NULL,
+    html TEXT NOT NULL,
+    expires_at DATETIMETZ NOT NULL
+);
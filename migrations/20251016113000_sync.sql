@@ -0,0 +1,15 @@
+-- Ids notes keep across databases synced with `fh sync`, filled in on the first sync.
+ALTER TABLE note ADD COLUMN uid TEXT;
+
+CREATE UNIQUE INDEX note_uid ON note (uid);
+
+-- Id of this database, told apart from its sync peers.
+CREATE table device (id TEXT PRIMARY KEY NOT NULL);
+
+INSERT INTO device (id) VALUES (lower(hex(randomblob(16))));
+
+-- When this database last synced with each peer.
+CREATE table sync_peer (
+    device TEXT PRIMARY KEY NOT NULL,
+    synced_at TEXT NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Due dates `fh notify` already sent a desktop notification for, a new due date notifies again.
+CREATE table notified (
+    note_id INTEGER PRIMARY KEY NOT NULL,
+    due DATE NOT NULL,
+    FOREIGN KEY (note_id) REFERENCES note (id)
+);
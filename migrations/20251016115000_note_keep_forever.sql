@@ -0,0 +1,2 @@
+-- Deleted notes exempt from the trash retention purge.
+ALTER TABLE note ADD COLUMN keep_forever INTEGER NOT NULL DEFAULT 0;
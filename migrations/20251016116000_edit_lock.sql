@@ -0,0 +1,6 @@
+-- Days open in `fh edit`, so a second session on the same day is warned.
+CREATE table edit_lock (
+    date DATE PRIMARY KEY NOT NULL,
+    pid INTEGER NOT NULL,
+    locked_at DATETIMETZ NOT NULL DEFAULT (datetime ('now'))
+);
@@ -0,0 +1,8 @@
+-- Quarterly and yearly goals, notes link to one with a `goal:<slug>` token.
+CREATE table goal (
+    id INTEGER PRIMARY KEY NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    -- `2025Q3` for a quarter, `2025` for a year.
+    period TEXT NOT NULL
+);
@@ -0,0 +1,8 @@
+-- Mood and energy from 1 to 5 and free tags recorded for a day.
+CREATE table day_metric (
+    date DATE PRIMARY KEY NOT NULL,
+    mood INTEGER,
+    energy INTEGER,
+    -- Comma separated.
+    tags TEXT NOT NULL DEFAULT ''
+);
@@ -0,0 +1,7 @@
+-- Key value metadata of a day, written as frontmatter above the edit buffer's header.
+CREATE table day_meta (
+    date DATE NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (date, key)
+);
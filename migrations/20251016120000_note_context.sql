@@ -0,0 +1,2 @@
+-- Where a note can be done, set by an `@context` token naming one of the configured contexts.
+ALTER TABLE note ADD COLUMN context TEXT;
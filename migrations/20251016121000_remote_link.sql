@@ -0,0 +1,8 @@
+-- Notes linked to an item in another system, e.g. a Jira issue, by its id there.
+CREATE table remote_link (
+    note_id INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    remote_id TEXT NOT NULL,
+    PRIMARY KEY (source, remote_id),
+    FOREIGN KEY (note_id) REFERENCES note (id)
+);
@@ -0,0 +1,10 @@
+-- Files attached to notes, stored once by the sha256 of their content.
+CREATE table attachment (
+    note_id INTEGER NOT NULL,
+    -- File name the attachment was added under.
+    name TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    created_at DATETIMETZ NOT NULL DEFAULT (datetime ('now')),
+    PRIMARY KEY (note_id, hash),
+    FOREIGN KEY (note_id) REFERENCES note (id)
+);
@@ -0,0 +1,6 @@
+-- Days marked intentionally empty by `fh day skip`, e.g. a vacation. They don't break streaks
+-- or count towards how late an overdue note is.
+CREATE table skipped_day (
+    date DATE PRIMARY KEY NOT NULL,
+    reason TEXT
+);
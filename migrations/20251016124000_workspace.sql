@@ -0,0 +1,25 @@
+-- Workspaces sharing the database, `--workspace` or the `workspace` config pick the one days
+-- and notes are read from and written to. A date has a day in each workspace, so the day table
+-- is rebuilt with dates unique per workspace. Its rows are copied out and inserted back, which
+-- lets the deferred foreign keys of notes find their days again before the commit.
+PRAGMA defer_foreign_keys = ON;
+
+CREATE table day_copy AS SELECT * FROM day;
+
+DROP TABLE day;
+
+CREATE table day (
+    id INTEGER PRIMARY KEY NOT NULL,
+    task_count INTEGER NOT NULL,
+    date DATE NOT NULL,
+    day_text TEXT NOT NULL,
+    workspace TEXT NOT NULL DEFAULT 'default',
+    UNIQUE (workspace, date)
+);
+
+INSERT INTO day (id, task_count, date, day_text)
+SELECT id, task_count, date, day_text FROM day_copy;
+
+DROP TABLE day_copy;
+
+ALTER TABLE note ADD COLUMN workspace TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,6 @@
+-- Times set by `fh remind` to notify about a note, removed once the reminder is sent.
+CREATE table reminder (
+    note_id INTEGER PRIMARY KEY NOT NULL,
+    remind_at DATETIMETZ NOT NULL,
+    FOREIGN KEY (note_id) REFERENCES note (id)
+);
@@ -0,0 +1,63 @@
+-- Metrics, metadata, skips and edit locks belong to a day of a workspace, like the day itself.
+-- Nothing references these tables, so each is renamed, recreated keyed by workspace and date
+-- and filled back in the default workspace.
+ALTER TABLE edit_lock RENAME TO edit_lock_old;
+
+CREATE table edit_lock (
+    workspace TEXT NOT NULL DEFAULT 'default',
+    date DATE NOT NULL,
+    pid INTEGER NOT NULL,
+    locked_at DATETIMETZ NOT NULL DEFAULT (datetime ('now')),
+    PRIMARY KEY (workspace, date)
+);
+
+INSERT INTO edit_lock (date, pid, locked_at)
+SELECT date, pid, locked_at FROM edit_lock_old;
+
+DROP TABLE edit_lock_old;
+
+ALTER TABLE day_metric RENAME TO day_metric_old;
+
+CREATE table day_metric (
+    workspace TEXT NOT NULL DEFAULT 'default',
+    date DATE NOT NULL,
+    mood INTEGER,
+    energy INTEGER,
+    -- Comma separated.
+    tags TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (workspace, date)
+);
+
+INSERT INTO day_metric (date, mood, energy, tags)
+SELECT date, mood, energy, tags FROM day_metric_old;
+
+DROP TABLE day_metric_old;
+
+ALTER TABLE day_meta RENAME TO day_meta_old;
+
+CREATE table day_meta (
+    workspace TEXT NOT NULL DEFAULT 'default',
+    date DATE NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (workspace, date, key)
+);
+
+INSERT INTO day_meta (date, key, value)
+SELECT date, key, value FROM day_meta_old;
+
+DROP TABLE day_meta_old;
+
+ALTER TABLE skipped_day RENAME TO skipped_day_old;
+
+CREATE table skipped_day (
+    workspace TEXT NOT NULL DEFAULT 'default',
+    date DATE NOT NULL,
+    reason TEXT,
+    PRIMARY KEY (workspace, date)
+);
+
+INSERT INTO skipped_day (date, reason)
+SELECT date, reason FROM skipped_day_old;
+
+DROP TABLE skipped_day_old;
@@ -0,0 +1,2 @@
+-- Tags read from a `#tag` word in the body, replaced whenever the body is indexed again.
+ALTER TABLE note_tag ADD COLUMN from_body INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,17 @@
+-- Uids only need to be unique within a workspace, each one syncs on its own.
+DROP INDEX note_uid;
+
+CREATE UNIQUE INDEX note_uid ON note (workspace, uid);
+
+-- Synced notes removed for good by purging the trash or archiving, sent to peers as deletions.
+CREATE table sync_tombstone (
+    workspace TEXT NOT NULL,
+    uid TEXT NOT NULL,
+    date DATE NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL,
+    section TEXT,
+    due DATE,
+    deleted_at TEXT NOT NULL,
+    PRIMARY KEY (workspace, uid)
+);
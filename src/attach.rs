@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use ring::digest;
+
+use crate::store::Attachment;
+
+/// Directory in the config directory holding attached files by content hash.
+pub const DIR_NAME: &str = "attachments";
+
+/// Copy `file` into `dir` under the sha256 of its content, returning the hash. A file with the
+/// same content is only kept once.
+pub fn store_file(file: &Path, dir: &Path) -> Result<String> {
+    let bytes = std::fs::read(file).context(format!("Failed reading {}.", file.display()))?;
+    let hash = digest::digest(&digest::SHA256, &bytes)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    let target = dir.join(stored_name(&hash, &file_name(file)?));
+    if !target.exists() {
+        std::fs::create_dir_all(dir).context(format!("Failed creating {}.", dir.display()))?;
+        std::fs::write(&target, &bytes).context(format!("Failed writing {}.", target.display()))?;
+    }
+    Ok(hash)
+}
+
+/// Where the attachment's file is kept in `dir`.
+pub fn path(dir: &Path, attachment: &Attachment) -> PathBuf {
+    dir.join(stored_name(&attachment.hash, &attachment.name))
+}
+
+pub fn file_name(file: &Path) -> Result<String> {
+    file.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or(anyhow!("{} isn't a file.", file.display()))
+}
+
+/// The hash with the name's extension, so the opener picks the right program.
+fn stored_name(hash: &str, name: &str) -> String {
+    match Path::new(name).extension() {
+        Some(ext) => format!("{}.{}", hash, ext.to_string_lossy()),
+        None => hash.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{path, store_file};
+    use crate::store::Attachment;
+
+    #[test]
+    fn test_store_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("design.png");
+        let second = dir.path().join("copy.png");
+        std::fs::write(&first, "pixels").unwrap();
+        std::fs::write(&second, "pixels").unwrap();
+        let store = dir.path().join("attachments");
+        let hash = store_file(&first, &store).unwrap();
+        assert_eq!(store_file(&second, &store).unwrap(), hash);
+        assert_eq!(std::fs::read_dir(&store).unwrap().count(), 1);
+        let attachment = Attachment {
+            note_id: 1,
+            name: String::from("design.png"),
+            hash: hash.clone(),
+        };
+        let stored = path(&store, &attachment);
+        assert_eq!(stored, store.join(format!("{}.png", hash)));
+        assert_eq!(std::fs::read_to_string(stored).unwrap(), "pixels");
+    }
+}
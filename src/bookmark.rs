@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+
+/// Note metadata key holding the url of a note saved with its page title.
+pub const KEY: &str = "bookmark";
+
+/// Largest page read looking for a title.
+const MAX_PAGE_BYTES: usize = 512 * 1024;
+
+/// Whether the body is a single http(s) url.
+pub fn is_url(body: &str) -> bool {
+    let body = body.trim();
+    !body.contains(char::is_whitespace)
+        && (body.starts_with("http://") || body.starts_with("https://"))
+}
+
+/// The title of the page at `url`, `None` when it has none.
+pub async fn fetch_title(url: &str) -> Result<Option<String>> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .context(format!("Failed fetching {}.", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("{} answered {}.", url, response.status()));
+    }
+    // The title is in the head, so stop reading long pages early.
+    let mut page = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        page.extend_from_slice(&chunk);
+        if page.len() > MAX_PAGE_BYTES {
+            break;
+        }
+    }
+    Ok(parse_title(&String::from_utf8_lossy(&page)))
+}
+
+/// Text of the first `<title>` element, on one line with the common entities decoded.
+pub fn parse_title(html: &str) -> Option<String> {
+    let lower = html.to_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = html[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_url, parse_title};
+
+    #[test]
+    fn test_parse_title() {
+        let html = "<html><HEAD><meta charset=\"utf-8\">\n<Title data-x=\"1\">\n  Rust &amp; Cargo\n  Book</title></head></html>";
+        assert_eq!(parse_title(html).as_deref(), Some("Rust & Cargo Book"));
+        assert_eq!(parse_title("<title> </title>"), None);
+        assert_eq!(parse_title("<p>no title</p>"), None);
+        assert!(is_url(" https://example.com/a?b=c "));
+        assert!(!is_url("read https://example.com"));
+        assert!(!is_url("ftp://example.com"));
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Local;
+
+use crate::{
+    notes::{NewNote, Note},
+    store::NoteStore,
+};
+
+/// Subdirectory of a capture directory processed files are moved to.
+pub const ARCHIVE_DIR: &str = "archive";
+/// Section of the day captured notes are added under.
+pub const SECTION: &str = "Inbox";
+
+/// Add a note to today's inbox for each `.txt` or `.md` file in `dir`, in name order, and
+/// move the file to the archive subdirectory. Empty files are archived without a note.
+pub async fn capture_dir(store: &NoteStore, dir: &Path) -> Result<Vec<Note>> {
+    let today = Local::now().date_naive();
+    let archive = dir.join(ARCHIVE_DIR);
+    let mut notes = Vec::new();
+    for file in capture_files(dir)? {
+        let text = std::fs::read_to_string(&file)
+            .context(format!("Failed reading {}.", file.display()))?;
+        if let Some(body) = note_body(&text) {
+            let new = NewNote::builder().body(body).section(Some(SECTION)).build();
+            let note = store.insert_note_on(today, new).await?;
+            store.index_note_body(&note).await?;
+            notes.push(note);
+        }
+        std::fs::create_dir_all(&archive)
+            .context(format!("Failed creating {}.", archive.display()))?;
+        let target = archive_path(&archive, &file);
+        std::fs::rename(&file, &target).context(format!("Failed archiving {}.", file.display()))?;
+    }
+    Ok(notes)
+}
+
+fn capture_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = std::fs::read_dir(dir)
+        .context(format!("Failed reading {}.", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "txt" || e == "md"))
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+/// A file's text on one line, a leading markdown list or task marker dropped.
+fn note_body(text: &str) -> Option<String> {
+    let text = text.trim_start();
+    let text = ["- [ ] ", "- ", "* "]
+        .iter()
+        .find_map(|m| text.strip_prefix(m))
+        .unwrap_or(text);
+    let body = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!body.is_empty()).then_some(body)
+}
+
+/// Where `file` goes in `archive`, numbered when a file of that name was archived before.
+fn archive_path(archive: &Path, file: &Path) -> PathBuf {
+    let name = file.file_name().unwrap_or_default();
+    let mut target = archive.join(name);
+    let mut n = 1;
+    while target.exists() {
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+        let ext = file.extension().unwrap_or_default().to_string_lossy();
+        target = archive.join(format!("{}-{}.{}", stem, n, ext));
+        n += 1;
+    }
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ARCHIVE_DIR, SECTION, capture_dir};
+    use crate::store::NoteStore;
+
+    #[tokio::test]
+    async fn test_capture_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "Call the\nplumber\n").unwrap();
+        std::fs::write(dir.path().join("b.md"), "- [ ] buy ink").unwrap();
+        std::fs::write(dir.path().join("c.txt"), "\n").unwrap();
+        std::fs::write(dir.path().join("memo.m4a"), "audio").unwrap();
+        let archive = dir.path().join(ARCHIVE_DIR);
+        std::fs::create_dir(&archive).unwrap();
+        std::fs::write(archive.join("a.txt"), "earlier").unwrap();
+        let store = NoteStore::open_in_memory().await.unwrap();
+        let notes = capture_dir(&store, dir.path()).await.unwrap();
+        let bodies = notes.iter().map(|n| n.body.as_str()).collect::<Vec<_>>();
+        assert_eq!(bodies, ["Call the plumber", "buy ink"]);
+        assert_eq!(notes[0].section.as_deref(), Some(SECTION));
+        assert!(dir.path().join("memo.m4a").exists());
+        assert!(!dir.path().join("a.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(archive.join("a-1.txt")).unwrap(),
+            "Call the\nplumber\n"
+        );
+        assert!(archive.join("c.txt").exists());
+        assert!(capture_dir(&store, dir.path()).await.unwrap().is_empty());
+    }
+}
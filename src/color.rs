@@ -0,0 +1,31 @@
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ansi_term::Style;
+use clap::ColorChoice;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Style output as `--color` asks, `auto` styles it when stdout is a terminal and `NO_COLOR`
+/// isn't set.
+pub fn init(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+        }
+    };
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// `style`, or no style at all when colors are off.
+pub fn style(style: Style) -> Style {
+    if ENABLED.load(Ordering::Relaxed) {
+        style
+    } else {
+        Style::new()
+    }
+}
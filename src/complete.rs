@@ -0,0 +1,112 @@
+use std::future::Future;
+
+use anyhow::Result;
+use clap_complete::CompletionCandidate;
+
+use crate::{
+    config::{Config, config_dir, db_path},
+    filter::NoteFilter,
+    notes::StatusMatch,
+    remote::RemoteStore,
+    store::NoteStore,
+};
+
+/// Most open notes offered when completing an id, newest first.
+const NOTE_LIMIT: usize = 50;
+
+/// Run a lookup against the configured database while completing, nothing is offered on error
+/// so a missing database never breaks the shell.
+fn lookup<F, Fut>(f: F) -> Vec<CompletionCandidate>
+where
+    F: FnOnce(NoteStore) -> Fut,
+    Fut: Future<Output = Result<Vec<CompletionCandidate>>>,
+{
+    let candidates = async {
+        let dir = config_dir()?;
+        let config = Config::load(&dir.join("config.toml"))?;
+        let db_path = db_path(&dir);
+        // The local database isn't the one commands use while a remote is configured.
+        if !db_path.exists() || RemoteStore::from_config(&config.remote)?.is_some() {
+            return Ok(vec![]);
+        }
+        f(NoteStore::open(&db_path)
+            .await?
+            .with_workspace(config.workspace()))
+        .await
+    };
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(candidates))
+        .unwrap_or_default()
+}
+
+/// Ids of open notes with their body as help.
+pub fn open_note_ids() -> Vec<CompletionCandidate> {
+    lookup(|store| async move {
+        let filter = NoteFilter {
+            status: Some(StatusMatch::Open),
+            ..Default::default()
+        };
+        let notes = store.list_notes(&filter, None).await?;
+        Ok(id_candidates(
+            notes.into_iter().map(|(_, n)| (n.id, n.body)),
+        ))
+    })
+}
+
+/// People mentioned with `@name`.
+pub fn people() -> Vec<CompletionCandidate> {
+    lookup(|store| async move {
+        Ok(store
+            .mention_counts()
+            .await?
+            .into_iter()
+            .map(|(name, _)| CompletionCandidate::new(name))
+            .collect())
+    })
+}
+
+/// Tags from `bulk tag` and `#tag` words, most used first.
+pub fn tags() -> Vec<CompletionCandidate> {
+    lookup(|store| async move {
+        Ok(store
+            .tag_counts()
+            .await?
+            .into_iter()
+            .map(|(tag, _)| CompletionCandidate::new(tag))
+            .collect())
+    })
+}
+
+pub fn projects() -> Vec<CompletionCandidate> {
+    lookup(|store| async move {
+        Ok(store
+            .project_counts()
+            .await?
+            .into_iter()
+            .map(|(name, _, _)| CompletionCandidate::new(name))
+            .collect())
+    })
+}
+
+fn id_candidates(
+    notes: impl DoubleEndedIterator<Item = (u32, String)>,
+) -> Vec<CompletionCandidate> {
+    notes
+        .rev()
+        .take(NOTE_LIMIT)
+        .map(|(id, body)| CompletionCandidate::new(id.to_string()).help(Some(body.into())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::id_candidates;
+
+    #[test]
+    fn test_id_candidates() {
+        let notes = (1..=60).map(|id| (id, format!("note {}", id)));
+        let candidates = id_candidates(notes);
+        assert_eq!(candidates.len(), 50);
+        assert_eq!(candidates[0].get_value(), "60");
+        assert_eq!(candidates[0].get_help().unwrap().to_string(), "note 60");
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::Weekday;
+use serde::Deserialize;
+
+use crate::{
+    gtasks::GtasksConfig, hooks::HooksConfig, jira::JiraConfig, mail::MailConfig,
+    notes::BufferFormat, push::PushConfig, remote::RemoteConfig, server::ServerConfig,
+    store::DEFAULT_WORKSPACE, urgency::UrgencyWeights,
+};
+
+/// Directory holding the config and the default database, `~/.fuckhead`.
+pub fn config_dir() -> Result<PathBuf> {
+    let home =
+        std::env::var("HOME").context("HOME isn't set, fh keeps its notes in ~/.fuckhead.")?;
+    Ok(PathBuf::from(home).join(".fuckhead"))
+}
+
+/// Database kept in the config directory `dir`.
+pub fn db_path(dir: &Path) -> PathBuf {
+    dir.join("db.db")
+}
+
+/// User configuration read from `~/.fuckhead/config.toml`, every key is optional.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Journaling questions shown as comments in the buffer of an empty day.
+    pub prompts: Vec<String>,
+    /// How `edit` writes notes, `markdown` tasks or the `legacy` `:id:` lines.
+    pub buffer_format: BufferFormat,
+    /// strftime format of the buffer's date header, e.g. `%Y-%m-%d (%A)`, `Today: <date>` when unset.
+    pub header_format: Option<String>,
+    /// First day of the week for `show week`, e.g. `monday` or `sunday`.
+    pub week_start: Weekday,
+    /// How many days back `new` looks for an open note with the same body.
+    pub duplicate_window_days: u32,
+    /// Most notes removed from an `edit` buffer that are deleted without asking, deleting more
+    /// needs confirming or `--yes`.
+    pub edit_delete_limit: usize,
+    /// Most notes `focus set` accepts for one day.
+    pub focus_limit: usize,
+    /// How many days ahead `notify` looks for due notes, 0 only notifies notes due today.
+    pub notify_window_days: u32,
+    pub urgency: UrgencyWeights,
+    /// How far back `onthisday` looks, as spans like `1m` or `2y`.
+    pub on_this_day: Vec<String>,
+    /// age recipient `new --secret` encrypts notes to.
+    pub age_recipient: Option<String>,
+    /// age identity file `show --reveal` decrypts notes with.
+    pub age_identity: Option<PathBuf>,
+    pub server: ServerConfig,
+    /// Workspace of the database days and notes are kept in, overridden by `--workspace`.
+    pub workspace: Option<String>,
+    /// Days deleted notes are kept before `edit`, the daemon or `trash --purge` remove them for
+    /// good, kept forever when unset.
+    pub trash_retention_days: Option<u32>,
+    /// Litestream replica url `replicate` streams to, e.g. `s3://bucket/fh`.
+    pub replica: Option<String>,
+    pub remote: RemoteConfig,
+    pub hooks: HooksConfig,
+    /// `@name` tokens naming where a note can be done rather than a person, for `show --at`.
+    pub contexts: Vec<String>,
+    /// Repositories `git-capture` reads today's commits from, `~/src/*` for every one in `~/src`.
+    pub git_repos: Vec<String>,
+    /// Save the page title before a new note that's only a url, like `new --fetch-title`.
+    pub fetch_titles: bool,
+    /// Directory `capture-dir` and the daemon turn dropped `.txt` and `.md` files into notes from.
+    pub capture_dir: Option<PathBuf>,
+    pub jira: JiraConfig,
+    pub gtasks: GtasksConfig,
+    pub push: PushConfig,
+    pub mail: MailConfig,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            prompts: vec![],
+            buffer_format: BufferFormat::default(),
+            header_format: None,
+            week_start: Weekday::Mon,
+            duplicate_window_days: 7,
+            edit_delete_limit: 3,
+            focus_limit: 3,
+            notify_window_days: 1,
+            urgency: UrgencyWeights::default(),
+            on_this_day: vec![String::from("1m"), String::from("1y"), String::from("2y")],
+            age_recipient: None,
+            age_identity: None,
+            server: ServerConfig::default(),
+            workspace: None,
+            trash_retention_days: None,
+            replica: None,
+            remote: RemoteConfig::default(),
+            hooks: HooksConfig::default(),
+            contexts: ["home", "office", "errands", "phone", "computer"]
+                .map(String::from)
+                .to_vec(),
+            git_repos: vec![],
+            fetch_titles: false,
+            capture_dir: None,
+            jira: JiraConfig::default(),
+            gtasks: GtasksConfig::default(),
+            push: PushConfig::default(),
+            mail: MailConfig::default(),
+        }
+    }
+}
+impl Config {
+    /// Load config from `path`, using the defaults when the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Config> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .context(format!("Failed reading config {}.", path.display()))?;
+        toml::from_str(&text).context(format!("Failed parsing config {}.", path.display()))
+    }
+    /// Workspace to read and write when `--workspace` isn't given.
+    pub fn workspace(&self) -> &str {
+        self.workspace.as_deref().unwrap_or(DEFAULT_WORKSPACE)
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+};
+
+use crate::{
+    capture,
+    notes::{NewNote, Note},
+    notify,
+    push::PushConfig,
+    store::NoteStore,
+};
+
+/// Name of the socket `fh daemon` listens on, next to the database.
+pub const SOCKET_NAME: &str = "fh.sock";
+
+/// A command the CLI hands to the daemon, sent as one JSON line.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum Request {
+    /// Add a note, `duplicate_since` is where the duplicate check starts, `None` skips it.
+    New {
+        date: NaiveDate,
+        body: String,
+        duplicate_since: Option<NaiveDate>,
+    },
+    /// Print a day.
+    Show { date: NaiveDate },
+}
+
+/// The daemon's answer, sent as one JSON line.
+#[derive(Serialize, Deserialize, Debug)]
+pub enum Response {
+    /// Text to print.
+    Output(String),
+    /// Notes changed by the request with their date, printed by the CLI in its `--format`.
+    Notes(Vec<(NaiveDate, Note)>),
+    /// The request needs the terminal, e.g. to confirm a duplicate, so the CLI should run it itself.
+    Fallback,
+    Error(String),
+}
+
+/// How often the daemon reads the capture directory.
+const CAPTURE_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the daemon sends the reminders that are due.
+const REMINDER_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Serve requests on the unix socket at `path` until interrupted, replacing a stale socket.
+/// Files dropped in `capture_dir` are added as notes and reminders are sent, through `push`
+/// too when it's configured, while it runs.
+pub async fn run(
+    store: NoteStore,
+    path: &Path,
+    capture_dir: Option<PathBuf>,
+    push: PushConfig,
+) -> Result<()> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(anyhow::anyhow!(
+                "A daemon is already listening on {}.",
+                path.display()
+            ));
+        }
+        std::fs::remove_file(path).context(format!("Failed removing {}.", path.display()))?;
+    }
+    let listener =
+        UnixListener::bind(path).context(format!("Failed binding {}.", path.display()))?;
+    println!("Listening on {}", path.display());
+    let store = Arc::new(store);
+    if let Some(dir) = capture_dir {
+        let store = store.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CAPTURE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = capture::capture_dir(&store, &dir).await {
+                    tracing::warn!("Capturing {} failed: {:#}", dir.display(), e);
+                }
+            }
+        });
+    }
+    {
+        let store = store.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REMINDER_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = notify::send_reminders(&store, &push, Utc::now(), false).await {
+                    tracing::warn!("Sending reminders failed: {:#}", e);
+                }
+            }
+        });
+    }
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .context("Failed accepting connection.")?;
+        let store = store.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(&store, stream).await {
+                tracing::warn!("Daemon connection failed: {:#}", e);
+            }
+        });
+    }
+}
+
+async fn handle(store: &NoteStore, stream: UnixStream) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(read).read_line(&mut line).await?;
+    let response = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => match respond(store, request).await {
+            Ok(response) => response,
+            Err(e) => Response::Error(format!("{:#}", e)),
+        },
+        Err(e) => Response::Error(format!("Invalid request: {}", e)),
+    };
+    let mut out = serde_json::to_string(&response)?;
+    out.push('\n');
+    write.write_all(out.as_bytes()).await?;
+    Ok(())
+}
+
+async fn respond(store: &NoteStore, request: Request) -> Result<Response> {
+    match request {
+        Request::New {
+            date,
+            body,
+            duplicate_since,
+        } => {
+            if let Some(since) = duplicate_since
+                && store.find_open_duplicate(&body, since).await?.is_some()
+            {
+                return Ok(Response::Fallback);
+            }
+            let note = store.insert_note_on(date, NewNote::new(body)).await?;
+            store.index_note_body(&note).await?;
+            Ok(Response::Notes(vec![(date, note)]))
+        }
+        Request::Show { date } => {
+            let notes = store.get_days_notes(date).await?;
+            Ok(Response::Output(notes.pretty()))
+        }
+    }
+}
+
+/// Send a request to the daemon at `path`, `None` when no daemon is listening.
+pub async fn send(path: &Path, request: &Request) -> Result<Option<Response>> {
+    let Ok(stream) = UnixStream::connect(path).await else {
+        return Ok(None);
+    };
+    let (read, mut write) = stream.into_split();
+    let mut out = serde_json::to_string(request)?;
+    out.push('\n');
+    write.write_all(out.as_bytes()).await?;
+    let mut line = String::new();
+    BufReader::new(read)
+        .read_line(&mut line)
+        .await
+        .context("Failed reading daemon response.")?;
+    serde_json::from_str(&line)
+        .context("Invalid daemon response.")
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use sqlx::migrate;
+
+    use super::{Request, Response, run, send};
+    use crate::{push::PushConfig, store::setup_db};
+
+    #[tokio::test]
+    async fn test_daemon_round_trip() {
+        let store = setup_db("sqlite://:memory:").await.unwrap();
+        migrate!().run(&store.pool).await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fh.sock");
+        assert!(
+            send(
+                &path,
+                &Request::Show {
+                    date: Utc::now().date_naive()
+                }
+            )
+            .await
+            .unwrap()
+            .is_none()
+        );
+        let server_path = path.clone();
+        tokio::spawn(async move { run(store, &server_path, None, PushConfig::default()).await });
+        while !path.exists() {
+            tokio::task::yield_now().await;
+        }
+        let date = Utc::now().date_naive();
+        let new = Request::New {
+            date,
+            body: String::from("milk"),
+            duplicate_since: Some(date),
+        };
+        let Some(Response::Notes(notes)) = send(&path, &new).await.unwrap() else {
+            panic!("Expected notes.");
+        };
+        assert_eq!(notes[0].0, date);
+        assert_eq!(notes[0].1.body, "milk");
+        assert!(matches!(
+            send(&path, &new).await.unwrap(),
+            Some(Response::Fallback)
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::NaiveDate;
+
+/// Process exit codes, so scripts can branch on fh's state without parsing output.
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;
+/// `fh check --quiet` found no notes for today.
+pub const EXIT_NO_NOTES: i32 = 2;
+pub const EXIT_PARSE: i32 = 3;
+pub const EXIT_NOT_FOUND: i32 = 4;
+
+/// A command ending with a non zero exit code and nothing else to report, like
+/// `fh check --quiet` finding no notes. The CLI exits with the code without printing an error.
+#[derive(Debug, thiserror::Error)]
+#[error("Exited with {0}.")]
+pub struct Exit(pub i32);
+
+/// Errors from the store and note parsing, for callers that want to tell them apart.
+#[derive(Debug, thiserror::Error)]
+pub enum FhError {
+    #[error("No notes found for day {0}.")]
+    DayNotFound(NaiveDate),
+    #[error("No note with id {id}.")]
+    NoteNotFound { id: u32 },
+    #[error("{reason} {line}")]
+    ParseError { line: String, reason: String },
+    /// A request the store refuses, e.g. merging a note into itself.
+    #[error("{0}")]
+    Invalid(String),
+    /// A database error with what was being done when it happened.
+    #[error("{context}")]
+    Storage {
+        context: String,
+        #[source]
+        source: sqlx::Error,
+    },
+    #[error("{context}")]
+    Migrate {
+        context: String,
+        #[source]
+        source: sqlx::migrate::MigrateError,
+    },
+}
+impl From<sqlx::Error> for FhError {
+    fn from(source: sqlx::Error) -> Self {
+        FhError::Storage {
+            context: String::from("Database error."),
+            source,
+        }
+    }
+}
+impl FhError {
+    pub fn parse(line: impl Into<String>, reason: impl Into<String>) -> FhError {
+        FhError::ParseError {
+            line: line.into(),
+            reason: reason.into(),
+        }
+    }
+    /// Process exit code the CLI uses for the error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            FhError::ParseError { .. } => EXIT_PARSE,
+            FhError::DayNotFound(_) | FhError::NoteNotFound { .. } => EXIT_NOT_FOUND,
+            _ => EXIT_ERROR,
+        }
+    }
+}
+
+pub type Result<T, E = FhError> = std::result::Result<T, E>;
+
+/// Attach what was being done to a database error, like `anyhow::Context`.
+pub trait StorageContext<T> {
+    fn storage(self, context: impl Into<String>) -> Result<T>;
+}
+impl<T> StorageContext<T> for std::result::Result<T, sqlx::Error> {
+    fn storage(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|source| FhError::Storage {
+            context: context.into(),
+            source,
+        })
+    }
+}
+impl<T> StorageContext<T> for std::result::Result<T, sqlx::migrate::MigrateError> {
+    fn storage(self, context: impl Into<String>) -> Result<T> {
+        self.map_err(|source| FhError::Migrate {
+            context: context.into(),
+            source,
+        })
+    }
+}
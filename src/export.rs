@@ -0,0 +1,227 @@
+use chrono::NaiveDate;
+
+use crate::notes::{DayNotes, Status};
+use crate::report::escape_html;
+
+/// Stylesheet embedded in printable exports, one day per block without page breaks inside.
+const PRINT_CSS: &str =
+    "body { font-family: Georgia, serif; max-width: 44em; margin: 2em auto; color: #111; }
+h1 { font-size: 1.6em; border-bottom: 1px solid #999; }
+h2 { font-size: 1.2em; margin-bottom: 0.3em; }
+h3 { font-size: 1em; font-style: italic; margin: 0.6em 0 0.2em; }
+ul { list-style: none; padding-left: 0.5em; margin: 0.2em 0; }
+li .marker { font-family: monospace; margin-right: 0.5em; }
+li.done, li.cancelled { color: #666; }
+li.cancelled { text-decoration: line-through; }
+.day { break-inside: avoid; page-break-inside: avoid; margin-bottom: 1.5em; }
+.text { white-space: pre-wrap; margin: 0.4em 0 0; }
+@page { margin: 2cm; }
+@media print { body { margin: 0; max-width: none; } }";
+
+/// Days as markdown in the editor buffer format, empty days are skipped.
+pub fn days_markdown(days: &[DayNotes]) -> String {
+    days.iter()
+        .filter(|d| !d.is_empty())
+        .map(|d| format!("{}\n", d.pretty_md()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Days as html, empty days are skipped.
+/// A printable export is a standalone document with its stylesheet inline, otherwise only the body is rendered.
+pub fn days_html(days: &[DayNotes], start: NaiveDate, end: NaiveDate, printable: bool) -> String {
+    let mut out = html_start(start, end, printable);
+    for day in days.iter().filter(|d| !d.is_empty()) {
+        out.push_str(&day_html(day));
+    }
+    out.push_str(html_end(printable));
+    out
+}
+
+/// Html before the first day of [`days_html`], for writing days as they're read.
+pub fn html_start(start: NaiveDate, end: NaiveDate, printable: bool) -> String {
+    let title = format!("Notes from {} to {}", start, end);
+    let heading = format!("<h1>{}</h1>\n", title);
+    if !printable {
+        return heading;
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}",
+        title, PRINT_CSS, heading
+    )
+}
+
+/// Html after the last day of [`days_html`].
+pub fn html_end(printable: bool) -> &'static str {
+    if printable { "</body>\n</html>\n" } else { "" }
+}
+
+pub fn day_html(day: &DayNotes) -> String {
+    let mut out = format!(
+        "<section class=\"day\">\n<h2><time>{}</time> {}</h2>\n",
+        day.date,
+        day.date.format("%A")
+    );
+    for (section, notes) in day.sections() {
+        if notes.is_empty() {
+            continue;
+        }
+        if let Some(section) = section {
+            out.push_str(&format!("<h3>{}</h3>\n", escape_html(section)));
+        }
+        out.push_str("<ul>\n");
+        for note in notes {
+            out.push_str(&format!(
+                "<li class=\"{}\"><span class=\"marker\">[{}]</span>{}</li>\n",
+                note.status.as_str(),
+                note_marker(note.status),
+                escape_html(note.display_body())
+            ));
+        }
+        out.push_str("</ul>\n");
+    }
+    if !day.day_text.trim().is_empty() {
+        out.push_str(&format!(
+            "<p class=\"text\">{}</p>\n",
+            escape_html(day.day_text.trim())
+        ));
+    }
+    out.push_str("</section>\n");
+    out
+}
+
+/// Days as an Atom feed, one entry per day that isn't empty, in the order given.
+/// Entry content is the day's notes and text as escaped html.
+pub fn days_atom(days: &[DayNotes], base_url: &str) -> String {
+    let timestamp = |date: NaiveDate| format!("{}T00:00:00Z", date);
+    let days = days.iter().filter(|d| !d.is_empty()).collect::<Vec<_>>();
+    let updated = days
+        .iter()
+        .map(|d| d.date)
+        .max()
+        .map(timestamp)
+        .unwrap_or(String::from("1970-01-01T00:00:00Z"));
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n<title>fh journal</title>\n<id>{0}/feed.atom</id>\n<link rel=\"self\" href=\"{0}/feed.atom\"/>\n<updated>{1}</updated>\n",
+        escape_html(base_url),
+        updated
+    );
+    for day in days {
+        let mut content = String::new();
+        if !day.notes.is_empty() {
+            content.push_str("<ul>\n");
+            for note in &day.notes {
+                content.push_str(&format!("<li>{}</li>\n", escape_html(note.display_body())));
+            }
+            content.push_str("</ul>\n");
+        }
+        if !day.day_text.trim().is_empty() {
+            content.push_str(&format!(
+                "<p style=\"white-space: pre-wrap\">{}</p>\n",
+                escape_html(day.day_text.trim())
+            ));
+        }
+        out.push_str(&format!(
+            "<entry>\n<title>{0} {1}</title>\n<id>urn:fh:day:{0}</id>\n<updated>{2}</updated>\n<content type=\"html\">{3}</content>\n</entry>\n",
+            day.date,
+            day.date.format("%A"),
+            timestamp(day.date),
+            escape_html(&content)
+        ));
+    }
+    out.push_str("</feed>\n");
+    out
+}
+
+/// Status marker as it prints, a space collapses in html.
+fn note_marker(status: Status) -> String {
+    match status {
+        Status::Todo => String::from("&nbsp;"),
+        s => escape_html(&s.marker().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{days_atom, days_html, days_markdown};
+    use crate::notes::{DayNotes, Note, Status};
+
+    fn day(date: NaiveDate, notes: Vec<Note>, day_text: &str) -> DayNotes {
+        DayNotes {
+            note_count: notes.len() as u32,
+            notes,
+            date,
+            day_text: String::from(day_text),
+        }
+    }
+    #[test]
+    fn test_days_html() {
+        let start = NaiveDate::from_ymd_opt(2025, 10, 13).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 10, 14).unwrap();
+        let note = Note {
+            id: 1,
+            body: String::from("fix <br> tag"),
+            status: Status::Done,
+            section: Some(String::from("Work")),
+            due: None,
+            focus_rank: None,
+            ..Default::default()
+        };
+        let days = vec![day(start, vec![note], "Quiet day."), day(end, vec![], "")];
+        let html = days_html(&days, start, end, false);
+        assert!(!html.contains("<style>"));
+        assert!(html.contains("<h3>Work</h3>"), "{}", html);
+        assert!(
+            html.contains(
+                "<li class=\"done\"><span class=\"marker\">[x]</span>fix &lt;br&gt; tag</li>"
+            ),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<p class=\"text\">Quiet day.</p>"),
+            "{}",
+            html
+        );
+        assert_eq!(html.matches("<section").count(), 1);
+        let printable = days_html(&days, start, end, true);
+        assert!(printable.starts_with("<!DOCTYPE html>"));
+        assert!(printable.contains("page-break-inside: avoid"));
+    }
+    #[test]
+    fn test_days_markdown_skips_empty() {
+        let start = NaiveDate::from_ymd_opt(2025, 10, 13).unwrap();
+        let days = vec![day(start, vec![], ""), day(start, vec![], "Text.\n")];
+        assert_eq!(days_markdown(&days).matches("# Day:").count(), 1);
+    }
+    #[test]
+    fn test_days_atom() {
+        let date = NaiveDate::from_ymd_opt(2025, 10, 13).unwrap();
+        let note = Note {
+            id: 1,
+            body: String::from("ship <v2>"),
+            status: Status::Done,
+            section: None,
+            due: None,
+            focus_rank: None,
+            ..Default::default()
+        };
+        let days = vec![day(date, vec![note], "Good day.\n"), day(date, vec![], "")];
+        let atom = days_atom(&days, "https://fh.example.com");
+        assert_eq!(atom.matches("<entry>").count(), 1);
+        assert!(atom.contains("<updated>2025-10-13T00:00:00Z</updated>"));
+        assert!(
+            atom.contains("<title>2025-10-13 Monday</title>"),
+            "{}",
+            atom
+        );
+        assert!(
+            atom.contains("&lt;li&gt;ship &amp;lt;v2&amp;gt;&lt;/li&gt;"),
+            "{}",
+            atom
+        );
+        assert!(atom.contains("<id>https://fh.example.com/feed.atom</id>"));
+    }
+}
@@ -0,0 +1,119 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+use sqlx::{QueryBuilder, Sqlite};
+
+use crate::notes::{OPEN_STATUS_SQL, StatusMatch};
+
+/// Note selection parsed from space separated `key=value` pairs,
+/// e.g. `tag=errands status=open before=2025-01-01`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NoteFilter {
+    pub status: Option<StatusMatch>,
+    pub tag: Option<String>,
+    pub section: Option<String>,
+    /// Notes on days strictly before this date.
+    pub before: Option<NaiveDate>,
+    /// Notes on days strictly after this date.
+    pub after: Option<NaiveDate>,
+}
+impl FromStr for NoteFilter {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut filter = NoteFilter::default();
+        for pair in s.split_whitespace() {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or(anyhow!("Filter {} should look like key=value.", pair))?;
+            match key {
+                "status" => filter.status = Some(StatusMatch::from_str(value)?),
+                "tag" => filter.tag = Some(String::from(value)),
+                "section" => filter.section = Some(String::from(value)),
+                "before" => {
+                    filter.before = Some(
+                        NaiveDate::from_str(value).context(format!("Invalid date {}.", value))?,
+                    )
+                }
+                "after" => {
+                    filter.after = Some(
+                        NaiveDate::from_str(value).context(format!("Invalid date {}.", value))?,
+                    )
+                }
+                _ => return Err(anyhow!("Unknown filter key {}.", key)),
+            }
+        }
+        Ok(filter)
+    }
+}
+impl NoteFilter {
+    /// Push the conditions for the filter, expects `note as n` joined to `day as d` in the query.
+    pub fn push_conditions(&self, qb: &mut QueryBuilder<'static, Sqlite>) {
+        qb.push(" WHERE n.deleted_at IS NULL");
+        if let Some(status) = self.status {
+            push_status(qb, status);
+        }
+        if let Some(section) = &self.section {
+            qb.push(" AND n.section = ").push_bind(section.clone());
+        }
+        if let Some(before) = self.before {
+            qb.push(" AND d.date < ").push_bind(before);
+        }
+        if let Some(after) = self.after {
+            qb.push(" AND d.date > ").push_bind(after);
+        }
+        if let Some(tag) = &self.tag {
+            qb.push(" AND EXISTS (SELECT 1 FROM note_tag as t WHERE t.note_id = n.id AND t.tag = ")
+                .push_bind(tag.clone())
+                .push(")");
+        }
+    }
+    /// Push a subquery selecting the ids of matching notes in `workspace`.
+    pub fn push_ids(&self, qb: &mut QueryBuilder<'static, Sqlite>, workspace: &str) {
+        qb.push("(SELECT n.id FROM note as n INNER JOIN day as d ON n.day_key = d.id");
+        self.push_conditions(qb);
+        qb.push(" AND d.workspace = ")
+            .push_bind(workspace.to_string())
+            .push(")");
+    }
+}
+
+/// Push the condition for a status match on `note as n`.
+pub fn push_status(qb: &mut QueryBuilder<'static, Sqlite>, status: StatusMatch) {
+    match status {
+        StatusMatch::Open => {
+            qb.push(" AND ").push(OPEN_STATUS_SQL);
+        }
+        StatusMatch::Is(status) => {
+            qb.push(" AND n.status = ").push_bind(status.as_str());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::NaiveDate;
+
+    use super::NoteFilter;
+    use crate::notes::{Status, StatusMatch};
+
+    #[test]
+    fn test_parse_filter() {
+        let filter = NoteFilter::from_str("tag=errands status=open before=2025-01-01").unwrap();
+        assert_eq!(filter.tag.as_deref(), Some("errands"));
+        assert_eq!(filter.status, Some(StatusMatch::Open));
+        assert_eq!(filter.before, NaiveDate::from_ymd_opt(2025, 1, 1));
+        let filter = NoteFilter::from_str("status=blocked").unwrap();
+        assert_eq!(filter.status, Some(StatusMatch::Is(Status::Blocked)));
+        assert_eq!(NoteFilter::from_str("").unwrap(), NoteFilter::default());
+    }
+    #[test]
+    fn test_parse_filter_fail() {
+        let table = vec!["tag", "status=maybe", "before=yesterday", "colour=red"];
+        for input in table {
+            assert!(NoteFilter::from_str(input).is_err(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+
+/// Note metadata key holding the hash of a captured commit.
+pub const COMMIT_KEY: &str = "commit";
+
+/// A commit found by `git-capture`.
+#[derive(Debug, PartialEq)]
+pub struct Commit {
+    pub hash: String,
+    pub subject: String,
+    /// Name of the repository's directory.
+    pub repo: String,
+}
+impl Commit {
+    /// Body of the completed note for the commit, e.g. `Fix login (fh 1a2b3c4)`.
+    pub fn note_body(&self) -> String {
+        let short = &self.hash[..self.hash.len().min(7)];
+        format!("{} ({} {})", self.subject, self.repo, short)
+    }
+}
+
+/// Repositories named by `patterns`, a leading `~/` is the home directory and a trailing `/*`
+/// stands for every repository directly inside the directory.
+pub fn expand_repos(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut repos = Vec::new();
+    for pattern in patterns {
+        let path = match pattern.strip_prefix("~/") {
+            Some(rest) => PathBuf::from(std::env::var("HOME")?).join(rest),
+            None => PathBuf::from(pattern),
+        };
+        if path.file_name().is_some_and(|n| n == "*") {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let mut found = std::fs::read_dir(dir)
+                .context(format!("Failed reading {}.", dir.display()))?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.join(".git").exists())
+                .collect::<Vec<_>>();
+            found.sort();
+            repos.extend(found);
+        } else {
+            repos.push(path);
+        }
+    }
+    Ok(repos)
+}
+
+/// Commits on any branch of `repo` authored on or after `since` by its configured `user.email`.
+pub fn commits_since(repo: &Path, since: NaiveDate) -> Result<Vec<Commit>> {
+    let email = git(repo, &["config", "user.email"])?;
+    let log = git(
+        repo,
+        &[
+            "log",
+            "--all",
+            "--no-merges",
+            &format!("--since={} 00:00", since),
+            &format!("--author={}", email.trim()),
+            "--format=%H%x09%s",
+        ],
+    )?;
+    let name = repo
+        .canonicalize()?
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(parse_log(&name, &log))
+}
+
+/// Read `git log --format=%H%x09%s` output, oldest commit first.
+pub fn parse_log(repo: &str, log: &str) -> Vec<Commit> {
+    let mut commits = log
+        .lines()
+        .filter_map(|l| l.split_once('\t'))
+        .map(|(hash, subject)| Commit {
+            hash: hash.to_string(),
+            subject: subject.to_string(),
+            repo: repo.to_string(),
+        })
+        .collect::<Vec<_>>();
+    commits.reverse();
+    commits
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .context("Failed running git.")?;
+    if !out.status.success() {
+        return Err(anyhow!(
+            "git {} failed in {}: {}",
+            args[0],
+            repo.display(),
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use chrono::{Days, Local};
+
+    use super::{commits_since, expand_repos};
+
+    #[test]
+    fn test_commits_since() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("fh");
+        std::fs::create_dir(&repo).unwrap();
+        std::fs::create_dir(dir.path().join("not-a-repo")).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "me@example.com"]);
+        git(&["config", "user.name", "Me"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "First"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "Second"]);
+        git(&[
+            "commit",
+            "-q",
+            "--allow-empty",
+            "--author",
+            "Other <other@example.com>",
+            "-m",
+            "Not mine",
+        ]);
+        let pattern = format!("{}/*", dir.path().display());
+        let repos = expand_repos(&[pattern]).unwrap();
+        assert_eq!(repos, vec![repo.clone()]);
+        let today = Local::now().date_naive();
+        let commits = commits_since(&repo, today).unwrap();
+        let subjects = commits
+            .iter()
+            .map(|c| c.subject.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(subjects, ["First", "Second"]);
+        assert!(commits[0].note_body().starts_with("First (fh "));
+        let tomorrow = today + Days::new(1);
+        assert!(commits_since(&repo, tomorrow).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,467 @@
+//! Two-way sync of open notes with a Google Tasks list, behind the `gtasks` feature.
+//! `fh gtasks login` stores a refresh token in the config directory, `fh gtasks sync` then
+//! pushes open notes as tasks and pulls completions, renames and new tasks back.
+use serde::Deserialize;
+
+/// `source` of the remote links to tasks.
+pub const SOURCE: &str = "gtasks";
+/// File in the config directory holding the OAuth refresh token.
+pub const TOKEN_NAME: &str = "gtasks_token.json";
+
+/// The `[gtasks]` table of the config, an OAuth client of type desktop app.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct GtasksConfig {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Task list id, the default list when unset.
+    pub list: Option<String>,
+}
+
+#[cfg(feature = "gtasks")]
+pub use client::{login, sync};
+
+#[cfg(not(feature = "gtasks"))]
+pub async fn login(_config: &GtasksConfig, _token_path: &std::path::Path) -> anyhow::Result<()> {
+    Err(missing())
+}
+#[cfg(not(feature = "gtasks"))]
+pub async fn sync(
+    _store: &crate::store::NoteStore,
+    _config: &GtasksConfig,
+    _token_path: &std::path::Path,
+) -> anyhow::Result<Vec<String>> {
+    Err(missing())
+}
+#[cfg(not(feature = "gtasks"))]
+fn missing() -> anyhow::Error {
+    anyhow::anyhow!("fh was built without Google Tasks, rebuild it with --features gtasks.")
+}
+
+/// A task as the Tasks API sends it.
+#[cfg(feature = "gtasks")]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Task {
+    pub id: String,
+    pub title: String,
+    /// `needsAction` or `completed`.
+    pub status: String,
+    pub updated: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub deleted: bool,
+}
+#[cfg(feature = "gtasks")]
+impl Task {
+    fn is_open(&self) -> bool {
+        self.status == "needsAction"
+    }
+}
+
+/// A change `sync` makes on one side.
+#[cfg(feature = "gtasks")]
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    CreateTask { note_id: u32, title: String },
+    CompleteTask { task_id: String },
+    RenameTask { task_id: String, title: String },
+    CompleteNote { note_id: u32 },
+    RenameNote { note_id: u32, body: String },
+    CreateNote { task_id: String, title: String },
+}
+
+/// What to change so `notes`, the open and linked ones, match the list's `tasks`.
+/// Unlinked open notes become tasks and unlinked open tasks become notes, a side closing an
+/// item closes the other and the later edit of a title wins. Deleted tasks are left alone.
+#[cfg(feature = "gtasks")]
+pub fn plan(
+    notes: &[crate::notes::Note],
+    links: &std::collections::HashMap<u32, String>,
+    tasks: &[Task],
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for note in notes
+        .iter()
+        .filter(|n| !crate::secret::is_encrypted(&n.body))
+    {
+        let Some(task_id) = links.get(&note.id) else {
+            if note.status.is_open() {
+                changes.push(Change::CreateTask {
+                    note_id: note.id,
+                    title: note.body.clone(),
+                });
+            }
+            continue;
+        };
+        let Some(task) = tasks.iter().find(|t| &t.id == task_id && !t.deleted) else {
+            continue;
+        };
+        match (note.status.is_open(), task.is_open()) {
+            (false, true) => changes.push(Change::CompleteTask {
+                task_id: task.id.clone(),
+            }),
+            (true, false) => changes.push(Change::CompleteNote { note_id: note.id }),
+            _ => {}
+        }
+        if task.title != note.body {
+            let note_changed = note.updated_at.or(note.created_at).unwrap_or_default();
+            if task.updated > note_changed {
+                changes.push(Change::RenameNote {
+                    note_id: note.id,
+                    body: task.title.clone(),
+                });
+            } else {
+                changes.push(Change::RenameTask {
+                    task_id: task.id.clone(),
+                    title: note.body.clone(),
+                });
+            }
+        }
+    }
+    let linked = links.values().collect::<std::collections::HashSet<_>>();
+    for task in tasks.iter().filter(|t| t.is_open() && !t.deleted) {
+        if !linked.contains(&task.id) && !task.title.trim().is_empty() {
+            changes.push(Change::CreateNote {
+                task_id: task.id.clone(),
+                title: task.title.clone(),
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(feature = "gtasks")]
+mod client {
+    use std::{collections::HashMap, os::unix::fs::PermissionsExt, path::Path};
+
+    use anyhow::{Context, Result, anyhow};
+    use chrono::Local;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::{Change, GtasksConfig, SOURCE, Task, plan};
+    use crate::{
+        filter::NoteFilter,
+        notes::{NewNote, StatusMatch},
+        store::NoteStore,
+    };
+
+    const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+    const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+    const SCOPE: &str = "https://www.googleapis.com/auth/tasks";
+    const API_URL: &str = "https://tasks.googleapis.com/tasks/v1";
+
+    #[derive(Serialize, Deserialize)]
+    struct StoredToken {
+        refresh_token: String,
+    }
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        access_token: String,
+        refresh_token: Option<String>,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TaskPage {
+        #[serde(default)]
+        items: Vec<Task>,
+        next_page_token: Option<String>,
+    }
+
+    fn client_credentials(config: &GtasksConfig) -> Result<(&str, &str)> {
+        match (&config.client_id, &config.client_secret) {
+            (Some(id), Some(secret)) => Ok((id, secret)),
+            _ => Err(anyhow!(
+                "Set client_id and client_secret in the [gtasks] config."
+            )),
+        }
+    }
+
+    /// Authorise fh in the browser and store the refresh token at `token_path`.
+    pub async fn login(config: &GtasksConfig, token_path: &Path) -> Result<()> {
+        let (client_id, client_secret) = client_credentials(config)?;
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let redirect = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+        let url = reqwest::Url::parse_with_params(
+            AUTH_URL,
+            [
+                ("client_id", client_id),
+                ("redirect_uri", &redirect),
+                ("response_type", "code"),
+                ("scope", SCOPE),
+                ("access_type", "offline"),
+                ("prompt", "consent"),
+            ],
+        )?;
+        println!("Open this url to let fh use your tasks:\n{}", url);
+        let (mut socket, _) = listener.accept().await?;
+        let mut request = vec![0; 8192];
+        let read = socket.read(&mut request).await?;
+        let request = String::from_utf8_lossy(&request[..read]);
+        let target = request
+            .split_whitespace()
+            .nth(1)
+            .ok_or(anyhow!("Invalid redirect request."))?;
+        let code = reqwest::Url::parse(&format!("{}{}", redirect, target))?
+            .query_pairs()
+            .find(|(k, _)| k == "code")
+            .map(|(_, v)| v.into_owned())
+            .ok_or(anyhow!("Authorisation was refused."))?;
+        socket
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nfh is authorised, close this tab.")
+            .await?;
+        let token: TokenResponse = reqwest::Client::new()
+            .post(TOKEN_URL)
+            .form(&[
+                ("code", code.as_str()),
+                ("client_id", client_id),
+                ("client_secret", client_secret),
+                ("redirect_uri", &redirect),
+                ("grant_type", "authorization_code"),
+            ])
+            .send()
+            .await?
+            .error_for_status()
+            .context("Failed exchanging the authorisation code.")?
+            .json()
+            .await?;
+        let refresh_token = token
+            .refresh_token
+            .ok_or(anyhow!("Google sent no refresh token."))?;
+        std::fs::write(
+            token_path,
+            serde_json::to_string(&StoredToken { refresh_token })?,
+        )?;
+        std::fs::set_permissions(token_path, std::fs::Permissions::from_mode(0o600))?;
+        println!("Saved the token to {}.", token_path.display());
+        Ok(())
+    }
+
+    struct Client {
+        http: reqwest::Client,
+        token: String,
+        list_url: String,
+    }
+    impl Client {
+        async fn new(config: &GtasksConfig, token_path: &Path) -> Result<Client> {
+            let (client_id, client_secret) = client_credentials(config)?;
+            let stored: StoredToken = serde_json::from_str(
+                &std::fs::read_to_string(token_path)
+                    .context("No Google Tasks token, run fh gtasks login first.")?,
+            )?;
+            let http = reqwest::Client::new();
+            let token: TokenResponse = http
+                .post(TOKEN_URL)
+                .form(&[
+                    ("refresh_token", stored.refresh_token.as_str()),
+                    ("client_id", client_id),
+                    ("client_secret", client_secret),
+                    ("grant_type", "refresh_token"),
+                ])
+                .send()
+                .await?
+                .error_for_status()
+                .context("Failed refreshing the token, run fh gtasks login again.")?
+                .json()
+                .await?;
+            let list = config.list.as_deref().unwrap_or("@default");
+            Ok(Client {
+                http,
+                token: token.access_token,
+                list_url: format!("{}/lists/{}/tasks", API_URL, list),
+            })
+        }
+        async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+            let response = request.bearer_auth(&self.token).send().await?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(anyhow!("Google Tasks answered {}: {}", status, body));
+            }
+            Ok(response)
+        }
+        async fn tasks(&self) -> Result<Vec<Task>> {
+            let mut tasks = Vec::new();
+            let mut page_token: Option<String> = None;
+            loop {
+                let mut query = vec![
+                    ("showCompleted", "true"),
+                    ("showHidden", "true"),
+                    ("showDeleted", "true"),
+                    ("maxResults", "100"),
+                ];
+                if let Some(token) = &page_token {
+                    query.push(("pageToken", token));
+                }
+                let request = self.http.get(&self.list_url).query(&query);
+                let page: TaskPage = self.send(request).await?.json().await?;
+                tasks.extend(page.items);
+                match page.next_page_token {
+                    Some(token) => page_token = Some(token),
+                    None => return Ok(tasks),
+                }
+            }
+        }
+        async fn insert(&self, title: &str) -> Result<Task> {
+            let request = self
+                .http
+                .post(&self.list_url)
+                .json(&json!({ "title": title }));
+            Ok(self.send(request).await?.json().await?)
+        }
+        async fn patch(&self, task_id: &str, fields: serde_json::Value) -> Result<()> {
+            let request = self
+                .http
+                .patch(format!("{}/{}", self.list_url, task_id))
+                .json(&fields);
+            self.send(request).await.map(|_| ())
+        }
+    }
+
+    /// Sync the open notes with the task list, returning a line for each change made.
+    pub async fn sync(
+        store: &NoteStore,
+        config: &GtasksConfig,
+        token_path: &Path,
+    ) -> Result<Vec<String>> {
+        let client = Client::new(config, token_path).await?;
+        let tasks = client.tasks().await?;
+        let links = store.remote_links(SOURCE).await?;
+        let open = NoteFilter {
+            status: Some(StatusMatch::Open),
+            ..Default::default()
+        };
+        let mut notes = store
+            .list_notes(&open, None)
+            .await?
+            .into_iter()
+            .map(|(_, n)| n)
+            .collect::<Vec<_>>();
+        for id in links.keys() {
+            if !notes.iter().any(|n| n.id == *id)
+                && let Ok(note) = store.get_note(*id).await
+            {
+                notes.push(note);
+            }
+        }
+        let titles = tasks
+            .iter()
+            .map(|t| (t.id.as_str(), t.title.as_str()))
+            .collect::<HashMap<_, _>>();
+        let mut done = Vec::new();
+        for change in plan(&notes, &links, &tasks) {
+            let line = match change {
+                Change::CreateTask { note_id, title } => {
+                    let task = client.insert(&title).await?;
+                    store.link_note(note_id, SOURCE, &task.id).await?;
+                    format!("Added task {}", title)
+                }
+                Change::CompleteTask { task_id } => {
+                    client
+                        .patch(&task_id, json!({ "status": "completed" }))
+                        .await?;
+                    format!("Completed task {}", titles[task_id.as_str()])
+                }
+                Change::RenameTask { task_id, title } => {
+                    client.patch(&task_id, json!({ "title": title })).await?;
+                    format!("Renamed task to {}", title)
+                }
+                Change::CompleteNote { note_id } => {
+                    let note = store
+                        .set_status(note_id, crate::notes::Status::Done)
+                        .await?;
+                    format!("Completed note {}", note.body)
+                }
+                Change::RenameNote { note_id, body } => {
+                    let mut note = store.get_note(note_id).await?;
+                    note.body = body;
+                    let note = store.update_note(&note).await?;
+                    format!("Renamed note {} to {}", note.id, note.body)
+                }
+                Change::CreateNote { task_id, title } => {
+                    let today = Local::now().date_naive();
+                    let note = store.insert_note_on(today, NewNote::new(title)).await?;
+                    store.link_note(note.id, SOURCE, &task_id).await?;
+                    format!("Added note {}", note.body)
+                }
+            };
+            done.push(line);
+        }
+        Ok(done)
+    }
+}
+
+#[cfg(all(test, feature = "gtasks"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{Change, Task, plan};
+    use crate::notes::{Note, Status};
+
+    #[test]
+    fn test_plan() {
+        let at = |hour| Utc.with_ymd_and_hms(2025, 10, 12, hour, 0, 0).unwrap();
+        let note = |id, body: &str, status| Note {
+            id,
+            body: body.to_string(),
+            status,
+            created_at: Some(at(9)),
+            ..Default::default()
+        };
+        let task = |id: &str, title: &str, status: &str| Task {
+            id: id.to_string(),
+            title: title.to_string(),
+            status: status.to_string(),
+            updated: at(10),
+            deleted: false,
+        };
+        let notes = [
+            note(1, "new note", Status::Todo),
+            note(2, "closed here", Status::Done),
+            note(3, "closed there", Status::Todo),
+            note(4, "old title", Status::Todo),
+            note(5, "gone there", Status::Todo),
+        ];
+        let links = HashMap::from([
+            (2, String::from("b")),
+            (3, String::from("c")),
+            (4, String::from("d")),
+            (5, String::from("e")),
+        ]);
+        let mut gone = task("e", "gone there", "needsAction");
+        gone.deleted = true;
+        let tasks = [
+            task("b", "closed here", "needsAction"),
+            task("c", "closed there", "completed"),
+            task("d", "new title", "needsAction"),
+            gone,
+            task("f", "from phone", "needsAction"),
+        ];
+        assert_eq!(
+            plan(&notes, &links, &tasks),
+            vec![
+                Change::CreateTask {
+                    note_id: 1,
+                    title: String::from("new note")
+                },
+                Change::CompleteTask {
+                    task_id: String::from("b")
+                },
+                Change::CompleteNote { note_id: 3 },
+                Change::RenameNote {
+                    note_id: 4,
+                    body: String::from("new title")
+                },
+                Change::CreateNote {
+                    task_id: String::from("f"),
+                    title: String::from("from phone")
+                },
+            ]
+        );
+    }
+}
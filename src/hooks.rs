@@ -0,0 +1,135 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::notes::{DayNotes, Note};
+
+/// The `[hooks]` table of the config, shell commands run once a change is saved.
+/// Each gets a JSON object on stdin with the `event` and the `note` or `day` it's about.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct HooksConfig {
+    pub on_note_created: Option<String>,
+    pub on_note_completed: Option<String>,
+    /// Run after `edit` saves a day.
+    pub on_day_saved: Option<String>,
+}
+impl HooksConfig {
+    pub fn note_created(&self, note: &Note) {
+        self.fire(
+            &self.on_note_created,
+            "note_created",
+            json!({ "note": note }),
+        );
+    }
+    pub fn note_completed(&self, note: &Note) {
+        self.fire(
+            &self.on_note_completed,
+            "note_completed",
+            json!({ "note": note }),
+        );
+    }
+    /// Run the hook for a day saved from the editor. Its created and completed notes ran their
+    /// hooks from the store as they were saved.
+    pub fn day_saved(&self, after: &DayNotes) {
+        self.fire(&self.on_day_saved, "day_saved", json!({ "day": after }));
+    }
+    /// Run `command` if set, a failing hook is logged so the saved change still stands.
+    fn fire(&self, command: &Option<String>, event: &str, mut payload: Value) {
+        let Some(command) = command else {
+            return;
+        };
+        payload["event"] = json!(event);
+        if let Err(e) = run(command, &payload) {
+            tracing::warn!("Hook {} failed: {:#}", event, e);
+        }
+    }
+}
+
+fn run(command: &str, payload: &Value) -> Result<()> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .spawn()
+        .context(format!("Failed running {}.", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read its payload closes the pipe early, that's fine.
+        let _ = writeln!(stdin, "{}", payload);
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("{} exited with {}", command, status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::HooksConfig;
+    use crate::{
+        filter::NoteFilter,
+        notes::{DayNotes, NewNote, Status},
+        store::NoteStore,
+    };
+
+    #[tokio::test]
+    async fn test_store_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("log");
+        let append = format!("cat >> {}", log.display());
+        let hooks = HooksConfig {
+            on_note_created: Some(append.clone()),
+            on_note_completed: Some(append.clone()),
+            on_day_saved: Some(append),
+        };
+        let store = NoteStore::open_in_memory()
+            .await
+            .unwrap()
+            .with_hooks(hooks.clone());
+        let date = NaiveDate::from_ymd_opt(2025, 10, 12).unwrap();
+        let first = store
+            .insert_note_on(date, NewNote::new("first"))
+            .await
+            .unwrap();
+        let second = store
+            .insert_note_on(date, NewNote::new("second"))
+            .await
+            .unwrap();
+        store.set_status(first.id, Status::Done).await.unwrap();
+        // Saving a done note again isn't a completion.
+        store.set_status(first.id, Status::Done).await.unwrap();
+        store.bulk_complete(&NoteFilter::default()).await.unwrap();
+        hooks.day_saved(&DayNotes {
+            notes: vec![],
+            note_count: 0,
+            date,
+            day_text: String::new(),
+        });
+        let events = std::fs::read_to_string(&log)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .map(|v| {
+                let id = v["note"]["id"].as_u64();
+                (v["event"].as_str().unwrap().to_string(), id)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                (String::from("note_created"), Some(first.id as u64)),
+                (String::from("note_created"), Some(second.id as u64)),
+                (String::from("note_completed"), Some(first.id as u64)),
+                (String::from("note_completed"), Some(second.id as u64)),
+                (String::from("day_saved"), None),
+            ]
+        );
+    }
+}
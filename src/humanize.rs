@@ -0,0 +1,52 @@
+use chrono::NaiveDate;
+
+/// How far `date` is from `today` in words, e.g. `today`, `yesterday`, `3 days ago`, `in 2 weeks`.
+/// Days are counted up to two weeks, then weeks, months and years.
+pub fn relative_day(date: NaiveDate, today: NaiveDate) -> String {
+    let days = (today - date).num_days();
+    match days {
+        0 => return String::from("today"),
+        1 => return String::from("yesterday"),
+        -1 => return String::from("tomorrow"),
+        _ => {}
+    }
+    let n = days.abs();
+    let (count, unit) = match n {
+        ..14 => (n, "day"),
+        14..60 => (n / 7, "week"),
+        60..730 => (n / 30, "month"),
+        _ => (n / 365, "year"),
+    };
+    let span = format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" });
+    if days > 0 {
+        format!("{} ago", span)
+    } else {
+        format!("in {}", span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, NaiveDate};
+
+    use super::relative_day;
+
+    #[test]
+    fn test_relative_day() {
+        let today = NaiveDate::from_ymd_opt(2025, 10, 12).unwrap();
+        let table = [
+            (0, "today"),
+            (1, "yesterday"),
+            (3, "3 days ago"),
+            (13, "13 days ago"),
+            (14, "2 weeks ago"),
+            (90, "3 months ago"),
+            (800, "2 years ago"),
+        ];
+        for (days, expected) in table {
+            assert_eq!(relative_day(today - Days::new(days), today), expected);
+        }
+        assert_eq!(relative_day(today + Days::new(1), today), "tomorrow");
+        assert_eq!(relative_day(today + Days::new(21), today), "in 3 weeks");
+    }
+}
@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+
+use crate::notes::Status;
+
+/// Which CSV columns hold a note's fields, parsed from comma separated `field=column` pairs
+/// counting columns from 1, e.g. `body=2,date=1,completed=3`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMap {
+    pub body: usize,
+    pub date: usize,
+    pub completed: Option<usize>,
+    pub section: Option<usize>,
+}
+impl FromStr for ColumnMap {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let (mut body, mut date, mut completed, mut section) = (None, None, None, None);
+        for pair in s.split(',') {
+            let (key, value) = pair.trim().split_once('=').ok_or(anyhow!(
+                "Column mapping {} should look like field=column.",
+                pair
+            ))?;
+            let column = usize::from_str(value)
+                .ok()
+                .filter(|c| *c > 0)
+                .ok_or(anyhow!("Invalid column {}, columns count from 1.", value))?;
+            match key {
+                "body" => body = Some(column - 1),
+                "date" => date = Some(column - 1),
+                "completed" => completed = Some(column - 1),
+                "section" => section = Some(column - 1),
+                _ => return Err(anyhow!("Unknown column mapping field {}.", key)),
+            }
+        }
+        Ok(ColumnMap {
+            body: body.ok_or(anyhow!("Column mapping needs a body column."))?,
+            date: date.ok_or(anyhow!("Column mapping needs a date column."))?,
+            completed,
+            section,
+        })
+    }
+}
+
+/// A note read from one CSV record.
+#[derive(Debug, PartialEq)]
+pub struct ImportRow {
+    pub date: NaiveDate,
+    pub body: String,
+    pub status: Status,
+    pub section: Option<String>,
+}
+impl ColumnMap {
+    /// Read a note from a record, `line` is only used in errors.
+    pub fn row(&self, record: &[String], line: usize) -> Result<ImportRow> {
+        let field = |column: usize| {
+            record.get(column).map(|v| v.trim()).ok_or(anyhow!(
+                "Line {} has no column {}.",
+                line,
+                column + 1
+            ))
+        };
+        let date = field(self.date)?;
+        let date = NaiveDate::from_str(date)
+            .context(format!("Invalid date {} on line {}.", date, line))?;
+        let body = field(self.body)?;
+        if body.is_empty() {
+            return Err(anyhow!("Line {} has an empty body.", line));
+        }
+        let status = match self.completed {
+            Some(column) if is_truthy(field(column)?) => Status::Done,
+            _ => Status::Todo,
+        };
+        let section = match self.section {
+            Some(column) => Some(field(column)?)
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+            None => None,
+        };
+        Ok(ImportRow {
+            date,
+            body: String::from(body),
+            status,
+            section,
+        })
+    }
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "1" | "true" | "yes" | "y" | "x" | "done"
+    )
+}
+
+/// Split CSV text into records, handling quoted fields with `""` escapes and newlines.
+pub fn parse_csv(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow!("Unterminated quoted field."));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    // Blank lines are not records.
+    records.retain(|r| !(r.len() == 1 && r[0].is_empty()));
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::NaiveDate;
+
+    use super::{ColumnMap, parse_csv};
+    use crate::notes::Status;
+
+    #[test]
+    fn test_parse_csv() {
+        let text = "date,body\n2025-01-02,\"buy milk, eggs\"\n\n2025-01-03,\"say \"\"hi\"\"\nthen leave\"\r\n";
+        let records = parse_csv(text).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1], vec!["2025-01-02", "buy milk, eggs"]);
+        assert_eq!(records[2][1], "say \"hi\"\nthen leave");
+        assert!(parse_csv("\"open").is_err());
+    }
+    #[test]
+    fn test_column_map() {
+        let map = ColumnMap::from_str("body=2,date=1,completed=3").unwrap();
+        assert_eq!(map.body, 1);
+        assert_eq!(map.completed, Some(2));
+        assert!(ColumnMap::from_str("body=2").is_err());
+        assert!(ColumnMap::from_str("body=0,date=1").is_err());
+        let record = ["2025-01-02", "buy milk", "yes"].map(String::from);
+        let row = map.row(&record, 1).unwrap();
+        assert_eq!(row.date, NaiveDate::from_ymd_opt(2025, 1, 2).unwrap());
+        assert_eq!(row.status, Status::Done);
+        assert!(map.row(&record[..2], 1).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+
+/// `source` of the remote links to Jira issues.
+pub const SOURCE: &str = "jira";
+
+/// The `[jira]` table of the config.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct JiraConfig {
+    /// Base url of the site, e.g. `https://example.atlassian.net`.
+    pub url: Option<String>,
+    /// Account email, Jira Cloud pairs it with an API token. Leave it unset to send the token
+    /// as a bearer token to Jira Server.
+    pub email: Option<String>,
+    pub token: Option<String>,
+}
+
+/// The fields of an issue fh reads.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Issue {
+    pub key: String,
+    pub fields: IssueFields,
+}
+impl Issue {
+    /// True once the issue's status is in the done category, whatever the workflow calls it.
+    pub fn is_done(&self) -> bool {
+        self.fields.status.status_category.key == "done"
+    }
+    /// Body of the note linked to the issue, e.g. `PROJ-123 Fix login`.
+    pub fn note_body(&self) -> String {
+        format!("{} {}", self.key, self.fields.summary)
+    }
+}
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct IssueFields {
+    pub summary: String,
+    pub status: IssueStatus,
+}
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct IssueStatus {
+    pub name: String,
+    #[serde(rename = "statusCategory")]
+    pub status_category: StatusCategory,
+}
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct StatusCategory {
+    pub key: String,
+}
+
+/// Reads issues over the Jira REST API.
+pub struct JiraClient {
+    client: reqwest::Client,
+    url: String,
+    email: Option<String>,
+    token: String,
+}
+impl JiraClient {
+    pub fn from_config(config: &JiraConfig) -> Result<JiraClient> {
+        let (Some(url), Some(token)) = (&config.url, &config.token) else {
+            return Err(anyhow!("Set url and token in the [jira] config."));
+        };
+        Ok(JiraClient {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            email: config.email.clone(),
+            token: token.clone(),
+        })
+    }
+    pub async fn issue(&self, key: &str) -> Result<Issue> {
+        let request = self
+            .client
+            .get(format!("{}/rest/api/2/issue/{}", self.url, key))
+            .query(&[("fields", "summary,status")]);
+        let request = match &self.email {
+            Some(email) => request.basic_auth(email, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        };
+        let response = request
+            .send()
+            .await
+            .context(format!("Failed reaching {}.", self.url))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("Jira answered {} for {}.", status, key));
+        }
+        response
+            .json()
+            .await
+            .context(format!("Invalid issue {} from {}.", key, self.url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Issue;
+
+    #[test]
+    fn test_issue() {
+        let issue: Issue = serde_json::from_str(
+            r#"{
+                "id": "10001",
+                "key": "PROJ-123",
+                "fields": {
+                    "summary": "Fix login",
+                    "status": {"name": "Shipped", "statusCategory": {"id": 3, "key": "done"}}
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(issue.note_body(), "PROJ-123 Fix login");
+        assert!(issue.is_done());
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fs::OpenOptions, path::Path, sync::Mutex};
+
+use anyhow::{Context, Result};
+use tracing_subscriber::EnvFilter;
+
+/// Log filter for a `-v` count, `RUST_LOG` overrides it.
+/// `-vv` logs every SQL statement sqlx runs with how long it took.
+pub fn filter(verbose: u8) -> EnvFilter {
+    let directives = match verbose {
+        0 => "warn",
+        1 => "warn,fh=info",
+        2 => "info,fh=debug,sqlx::query=debug",
+        _ => "debug,fh=trace",
+    };
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(directives))
+}
+
+/// Start logging to stderr, or appending to `log_file` when given.
+pub fn init(verbose: u8, log_file: Option<&Path>) -> Result<()> {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter(verbose))
+        .with_writer(std::io::stderr);
+    match log_file {
+        None => builder.init(),
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .context(format!("Failed opening log file {}.", path.display()))?;
+            builder
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .init()
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::filter;
+
+    #[test]
+    fn test_filter() {
+        if std::env::var("RUST_LOG").is_ok() {
+            return;
+        }
+        assert_eq!(filter(0).to_string(), "warn");
+        assert!(filter(2).to_string().contains("sqlx::query=debug"));
+        assert_eq!(filter(9).to_string(), filter(3).to_string());
+    }
+}
@@ -0,0 +1,252 @@
+//! Lua plugins from `~/.fuckhead/plugins`, registering renderers for `show --render` and
+//! note filters for `show --plugin-filter`:
+//!
+//! ```lua
+//! fh.renderer("titles", function(day) return day.date .. ": " .. #day.notes .. " notes" end)
+//! fh.filter("work", function(note) return note.body:find("+work", 1, true) ~= nil end)
+//! ```
+//!
+//! Days and notes are passed as the same tables `list --format json` prints.
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+
+/// Directory in the config directory holding the installed `.lua` files.
+pub const DIR_NAME: &str = "plugins";
+
+/// An installed plugin file and the names it registered.
+#[cfg_attr(not(feature = "lua"), allow(dead_code))]
+#[derive(Debug, PartialEq)]
+pub struct Plugin {
+    pub name: String,
+    pub renderers: Vec<String>,
+    pub filters: Vec<String>,
+}
+
+/// Copy `file` into `dir` once it loads without errors, returning where it went.
+pub fn install(file: &Path, dir: &Path) -> Result<PathBuf> {
+    let name = file
+        .file_name()
+        .filter(|_| file.extension().is_some_and(|e| e == "lua"))
+        .ok_or(anyhow!("{} isn't a .lua file.", file.display()))?;
+    Plugins::load_files(&[file.to_path_buf()])?;
+    std::fs::create_dir_all(dir).context(format!("Failed creating {}.", dir.display()))?;
+    let target = dir.join(name);
+    std::fs::copy(file, &target).context(format!("Failed copying {}.", file.display()))?;
+    Ok(target)
+}
+
+#[cfg(feature = "lua")]
+pub use runtime::Plugins;
+#[cfg(not(feature = "lua"))]
+pub use stub::Plugins;
+
+#[cfg(feature = "lua")]
+mod runtime {
+    use std::{collections::HashSet, path::Path, path::PathBuf};
+
+    use anyhow::{Context, Result, anyhow};
+    use mlua::{Function, Lua, LuaSerdeExt, SerializeOptions, Table, Value};
+    use serde::Serialize;
+
+    use super::Plugin;
+    use crate::notes::{DayNotes, Note};
+
+    /// The `.lua` files in `dir` sorted by name, none if it doesn't exist.
+    fn plugin_files(dir: &Path) -> Result<Vec<PathBuf>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut files = std::fs::read_dir(dir)
+            .context(format!("Failed reading {}.", dir.display()))?
+            .map(|e| e.map(|e| e.path()))
+            .filter(|p| {
+                p.as_ref()
+                    .is_ok_and(|p| p.extension().is_some_and(|e| e == "lua"))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        files.sort();
+        Ok(files)
+    }
+
+    /// Defines the `fh` table plugins register into.
+    const PRELUDE: &str = r#"
+fh = { renderers = {}, filters = {} }
+function fh.renderer(name, f) fh.renderers[name] = f end
+function fh.filter(name, f) fh.filters[name] = f end
+"#;
+
+    /// Every installed plugin loaded into one Lua state.
+    pub struct Plugins {
+        lua: Lua,
+        plugins: Vec<Plugin>,
+    }
+    impl Plugins {
+        /// Load the plugins in `dir`, a file loaded later replaces same named renderers and filters.
+        pub fn load(dir: &Path) -> Result<Plugins> {
+            Self::load_files(&plugin_files(dir)?)
+        }
+        pub fn load_files(files: &[PathBuf]) -> Result<Plugins> {
+            let lua = Lua::new();
+            lua.load(PRELUDE).exec()?;
+            let mut plugins = Vec::new();
+            for file in files {
+                let before = Self::registered(&lua)?;
+                let source = std::fs::read_to_string(file)
+                    .context(format!("Failed reading {}.", file.display()))?;
+                let name = file
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                lua.load(&source)
+                    .set_name(&name)
+                    .exec()
+                    .context(format!("Failed loading plugin {}.", name))?;
+                let after = Self::registered(&lua)?;
+                let new = |i: usize| {
+                    let mut names = after[i].difference(&before[i]).cloned().collect::<Vec<_>>();
+                    names.sort();
+                    names
+                };
+                plugins.push(Plugin {
+                    name,
+                    renderers: new(0),
+                    filters: new(1),
+                });
+            }
+            Ok(Plugins { lua, plugins })
+        }
+        pub fn list(&self) -> &[Plugin] {
+            &self.plugins
+        }
+        /// The day as the renderer `name` writes it.
+        pub fn render(&self, name: &str, day: &DayNotes) -> Result<String> {
+            let f = self.function("renderers", name)?;
+            Ok(f.call::<_, String>(self.value(day)?)?)
+        }
+        /// Whether the filter `name` keeps the note.
+        pub fn keep(&self, name: &str, note: &Note) -> Result<bool> {
+            let f = self.function("filters", name)?;
+            Ok(f.call::<_, bool>(self.value(note)?)?)
+        }
+        fn function(&self, kind: &str, name: &str) -> Result<Function<'_>> {
+            let fh: Table = self.lua.globals().get("fh")?;
+            let table: Table = fh.get(kind)?;
+            table.get::<_, Option<Function>>(name)?.ok_or(anyhow!(
+                "No plugin registered a {} called {}.",
+                &kind[..kind.len() - 1],
+                name
+            ))
+        }
+        fn value(&self, v: &impl Serialize) -> Result<Value<'_>> {
+            // Missing fields stay nil rather than a userdata null that's truthy in Lua.
+            let options = SerializeOptions::new().serialize_none_to_null(false);
+            Ok(self.lua.to_value_with(v, options)?)
+        }
+        /// Names of the registered renderers and filters.
+        fn registered(lua: &Lua) -> Result<[HashSet<String>; 2]> {
+            let fh: Table = lua.globals().get("fh")?;
+            let names = |kind: &str| -> Result<HashSet<String>> {
+                let table: Table = fh.get(kind)?;
+                Ok(table
+                    .pairs::<String, Value>()
+                    .map(|p| p.map(|(k, _)| k))
+                    .collect::<mlua::Result<_>>()?)
+            };
+            Ok([names("renderers")?, names("filters")?])
+        }
+    }
+}
+
+#[cfg(not(feature = "lua"))]
+mod stub {
+    use std::path::{Path, PathBuf};
+
+    use anyhow::{Result, anyhow};
+
+    use super::Plugin;
+    use crate::notes::{DayNotes, Note};
+
+    /// Stands in for the plugins when fh is built without Lua, loading always fails.
+    pub enum Plugins {}
+    impl Plugins {
+        pub fn load(_dir: &Path) -> Result<Plugins> {
+            Err(Self::missing())
+        }
+        pub fn load_files(_files: &[PathBuf]) -> Result<Plugins> {
+            Err(Self::missing())
+        }
+        pub fn list(&self) -> &[Plugin] {
+            match *self {}
+        }
+        pub fn render(&self, _name: &str, _day: &DayNotes) -> Result<String> {
+            match *self {}
+        }
+        pub fn keep(&self, _name: &str, _note: &Note) -> Result<bool> {
+            match *self {}
+        }
+        fn missing() -> anyhow::Error {
+            anyhow!("fh was built without plugins, rebuild it with --features lua.")
+        }
+    }
+}
+
+#[cfg(all(test, feature = "lua"))]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{Plugin, Plugins, install};
+    use crate::notes::{DayNotes, Note, Status};
+
+    #[test]
+    fn test_plugins() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("work.lua");
+        std::fs::write(
+            &source,
+            r#"
+fh.renderer("count", function(day)
+  local open = 0
+  for _, note in ipairs(day.notes) do
+    if note.status ~= "done" then open = open + 1 end
+  end
+  return day.date .. " " .. open .. "/" .. #day.notes
+end)
+fh.filter("work", function(note) return note.body:find("+work", 1, true) ~= nil and note.due == nil end)
+"#,
+        )
+        .unwrap();
+        let plugins_dir = dir.path().join("plugins");
+        install(&source, &plugins_dir).unwrap();
+        let plugins = Plugins::load(&plugins_dir).unwrap();
+        assert_eq!(
+            plugins.list(),
+            [Plugin {
+                name: String::from("work.lua"),
+                renderers: vec![String::from("count")],
+                filters: vec![String::from("work")],
+            }]
+        );
+        let note = |id, body: &str, status| Note {
+            id,
+            body: body.to_string(),
+            status,
+            ..Default::default()
+        };
+        let day = DayNotes {
+            notes: vec![
+                note(1, "plan +work", Status::Done),
+                note(2, "milk", Status::Todo),
+            ],
+            note_count: 2,
+            date: NaiveDate::from_ymd_opt(2025, 10, 12).unwrap(),
+            day_text: String::new(),
+        };
+        assert_eq!(plugins.render("count", &day).unwrap(), "2025-10-12 1/2");
+        assert!(plugins.keep("work", &day.notes[0]).unwrap());
+        assert!(!plugins.keep("work", &day.notes[1]).unwrap());
+        assert!(plugins.render("missing", &day).is_err());
+        std::fs::write(&source, "fh.renderer(").unwrap();
+        assert!(install(&source, &plugins_dir).is_err());
+    }
+}
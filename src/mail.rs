@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Note metadata key holding a `mid:` link to the message a note was ingested from.
+pub const LINK_KEY: &str = "mail";
+
+/// The `[mail]` table of the config.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct MailConfig {
+    /// mbox file or Maildir directory `ingest-mail` reads.
+    pub path: Option<PathBuf>,
+    /// Address whose messages become notes, e.g. `fh@me.com`. A `+day` suffix like
+    /// `fh+tomorrow@me.com` files the note on that day, today otherwise.
+    pub address: Option<String>,
+}
+
+/// The headers of a message fh reads.
+#[derive(Debug, Default, PartialEq)]
+pub struct Message {
+    pub subject: String,
+    /// Addresses in the `To`, `Cc` and `Delivered-To` headers, lowercased.
+    pub recipients: Vec<String>,
+    pub message_id: Option<String>,
+}
+impl Message {
+    /// `mid:` url of the message, as mail clients open them.
+    pub fn link(&self) -> Option<String> {
+        self.message_id.as_ref().map(|id| format!("mid:{}", id))
+    }
+    /// The day suffix of the first recipient matching `address`, empty without one.
+    /// `None` when the message isn't for `address`.
+    pub fn day_for(&self, address: &str) -> Option<String> {
+        let (user, domain) = address
+            .to_lowercase()
+            .split_once('@')
+            .map(|(u, d)| (u.to_string(), d.to_string()))?;
+        self.recipients.iter().find_map(|r| {
+            let (local, d) = r.split_once('@')?;
+            if d != domain {
+                return None;
+            }
+            match local.split_once('+') {
+                Some((u, day)) if u == user => Some(day.to_string()),
+                None if local == user => Some(String::new()),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// The messages at `path`, a Maildir when it's a directory and an mbox file otherwise.
+pub fn read_messages(path: &Path) -> Result<Vec<Message>> {
+    if path.is_dir() {
+        read_maildir(path)
+    } else {
+        let mbox =
+            std::fs::read_to_string(path).context(format!("Failed reading {}.", path.display()))?;
+        Ok(split_mbox(&mbox).into_iter().map(parse_headers).collect())
+    }
+}
+
+fn read_maildir(dir: &Path) -> Result<Vec<Message>> {
+    let mut files = Vec::new();
+    for sub in ["cur", "new"] {
+        let sub = dir.join(sub);
+        if !sub.exists() {
+            continue;
+        }
+        for entry in
+            std::fs::read_dir(&sub).context(format!("Failed reading {}.", sub.display()))?
+        {
+            files.push(entry?.path());
+        }
+    }
+    files.sort();
+    files
+        .iter()
+        .map(|f| {
+            let bytes = std::fs::read(f).context(format!("Failed reading {}.", f.display()))?;
+            Ok(parse_headers(&String::from_utf8_lossy(&bytes)))
+        })
+        .collect()
+}
+
+/// The messages of an mbox, each starts after a `From ` line.
+pub fn split_mbox(mbox: &str) -> Vec<&str> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in mbox.split_inclusive('\n') {
+        if line.starts_with("From ") {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(mbox.len());
+            let message = &mbox[start..end];
+            message.split_once('\n').map(|(_, m)| m).unwrap_or("")
+        })
+        .collect()
+}
+
+/// Read the header block of a raw message, joining folded lines.
+pub fn parse_headers(raw: &str) -> Message {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in raw.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    let mut message = Message::default();
+    for (name, value) in headers {
+        match name.as_str() {
+            "subject" => message.subject = value,
+            "message-id" => {
+                message.message_id = Some(value.trim_matches(['<', '>']).to_string());
+            }
+            "to" | "cc" | "delivered-to" => message.recipients.extend(addresses(&value)),
+            _ => {}
+        }
+    }
+    message
+}
+
+/// Addresses in a header like `Me <fh+today@me.com>, other@me.com`.
+fn addresses(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .filter_map(|part| {
+            let address = match part.split_once('<') {
+                Some((_, rest)) => rest.split('>').next()?,
+                None => part,
+            };
+            let address = address.trim().to_lowercase();
+            address.contains('@').then_some(address)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_headers, split_mbox};
+
+    #[test]
+    fn test_mbox() {
+        let mbox = "From me@me.com Thu Oct 16 08:00:00 2025\n\
+            To: Me <fh+tomorrow@me.com>\n\
+            Subject: Buy\n\tprinter ink\n\
+            Message-ID: <abc@me.com>\n\
+            \n\
+            body\n\
+            From other@me.com Thu Oct 16 09:00:00 2025\n\
+            To: someone@else.com, FH@me.com\n\
+            Subject: Call mum\n\
+            \n\
+            From someone@else.com Thu Oct 16 10:00:00 2025\n\
+            To: fh@other.com\n\
+            Subject: Not mine\n";
+        let messages = split_mbox(mbox)
+            .into_iter()
+            .map(parse_headers)
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].subject, "Buy printer ink");
+        assert_eq!(messages[0].link().as_deref(), Some("mid:abc@me.com"));
+        let days = messages
+            .iter()
+            .map(|m| m.day_for("fh@me.com"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            days,
+            [Some(String::from("tomorrow")), Some(String::new()), None]
+        );
+    }
+}
@@ -1,213 +1,2863 @@
+pub mod attach;
+pub mod bookmark;
+pub mod capture;
+pub mod color;
+pub mod complete;
+pub mod config;
+pub mod daemon;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod git;
+pub mod gtasks;
+pub mod hooks;
+pub mod humanize;
+pub mod import;
+pub mod jira;
+pub mod logging;
+pub mod lua;
+pub mod mail;
+pub mod mood;
 pub mod notes;
+pub mod notify;
+pub mod plugin;
+pub mod push;
+pub mod query;
+pub mod remote;
+pub mod repl;
+pub mod replicate;
+pub mod report;
+pub mod secret;
+pub mod server;
+pub mod service;
+pub mod slack;
+pub mod status;
 pub mod store;
+pub mod sync;
+pub mod template;
+pub mod urgency;
 use std::{
-    fs::File,
-    io::{Read, Seek, Write},
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    io::{BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
     process,
-    str::FromStr,
 };
 
-use crate::store::setup_db;
+use crate::{
+    config::Config,
+    error::{EXIT_ERROR, EXIT_NO_NOTES, Exit, FhError},
+    filter::NoteFilter,
+    import::ColumnMap,
+    query::{Cmp, Query, Term},
+    remote::RemoteStore,
+    report::Report,
+    urgency::{UrgencyInput, urgency},
+};
+use ansi_term::Style;
 use anyhow::{Context, Result, anyhow};
-use chrono::{DateTime, Days, Local, NaiveDate, TimeZone};
-use clap::{Parser, Subcommand};
-use env_logger::Env;
-use log::{debug, info};
-use notes::{DayNotes, Note};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, TimeDelta, TimeZone, Utc, Weekday};
+use clap::{ArgAction, Args, ColorChoice, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{CompleteEnv, Shell, engine::ArgValueCandidates, env::Shells};
+use notes::{
+    DayNotes, NewNote, Note, ParsedDayNotes, Status, StatusMatch, extract_urls, find_date,
+    frontmatter_md, split_frontmatter,
+};
+use rustyline::error::ReadlineError;
 use store::NoteStore;
 use tempfile::NamedTempFile;
+use tracing::{debug, info};
+
+/// Name of the high-water mark kept by `export --since-last`.
+const EXPORT_MARK: &str = "export";
+/// Hours after which an edit lock is treated as left behind, whatever its process.
+const EDIT_LOCK_HOURS: i64 = 12;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let args = Mode::parse();
-    let home = std::env::var("HOME")?;
+async fn main() {
+    CompleteEnv::with_factory(Cli::command).complete();
+    let cli = Cli::parse();
+    if let Err(e) = logging::init(cli.verbose, cli.log_file.as_deref()) {
+        eprintln!("Error: {:#}", e);
+        process::exit(EXIT_ERROR);
+    }
+    color::init(cli.color);
+    if let Err(e) = run(cli).await {
+        if let Some(Exit(code)) = e.downcast_ref::<Exit>() {
+            process::exit(*code);
+        }
+        eprintln!("Error: {:#}", e);
+        process::exit(exit_code(&e));
+    }
+}
+
+/// Exit code for an error, taken from the first [`FhError`] in its chain.
+fn exit_code(e: &anyhow::Error) -> i32 {
+    e.chain()
+        .find_map(|c| c.downcast_ref::<FhError>())
+        .map(FhError::exit_code)
+        .unwrap_or(EXIT_ERROR)
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let Cli {
+        mode: args,
+        ephemeral,
+        db,
+        workspace,
+        format,
+        remote,
+        ..
+    } = cli;
+    if let Mode::Completions { shell, r#static } = args {
+        return completions(shell, r#static);
+    }
+    let args = with_clipboard(args)?;
+    let (args, format) = with_format(args, format)?;
     // Setup fuckhead config.
-    let db_path = PathBuf::from(home).join(".fuckhead/db.db");
-    let parent = db_path.parent().unwrap();
+    let config_dir = config::config_dir()?;
+    let parent = config_dir.as_path();
+    if let Mode::Init = args {
+        return init(parent).await;
+    }
     if !parent.exists() {
         debug!("Creating parent config dir at {}", parent.display());
-        std::fs::create_dir(parent).unwrap();
+        std::fs::create_dir_all(parent)
+            .context(format!("Failed creating {}.", parent.display()))?;
+    }
+    let config = Config::load(&parent.join("config.toml"))?;
+    // The daemon serves the configured database and workspace, picking others skips it.
+    let use_daemon = db.is_none() && workspace.is_none() && !ephemeral;
+    // Picking a local database skips the remote from FH_REMOTE or the config.
+    let remote = match remote {
+        Some(url) => Some(RemoteStore::new(url, &config.remote)?),
+        None if db.is_none() && !ephemeral => RemoteStore::from_config(&config.remote)?,
+        None => None,
+    };
+    let db_path = match db {
+        Some(db) => db,
+        None => config::db_path(parent),
+    };
+    let socket = parent.join(daemon::SOCKET_NAME);
+    let workspace = workspace.unwrap_or(config.workspace().to_string());
+    if ephemeral {
+        if matches!(
+            args,
+            Mode::Daemon | Mode::Replicate { .. } | Mode::External(_)
+        ) {
+            return Err(anyhow!(
+                "The daemon, replication and plugins need the database file, drop --ephemeral."
+            ));
+        }
+        let store = NoteStore::open_in_memory()
+            .await?
+            .with_contexts(&config.contexts)
+            .with_workspace(&workspace)
+            .with_hooks(config.hooks.clone())
+            .with_push(config.push.clone());
+        if let Mode::Repl = args {
+            return repl(&store, &config, &db_path, &socket).await;
+        }
+        return execute(&store, &config, &db_path, &socket, args, format).await;
+    }
+    if let Some(remote) = remote {
+        return run_remote(&remote, &config, args, format).await;
+    }
+    if let Mode::External(args) = &args {
+        let code = plugin::run(args, &db_path, &workspace, parent)?;
+        process::exit(code);
+    }
+    // Restoring needs the database missing, so runs before it's created.
+    if let Mode::Replicate {
+        bucket,
+        restore: true,
+    } = &args
+    {
+        replicate::restore(&db_path, &replica_url(&config, bucket.as_deref())?)?;
+        println!("Restored {}.", db_path.display());
+        return Ok(());
+    }
+    // Hand the quick commands to a running daemon, skipping the database setup.
+    if let Some(request) = daemon_request(&args, &config).filter(|_| use_daemon) {
+        match daemon::send(&socket, &request).await? {
+            Some(daemon::Response::Output(out)) => {
+                println!("{}", out);
+                return Ok(());
+            }
+            Some(daemon::Response::Notes(notes)) => {
+                let Mode::New { out, .. } = &args else {
+                    unreachable!("only new is answered with notes");
+                };
+                out.print(&notes, format)?;
+                return Ok(());
+            }
+            Some(daemon::Response::Error(e)) => return Err(anyhow!(e)),
+            Some(daemon::Response::Fallback) | None => (),
+        }
+    }
+    let store = NoteStore::open(&db_path)
+        .await?
+        .with_contexts(&config.contexts)
+        .with_workspace(&workspace)
+        .with_hooks(config.hooks.clone())
+        .with_push(config.push.clone());
+    if let Mode::Repl = args {
+        return repl(&store, &config, &db_path, &socket).await;
+    }
+    execute(&store, &config, &db_path, &socket, args, format).await
+}
+
+/// Read commands from a prompt and run them against one open store until `exit` or end of input.
+async fn repl(store: &NoteStore, config: &Config, db_path: &Path, socket: &Path) -> Result<()> {
+    let history = config::config_dir()?.join(repl::HISTORY_NAME);
+    let mut editor = repl::editor(Cli::command, &history)?;
+    loop {
+        let line = match tokio::task::block_in_place(|| editor.readline("fh> ")) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let args = match repl::parse_line(&line) {
+            Ok(repl::Input::Empty) => continue,
+            Ok(repl::Input::Exit) => break,
+            Ok(repl::Input::Command(args)) => args,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                continue;
+            }
+        };
+        editor.add_history_entry(line.trim())?;
+        let (mode, format) =
+            match Cli::try_parse_from(std::iter::once(String::from("fh")).chain(args)) {
+                Ok(cli) => (cli.mode, cli.format),
+                Err(e) => {
+                    e.print()?;
+                    continue;
+                }
+            };
+        let (mode, format) = match with_format(mode, format) {
+            Ok(picked) => picked,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                continue;
+            }
+        };
+        match mode {
+            Mode::Init
+            | Mode::Completions { .. }
+            | Mode::Repl
+            | Mode::Daemon
+            | Mode::Serve { .. }
+            | Mode::Replicate { .. } => eprintln!("Error: Run {} outside the repl.", line.trim()),
+            mode => {
+                let result = match with_clipboard(mode) {
+                    Ok(mode) => execute(store, config, db_path, socket, mode, format).await,
+                    Err(e) => Err(e),
+                };
+                // Quiet commands only set an exit code, which the repl has no use for.
+                if let Err(e) = result
+                    && e.downcast_ref::<Exit>().is_none()
+                {
+                    eprintln!("Error: {:#}", e);
+                }
+            }
+        }
+    }
+    editor.save_history(&history)?;
+    Ok(())
+}
+
+/// Run a command against the local store.
+async fn execute(
+    store: &NoteStore,
+    config: &Config,
+    db_path: &Path,
+    socket: &Path,
+    args: Mode,
+    format: Format,
+) -> Result<()> {
+    match args {
+        Mode::External(args) => {
+            let code = plugin::run(&args, db_path, store.workspace(), &config::config_dir()?)?;
+            if code != 0 {
+                return Err(anyhow!(
+                    "{}{} exited with {}.",
+                    plugin::PREFIX,
+                    args[0],
+                    code
+                ));
+            }
+        }
+        Mode::Plan {
+            yes,
+            day,
+            week,
+            days,
+        } => {
+            let (first_day, last_day) = if week {
+                period_range(config, day, Period::Week)
+            } else {
+                let first_day = map_day(Local::now(), day);
+                (
+                    first_day,
+                    first_day + Days::new(days.unwrap_or(7) as u64 - 1),
+                )
+            };
+            plan(store, config, first_day, last_day, yes).await?;
+            show_range(
+                store,
+                config,
+                first_day,
+                last_day,
+                false,
+                &ShowArgs::default(),
+            )
+            .await?;
+        }
+        Mode::Edit {
+            day,
+            date,
+            force,
+            yes,
+        } => {
+            let day = day_arg(day, date.as_deref())?;
+            edit(store, config, day, force, yes).await?;
+            purge_trash(store, config).await?;
+            show(store, config, day, &ShowArgs::default()).await?;
+        }
+        Mode::Check { quiet } => {
+            let day = Local::now().date_naive();
+            let notes = store.get_days_notes(day).await?;
+            if quiet {
+                if notes.note_count == 0 {
+                    return Err(Exit(EXIT_NO_NOTES).into());
+                }
+                return Ok(());
+            }
+            if notes.note_count == 0 {
+                edit(store, config, None, false, false).await?
+            } else {
+                let (start_day, end_day) = period_range(config, None, Period::Week);
+                show_range(
+                    store,
+                    config,
+                    start_day,
+                    end_day,
+                    false,
+                    &ShowArgs::default(),
+                )
+                .await?
+            }
+        }
+        Mode::Show {
+            from: Some(from),
+            to,
+            page,
+            per_page,
+            args,
+            ..
+        } => {
+            let today = Local::now().date_naive();
+            let start_day = query::parse_date(&from, today)?;
+            let end_day = query::parse_date(&to, today)?;
+            let pages = query::page_count(start_day, end_day, per_page);
+            let (first, last) = query::page_range(start_day, end_day, page, per_page)
+                .ok_or(anyhow!("No page {}, the range has {} pages.", page, pages))?;
+            show_range(store, config, first, last, false, &args).await?;
+            println!("Page {} of {}.", page, pages);
+        }
+        Mode::Show {
+            day,
+            period,
+            week,
+            args,
+            ..
+        } => match (week, period) {
+            (Some(monday), _) => {
+                let sunday = monday + Days::new(6);
+                show_range(store, config, monday, sunday, false, &args).await?
+            }
+            (None, None) => show(store, config, day, &args).await?,
+            (None, Some(p)) => {
+                let (start_day, end_day) = period_range(config, day, p);
+                let by_week = matches!(p, Period::Month);
+                show_range(store, config, start_day, end_day, by_week, &args).await?
+            }
+        },
+        Mode::Focus { action } => match action {
+            Some(FocusAction::Set { ids, out }) => {
+                store.set_focus(&ids, config.focus_limit).await?;
+                let mut focused = vec![];
+                for id in ids {
+                    focused.push(store.get_dated_note(id).await?);
+                }
+                let notes = store.get_days_notes(Local::now().date_naive()).await?;
+                out.summarise(&focused, format, notes.pretty_focus().trim_end())?;
+            }
+            None => {
+                let notes = store.get_days_notes(Local::now().date_naive()).await?;
+                print!("{}", notes.pretty_focus());
+            }
+        },
+        Mode::OnThisDay => {
+            let today = Local::now().date_naive();
+            for span in &config.on_this_day {
+                let day = query::date_ago(span, today)?;
+                let notes = store.get_days_notes(day).await?;
+                if notes.is_empty() {
+                    continue;
+                }
+                let ago = humanize::relative_day(day, today);
+                println!("{}", color::style(Style::new().bold()).paint(ago));
+                println!("{}", notes.pretty());
+            }
+        }
+        Mode::Next { top } => next(store, config, top).await?,
+        Mode::Cancel { id, out } => {
+            store.set_status(id, Status::Cancelled).await?;
+            out.print(&[store.get_dated_note(id).await?], format)?;
+        }
+        Mode::Assign { id, project, out } => {
+            store.set_project(id, &project).await?;
+            out.print(&[store.get_dated_note(id).await?], format)?;
+        }
+        Mode::Projects => {
+            for (name, open, done) in store.project_counts().await? {
+                println!("+{} {} open, {} done", name, open, done);
+            }
+        }
+        Mode::Report {
+            project,
+            period,
+            format,
+            include_private,
+        } => report(store, project, period, format, include_private).await?,
+        Mode::Export {
+            since_last: true,
+            include_private,
+            ..
+        } => export_since_last(store, include_private).await?,
+        Mode::Export {
+            from,
+            to,
+            format,
+            printable,
+            include_private,
+            ..
+        } => {
+            let from = from.expect("clap requires from without since_last");
+            let today = Local::now().date_naive();
+            let start_day = query::parse_date(&from, today)?;
+            let end_day = query::parse_date(&to, today)?;
+            let html = printable || matches!(format, ExportFormat::Html);
+            export_range(store, start_day, end_day, html, printable, include_private).await?;
+        }
+        Mode::Init | Mode::Completions { .. } | Mode::Repl => {
+            unreachable!("handled before the database is opened")
+        }
+        Mode::Daemon => {
+            purge_trash(store, config).await?;
+            daemon::run(
+                store.clone(),
+                socket,
+                config.capture_dir.clone(),
+                config.push.clone(),
+            )
+            .await?
+        }
+        Mode::Serve {
+            gen_token: true,
+            scope,
+            ..
+        } => println!("{}", server::gen_token(scope)),
+        Mode::Serve {
+            include_private, ..
+        } => server::serve(store.clone(), &config.server, include_private).await?,
+        Mode::Share {
+            day,
+            period,
+            expires,
+        } => share(store, config, day, period, &expires).await?,
+        Mode::Replicate { bucket, .. } => {
+            // Nothing else is read while litestream runs.
+            store.pool.close().await;
+            replicate::replicate(db_path, &replica_url(config, bucket.as_deref())?)?
+        }
+        Mode::Sync { peer } => {
+            let peer = NoteStore::open(&peer)
+                .await?
+                .with_workspace(store.workspace());
+            let plan = sync::sync(store, &peer).await?;
+            for conflict in &plan.conflicts {
+                println!("Conflict: {}", conflict.pretty());
+            }
+            println!(
+                "Received {} notes, sent {}, {} conflicts.",
+                plan.to_local.len(),
+                plan.to_peer.len(),
+                plan.conflicts.len()
+            );
+        }
+        Mode::Private { id, off, out } => {
+            store.set_private(id, !off).await?;
+            out.print(&[store.get_dated_note(id).await?], format)?;
+        }
+        Mode::Trash {
+            keep_forever: Some(id),
+            undo,
+            out,
+            ..
+        } => {
+            store.set_keep_forever(id, !undo).await?;
+            let notes = store.deleted_notes().await?;
+            out.print(
+                &notes
+                    .into_iter()
+                    .filter(|(_, n)| n.id == id)
+                    .collect::<Vec<_>>(),
+                format,
+            )?;
+        }
+        Mode::Trash { purge: true, .. } => {
+            if config.trash_retention_days.is_none() {
+                return Err(anyhow!("Set trash_retention_days in the config to purge."));
+            }
+            println!("Purged {} notes.", purge_trash(store, config).await?);
+        }
+        Mode::Trash { .. } => {
+            for (date, note) in store.deleted_notes().await? {
+                let deleted = note.deleted_at.map(|d| d.date_naive().to_string());
+                println!("{} {} {}", deleted.unwrap_or_default(), date, note.pretty());
+            }
+        }
+        Mode::Import {
+            file,
+            format: ImportFormat::Csv,
+            map,
+            header,
+            dry_run,
+        } => import_csv(store, &file, &map, header, dry_run).await?,
+        Mode::Archive { before, to } => {
+            let before = query::parse_date(&before, Local::now().date_naive())?;
+            let moved = store.archive_before(before, &to).await?;
+            println!(
+                "Archived {} days before {} to {}.",
+                moved,
+                before,
+                to.display()
+            );
+        }
+        Mode::Status { template, waybar } => {
+            let today = Local::now().date_naive();
+            let line = status::StatusLine::load(store, today).await?;
+            if waybar {
+                let notes = store.get_days_notes(today).await?.notes;
+                println!("{}", line.waybar(&template, &notes));
+            } else {
+                println!("{}", line.render(&template));
+            }
+        }
+        Mode::Notify { dry_run } => {
+            let today = Local::now().date_naive();
+            for note in notify::notify_due(store, today, config.notify_window_days, dry_run).await?
+            {
+                println!("{}", note.pretty());
+            }
+            for note in notify::send_reminders(store, &config.push, Utc::now(), dry_run).await? {
+                println!("Reminder {}", note.pretty());
+            }
+        }
+        Mode::Remind {
+            id,
+            time,
+            date,
+            out,
+        } => {
+            let remind_at = match time.as_str() {
+                "none" => None,
+                time => {
+                    let date = date
+                        .map(|d| query::parse_date(&d, Local::now().date_naive()))
+                        .transpose()?;
+                    Some(notify::remind_at(time, date, Local::now())?)
+                }
+            };
+            store.set_reminder(id, remind_at).await?;
+            let summary = match remind_at {
+                Some(at) => format!(
+                    "Reminding about note {} at {}.",
+                    id,
+                    at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                ),
+                None => format!("Cleared the reminder of note {}.", id),
+            };
+            out.summarise(&[store.get_dated_note(id).await?], format, &summary)?;
+        }
+        Mode::Reminders => {
+            for (at, note) in store.reminders(Utc::now(), false).await? {
+                let at = at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                println!("{} {}", at, note.pretty_term());
+            }
+        }
+        Mode::InstallService { user, dry_run } => service::install(config, user, dry_run)?,
+        Mode::People => {
+            for (name, count) in store.mention_counts().await? {
+                println!("@{} {}", name, count);
+            }
+        }
+        Mode::Copy {
+            from,
+            to,
+            open_only,
+            reset,
+            out,
+        } => {
+            let from_day = map_day(Local::now(), Some(from));
+            let to_day = map_day(Local::now(), Some(to));
+            let before = store.get_days_notes(to_day).await?.notes;
+            let copied = store.copy_notes(from_day, to_day, open_only, reset).await?;
+            let notes = store.get_days_notes(to_day).await?.notes;
+            let notes = notes
+                .into_iter()
+                .filter(|n| before.iter().all(|b| b.id != n.id))
+                .map(|n| (to_day, n))
+                .collect::<Vec<_>>();
+            let summary = format!("Copied {} notes from {} to {}.", copied, from_day, to_day);
+            out.summarise(&notes, format, &summary)?;
+            if !out.quiet && format != Format::Json {
+                show(store, config, Some(to), &ShowArgs::default()).await?;
+            }
+        }
+        Mode::New {
+            day,
+            date,
+            file: Some(path),
+            out,
+            ..
+        } => {
+            let day = day_arg(day, date.as_deref())?;
+            out.print(&new_from_file(store, &path, day).await?, format)?;
+        }
+        Mode::New {
+            day,
+            date,
+            template: Some(name),
+            vars,
+            out,
+            ..
+        } => {
+            let day = day_arg(day, date.as_deref())?;
+            out.print(&new_from_template(store, &name, &vars, day).await?, format)?;
+        }
+        Mode::New {
+            body,
+            day,
+            date,
+            force,
+            secret,
+            fetch_title,
+            out,
+            ..
+        } => {
+            let fetch_title = fetch_title || config.fetch_titles;
+            let note = new_note(
+                store,
+                config,
+                body.join(" "),
+                day_arg(day, date.as_deref())?,
+                force,
+                secret,
+                fetch_title,
+            )
+            .await?;
+            out.print(&[note], format)?;
+        }
+        Mode::Replace {
+            old,
+            new,
+            from,
+            to,
+            dry_run,
+            out,
+        } => {
+            let start_day = from.map(|d| map_day(Local::now(), Some(d)));
+            let end_day = to.map(|d| map_day(Local::now(), Some(d)));
+            let range = (start_day, end_day);
+            replace(store, &old, &new, range, dry_run, out, format).await?
+        }
+        Mode::Bulk { action, out } => bulk(store, action, out, format).await?,
+        Mode::Query { query, save } => {
+            let parsed = Query::parse(&query, Local::now().date_naive())?;
+            if let Some(name) = save {
+                store.save_query(&name, &query).await?;
+                println!("Saved query {}.", name);
+            }
+            let notes = store.query_notes(&parsed).await?;
+            print_notes(&notes, format)?;
+        }
+        Mode::Q { name } => {
+            let query = store
+                .get_saved_query(&name)
+                .await?
+                .ok_or(anyhow!("No saved query named {}.", name))?;
+            let query = Query::parse(&query, Local::now().date_naive())?;
+            let notes = store.query_notes(&query).await?;
+            print_notes(&notes, format)?;
+        }
+        Mode::Due { id, date, out } => {
+            let due = match date.as_deref() {
+                None | Some("none") => None,
+                Some(d) => Some(query::parse_date(d, Local::now().date_naive())?),
+            };
+            store.set_due(id, due).await?;
+            out.print(&[store.get_dated_note(id).await?], format)?;
+        }
+        Mode::List {
+            done, all, limit, ..
+        } => {
+            let filter = NoteFilter {
+                status: match (done, all) {
+                    (_, true) => None,
+                    (true, _) => Some(StatusMatch::Is(Status::Done)),
+                    _ => Some(StatusMatch::Open),
+                },
+                ..Default::default()
+            };
+            let notes = store.list_notes(&filter, limit).await?;
+            print_notes(&notes, format)?;
+        }
+        Mode::Overdue => overdue(store).await?,
+        Mode::Day { action } => match action {
+            DayAction::Delete { date, out } => {
+                let date = query::parse_date(&date, Local::now().date_naive())?;
+                let notes = store.get_days_notes(date).await?.notes;
+                let deleted = store.delete_day(date).await?;
+                let notes = notes.into_iter().map(|n| (date, n)).collect::<Vec<_>>();
+                let summary = format!("Deleted {} notes from {}.", deleted, date);
+                out.summarise(&notes, format, &summary)?;
+            }
+            DayAction::Skip {
+                from,
+                to,
+                reason,
+                out,
+            } => {
+                let today = Local::now().date_naive();
+                let (from, to) = (
+                    query::parse_date(&from, today)?,
+                    query::parse_date(&to, today)?,
+                );
+                let skipped = store.skip_days(from, to, reason.as_deref()).await?;
+                // Skipping leaves the notes as they are.
+                let summary = format!("Skipped {} days from {} to {}.", skipped, from, to);
+                out.summarise(&[], format, &summary)?;
+            }
+        },
+        Mode::Links { day } => {
+            let notes = store.get_days_notes(map_day(Local::now(), day)).await?;
+            for note in &notes.notes {
+                for url in extract_urls(&note.body) {
+                    println!("{} {}", note.id, url);
+                }
+            }
+            for url in extract_urls(&notes.day_text) {
+                println!("- {}", url);
+            }
+        }
+        Mode::Open { id, index } => {
+            let note = store.get_note(id).await?;
+            let urls = extract_urls(&note.body);
+            let url = urls.get(index.saturating_sub(1)).ok_or(anyhow!(
+                "Note {} has no link {}.",
+                id,
+                index
+            ))?;
+            open_url(url)?;
+        }
+        Mode::Mood {
+            action: Some(MoodAction::Chart { period }),
+            ..
+        } => {
+            let (start_day, end_day) = period_range(config, None, period);
+            let recorded = store
+                .day_metrics_in_range(start_day, end_day)
+                .await?
+                .into_iter()
+                .collect::<HashMap<_, _>>();
+            let days = start_day.iter_days().take_while(|d| *d <= end_day);
+            let (mood, energy): (Vec<_>, Vec<_>) = days
+                .map(|d| {
+                    recorded
+                        .get(&d)
+                        .map_or((None, None), |m| (m.mood, m.energy))
+                })
+                .unzip();
+            println!("{} to {}", start_day, end_day);
+            println!("Mood   {}", mood::sparkline(&mood));
+            println!("Energy {}", mood::sparkline(&energy));
+        }
+        Mode::Mood {
+            day,
+            mood,
+            energy,
+            tags,
+            action: None,
+        } => {
+            let date = map_day(Local::now(), day);
+            let mut metrics = store.day_metrics(date).await?;
+            if mood.is_some() || energy.is_some() || tags.is_some() {
+                metrics.mood = mood.or(metrics.mood);
+                metrics.energy = energy.or(metrics.energy);
+                metrics.tags = tags.map_or(metrics.tags, |t| mood::parse_tags(&t));
+                store.set_day_metrics(date, &metrics).await?;
+            }
+            println!("{} {}", date, mood::metrics_line(&metrics));
+        }
+        Mode::Attach {
+            action: Some(AttachAction::Open { id }),
+            ..
+        } => {
+            let dir = config::config_dir()?.join(attach::DIR_NAME);
+            let attachments = store
+                .attachments_by_note()
+                .await?
+                .remove(&id)
+                .ok_or(anyhow!("Note {} has no attachments.", id))?;
+            for attachment in attachments {
+                open_url(&attach::path(&dir, &attachment).to_string_lossy())?;
+            }
+        }
+        Mode::Attach {
+            id: Some(id),
+            file: Some(file),
+            action: None,
+            out,
+        } => {
+            let dir = config::config_dir()?.join(attach::DIR_NAME);
+            let hash = attach::store_file(&file, &dir)?;
+            let attachment = store
+                .add_attachment(id, &attach::file_name(&file)?, &hash)
+                .await?;
+            let summary = format!("Attached {} to note {}.", attachment.name, id);
+            out.summarise(&[store.get_dated_note(id).await?], format, &summary)?;
+        }
+        Mode::Attach { .. } => unreachable!("clap requires an id and file without a subcommand"),
+        Mode::Gtasks { action } => {
+            let token_path = config::config_dir()?.join(gtasks::TOKEN_NAME);
+            match action {
+                GtasksAction::Login => gtasks::login(&config.gtasks, &token_path).await?,
+                GtasksAction::Sync => {
+                    let changes = gtasks::sync(store, &config.gtasks, &token_path).await?;
+                    for change in &changes {
+                        println!("{}", change);
+                    }
+                    println!("{} changes.", changes.len());
+                }
+            }
+        }
+        Mode::Jira { action } => jira(store, config, action).await?,
+        Mode::Bookmarks => bookmarks(store).await?,
+        Mode::CaptureDir { dir } => {
+            let dir = dir.or(config.capture_dir.clone()).ok_or(anyhow!(
+                "No directory to capture, pass one or set capture_dir in the config."
+            ))?;
+            let notes = capture::capture_dir(store, &dir).await?;
+            for note in &notes {
+                println!("{}", note.pretty());
+            }
+            println!("Captured {} files.", notes.len());
+        }
+        Mode::GitCapture { repos } => git_capture(store, config, &repos).await?,
+        Mode::IngestMail { path, address } => {
+            ingest_mail(store, config, path.as_deref(), address.as_deref()).await?
+        }
+        Mode::Goal { action } => match action {
+            GoalAction::Add {
+                title,
+                quarter,
+                year,
+            } => {
+                let period = quarter.or(year.map(|y| y.to_string())).unwrap_or_default();
+                let goal = store.add_goal(&title, &period).await?;
+                println!("Added goal:{} for {}.", goal.slug, goal.period);
+            }
+            GoalAction::List { period } => {
+                for goal in store.goals(period.as_deref()).await? {
+                    println!("{} goal:{} {}", goal.period, goal.slug, goal.title);
+                }
+            }
+            GoalAction::Progress { period } => {
+                for p in store.goal_progress(period.as_deref()).await? {
+                    println!(
+                        "{} {}: {} open, {} done, {}h{:02}m logged",
+                        p.goal.period,
+                        p.goal.title,
+                        p.open,
+                        p.done,
+                        p.time.num_hours(),
+                        p.time.num_minutes() % 60
+                    );
+                }
+            }
+        },
+        Mode::Plugin { action } => {
+            let dir = config::config_dir()?.join(lua::DIR_NAME);
+            match action {
+                PluginAction::Install { file } => {
+                    let target = lua::install(&file, &dir)?;
+                    println!("Installed {}.", target.display());
+                }
+                PluginAction::List => {
+                    for plugin in lua::Plugins::load(&dir)?.list() {
+                        println!(
+                            "{}: renderers [{}], filters [{}]",
+                            plugin.name,
+                            plugin.renderers.join(", "),
+                            plugin.filters.join(", ")
+                        );
+                    }
+                }
+            }
+        }
+        Mode::Meta { action } => match action {
+            MetaAction::Set {
+                id,
+                key,
+                value,
+                out,
+            } => {
+                store.set_meta(id, &key, &value).await?;
+                out.print(&[store.get_dated_note(id).await?], format)?;
+            }
+            MetaAction::Get { id } => {
+                for (key, value) in store.get_meta(id).await? {
+                    println!("{}: {}", key, value);
+                }
+            }
+        },
+        Mode::Sql { sql, csv } => {
+            let (columns, rows) = store.read_only_query(&sql).await?;
+            if csv {
+                print_csv(&columns, &rows)
+            } else {
+                print_table(&columns, &rows)
+            }
+        }
+        Mode::Merge {
+            keep_id,
+            dup_id,
+            out,
+        } => {
+            store.merge_notes(keep_id, dup_id).await?;
+            out.print(&[store.get_dated_note(keep_id).await?], format)?;
+        }
+        Mode::Template { action } => match action {
+            TemplateAction::Edit { weekday } => edit_template(store, weekday).await?,
+        },
+    }
+    Ok(())
+}
+/// Run a command against a remote store, only adding, showing and cancelling notes are supported.
+async fn run_remote(
+    remote: &RemoteStore,
+    config: &Config,
+    args: Mode,
+    format: Format,
+) -> Result<()> {
+    match args {
+        Mode::New {
+            body,
+            day,
+            secret: false,
+            template: None,
+            file: None,
+            date: None,
+            out,
+            ..
+        } => {
+            let date = map_day(Local::now(), day);
+            let note = remote.insert_note_on(date, &body.join(" ")).await?;
+            out.print(&[(date, note)], format)?;
+        }
+        Mode::Show {
+            day,
+            period,
+            week,
+            from: None,
+            args:
+                args @ ShowArgs {
+                    person: None,
+                    at: None,
+                    tag: None,
+                    include_archives: false,
+                    reveal: false,
+                    long: false,
+                    ..
+                },
+            ..
+        } => {
+            let (start_day, end_day) = match (week, period) {
+                (Some(monday), _) => (monday, monday + Days::new(6)),
+                (None, None) => (map_day(Local::now(), day), map_day(Local::now(), day)),
+                (None, Some(p)) => period_range(config, day, p),
+            };
+            let plugins = load_plugins(&args)?;
+            for mut notes in remote.get_day_notes_in_range(start_day, end_day).await? {
+                filter_plugin(plugins.as_ref(), std::slice::from_mut(&mut notes), &args)?;
+                let day = pretty_day(&notes, &args, plugins.as_ref())?;
+                if !day.is_empty() {
+                    println!("{}", day);
+                }
+            }
+        }
+        Mode::Cancel { id, out } => {
+            let note = remote.set_status(id, Status::Cancelled).await?;
+            match note.date {
+                Some(date) => out.print(&[(date, note)], format)?,
+                None if !out.quiet => println!("{}", note.pretty_term()),
+                None => {}
+            }
+        }
+        _ => {
+            return Err(anyhow!(
+                "Only new, show and cancel work with a remote store, drop --remote, unset FH_REMOTE or [remote] url, or pick a database with --db."
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Commented config written by `fh init`, every key falls back to its default.
+const CONFIG_TEMPLATE: &str = r#"# fh config, uncomment keys to change them.
+# prompts = ["What went well?"]
+# buffer_format = "legacy"
+# header_format = "%Y-%m-%d (%A)"
+# week_start = "sunday"
+# duplicate_window_days = 7
+# edit_delete_limit = 3
+# focus_limit = 3
+# workspace = "work"
+# trash_retention_days = 30
+# [hooks]
+# on_note_created = "notify-send 'New note'"
+"#;
+
+/// Hard delete notes deleted longer than `trash_retention_days` ago, nothing when it's unset.
+async fn purge_trash(store: &NoteStore, config: &Config) -> Result<u64> {
+    let Some(days) = config.trash_retention_days else {
+        return Ok(0);
+    };
+    let before = Utc::now() - Days::new(days as u64);
+    Ok(store.purge_deleted(before).await?)
+}
+
+/// Set up the config directory, config file and database, printing what was created.
+async fn init(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        println!("Found {}", dir.display());
+    } else {
+        std::fs::create_dir_all(dir).context(format!("Failed creating {}.", dir.display()))?;
+        println!("Created {}", dir.display());
+    }
+    let config_path = dir.join("config.toml");
+    if config_path.exists() {
+        println!("Found {}", config_path.display());
+    } else {
+        std::fs::write(&config_path, CONFIG_TEMPLATE)
+            .context(format!("Failed writing {}.", config_path.display()))?;
+        println!("Created {}", config_path.display());
+    }
+    let db_path = config::db_path(dir);
+    let existed = db_path.exists();
+    NoteStore::open(&db_path).await?;
+    println!(
+        "{} {}",
+        if existed { "Found" } else { "Created" },
+        db_path.display()
+    );
+    Ok(())
+}
+
+/// Print the completion script for `shell`.
+fn completions(shell: Shell, r#static: bool) -> Result<()> {
+    let mut out = std::io::stdout();
+    if r#static {
+        clap_complete::generate(shell, &mut Cli::command(), "fh", &mut out);
+        return Ok(());
+    }
+    let name = shell.to_string();
+    let shells = Shells::builtins();
+    let completer = shells.completer(&name).ok_or(anyhow!(
+        "No dynamic completions for {}, use --static.",
+        name
+    ))?;
+    completer.write_registration("COMPLETE", "fh", "fh", "fh", &mut out)?;
+    Ok(())
+}
+
+/// Replica url from `--bucket` or the `replica` config.
+fn replica_url(config: &Config, bucket: Option<&str>) -> Result<String> {
+    bucket
+        .or(config.replica.as_deref())
+        .map(replicate::replica_url)
+        .ok_or(anyhow!("Pass --bucket or set replica in the config."))
+}
+
+/// The request a daemon can answer for `args`, if any.
+fn daemon_request(args: &Mode, config: &Config) -> Option<daemon::Request> {
+    match args {
+        Mode::New {
+            body,
+            day,
+            force,
+            secret: false,
+            fetch_title: false,
+            template: None,
+            file: None,
+            date: None,
+            ..
+        } if !config.fetch_titles => {
+            let date = map_day(Local::now(), *day);
+            let duplicate_since = (!force).then(|| {
+                date.checked_sub_days(Days::new(config.duplicate_window_days as u64))
+                    .expect("Don't account for leap")
+            });
+            Some(daemon::Request::New {
+                date,
+                body: body.join(" "),
+                duplicate_since,
+            })
+        }
+        Mode::Show {
+            day,
+            period: None,
+            week: None,
+            args:
+                ShowArgs {
+                    person: None,
+                    at: None,
+                    tag: None,
+                    include_archives: false,
+                    reveal: false,
+                    long: false,
+                    ago: false,
+                    skip_empty: false,
+                    summary: false,
+                    render: None,
+                    plugin_filter: None,
+                },
+            from: None,
+            ..
+        } => Some(daemon::Request::Show {
+            date: map_day(Local::now(), *day),
+        }),
+        _ => None,
+    }
+}
+fn map_day<Tz>(start_datetime: DateTime<Tz>, day: Option<i32>) -> NaiveDate
+where
+    Tz: TimeZone,
+{
+    let Some(day) = day else {
+        return start_datetime.naive_utc().date();
+    };
+    let target_datetime = if day > 0 {
+        start_datetime
+            .checked_add_days(Days::new(day as u64))
+            .expect("Don't account for leap")
+    } else {
+        start_datetime
+            .checked_sub_days(Days::new(day.unsigned_abs() as u64))
+            .expect("Don't account for leap")
+    };
+    target_datetime.naive_utc().date()
+}
+
+/// Run the edit subcommand open the prefered editor (should be vim)
+/// get the daily notes and update any changes made by the user.
+#[tracing::instrument(skip(store, config))]
+async fn edit(
+    store: &NoteStore,
+    config: &Config,
+    day: Option<i32>,
+    force: bool,
+    yes: bool,
+) -> Result<()> {
+    let target_day = map_day(Local::now(), day);
+    let pid = process::id();
+    if let Some(lock) = store.edit_lock(target_day).await?
+        && lock.pid != pid
+        && !lock_is_stale(&lock)
+        && !force
+    {
+        return Err(anyhow!(
+            "{} is open in another edit session, process {} since {}. Use --force to edit it anyway.",
+            target_day,
+            lock.pid,
+            lock.locked_at.with_timezone(&Local).format("%H:%M")
+        ));
+    }
+    store.lock_day(target_day, pid).await?;
+    let edited = edit_day(store, config, target_day, yes).await;
+    store.unlock_day(target_day, pid).await?;
+    edited
+}
+
+/// Run the plan subcommand, editing `count` days from `first_day` in one buffer.
+async fn plan(
+    store: &NoteStore,
+    config: &Config,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+    yes: bool,
+) -> Result<()> {
+    let days = first_day
+        .iter_days()
+        .take_while(|d| *d <= last_day)
+        .collect::<Vec<_>>();
+    let pid = process::id();
+    for day in &days {
+        if let Some(lock) = store.edit_lock(*day).await?
+            && lock.pid != pid
+            && !lock_is_stale(&lock)
+        {
+            return Err(anyhow!(
+                "{} is open in another edit session, process {}.",
+                day,
+                lock.pid
+            ));
+        }
+    }
+    for day in &days {
+        store.lock_day(*day, pid).await?;
+    }
+    let planned = plan_days(store, config, &days, yes).await;
+    for day in &days {
+        store.unlock_day(*day, pid).await?;
+    }
+    planned
+}
+
+async fn plan_days(
+    store: &NoteStore,
+    config: &Config,
+    days: &[NaiveDate],
+    yes: bool,
+) -> Result<()> {
+    let mut buffers = Vec::with_capacity(days.len());
+    for day in days {
+        let notes = store.get_days_notes(*day).await?;
+        buffers.push(day_buffer(store, config, &notes).await?);
+    }
+    let edited = open_in_editor(&buffers.join("\n\n"))?;
+    for saved in parse_notes_string(edited, store, config, yes).await? {
+        if days.contains(&saved.date) {
+            config.hooks.day_saved(&saved);
+        }
+    }
+    Ok(())
+}
+
+/// Whether an edit lock was left behind, its process has exited or it's older than
+/// [`EDIT_LOCK_HOURS`].
+fn lock_is_stale(lock: &store::EditLock) -> bool {
+    if Utc::now() - lock.locked_at > TimeDelta::hours(EDIT_LOCK_HOURS) {
+        return true;
+    }
+    // `kill -0` only checks the process exists.
+    !process::Command::new("kill")
+        .args(["-0", &lock.pid.to_string()])
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Editor buffer for a day, an empty day is filled from its weekday's template.
+async fn day_buffer(store: &NoteStore, config: &Config, notes: &DayNotes) -> Result<String> {
+    let template = if notes.is_empty() {
+        store.get_template_for(notes.date.weekday()).await?
+    } else {
+        None
+    };
+    Ok(match template {
+        Some(template) => notes.template_md(&template, config.header_format.as_deref()),
+        None => notes.pretty_md_with_prompts(
+            &config.prompts,
+            config.buffer_format,
+            config.header_format.as_deref(),
+        ),
+    })
+}
+
+async fn edit_day(
+    store: &NoteStore,
+    config: &Config,
+    target_day: NaiveDate,
+    yes: bool,
+) -> Result<()> {
+    let notes = store.get_days_notes(target_day).await?;
+    let buffer = day_buffer(store, config, &notes).await?;
+    let metrics = store.day_metrics(target_day).await?;
+    let buffer = mood::insert_metrics_line(&buffer, &metrics);
+    let meta = store.day_meta(target_day).await?;
+    let buffer = format!("{}{}", frontmatter_md(&meta), buffer);
+    let new_notes = open_in_editor(&buffer)?;
+    let (edited_meta, new_notes) = split_frontmatter(&new_notes)?;
+    let (new_notes, edited) = mood::take_metrics_line(new_notes)?;
+    let header_format = config.header_format.as_deref();
+    let move_to = moved_header(&new_notes, &buffer, target_day, header_format)?;
+    let new_notes = match move_to {
+        // Save the buffer as the day it was opened as, then move the day over.
+        Some(_) => restore_header(&new_notes, &buffer),
+        None => new_notes.to_string(),
+    };
+    // Other days added to the buffer are saved too, the edited day is the first.
+    let saved = parse_notes_string(new_notes, store, config, yes)
+        .await?
+        .swap_remove(0);
+    let saved_date = move_to.unwrap_or(target_day);
+    if edited_meta != meta || move_to.is_some() {
+        store.set_day_meta(saved_date, &edited_meta).await?;
+    }
+    // Deleting the line clears the day's metrics.
+    let edited = edited.unwrap_or_default();
+    if edited != metrics || move_to.is_some() {
+        store.set_day_metrics(saved_date, &edited).await?;
+    }
+    let saved = match move_to {
+        Some(date) => {
+            store.set_day_meta(target_day, &Default::default()).await?;
+            store
+                .set_day_metrics(target_day, &Default::default())
+                .await?;
+            let moved = store.move_day(target_day, date).await?;
+            println!("Moved {} notes from {} to {}.", moved, target_day, date);
+            store.get_days_notes(date).await?
+        }
+        None => saved,
+    };
+    config.hooks.day_saved(&saved);
+    Ok(())
+}
+
+/// The date the header of an edited buffer was changed to, when the day should move there.
+/// Asks whether to move the day, erroring without saving anything when it shouldn't.
+fn moved_header(
+    edited: &str,
+    buffer: &str,
+    target_day: NaiveDate,
+    header_format: Option<&str>,
+) -> Result<Option<NaiveDate>> {
+    let date = find_date(&mut edited.lines(), header_format)?;
+    if date == target_day {
+        return Ok(None);
+    }
+    // A header format fh can't read back as the day it wrote can't tell a move apart.
+    if find_date(&mut buffer.lines(), header_format)? != target_day {
+        return Ok(None);
+    }
+    if confirm(&format!(
+        "The header changed from {} to {}, move the day's notes and text to {}?",
+        target_day, date, date
+    ))? {
+        return Ok(Some(date));
+    }
+    Err(anyhow!(
+        "Nothing was saved, the header date of {} changed to {}. Edit {} to add notes to it.",
+        target_day,
+        date,
+        date
+    ))
+}
+
+/// `edited` with its header line put back to the header of `buffer`.
+fn restore_header(edited: &str, buffer: &str) -> String {
+    let is_header = |l: &&str| l.trim().starts_with("# ");
+    let Some(header) = buffer.lines().find(is_header) else {
+        return edited.to_string();
+    };
+    let mut restored = false;
+    edited
+        .lines()
+        .map(|l| {
+            if !restored && is_header(&l) {
+                restored = true;
+                header
+            } else {
+                l
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `new --clipboard` with the clipboard's text read into the body.
+fn with_clipboard(mut args: Mode) -> Result<Mode> {
+    if let Mode::New {
+        body,
+        clipboard: clipboard @ true,
+        ..
+    } = &mut args
+    {
+        let text = arboard::Clipboard::new()
+            .and_then(|mut c| c.get_text())
+            .context("Failed reading text from the clipboard.")?;
+        *body = vec![clipboard_body(&body.join(" "), &text)?];
+        *clipboard = false;
+    }
+    Ok(args)
+}
+
+/// `prefix` followed by the clipboard text on one line, as a note is a single line.
+fn clipboard_body(prefix: &str, text: &str) -> Result<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return Err(anyhow!("The clipboard holds no text."));
+    }
+    Ok(match prefix.trim() {
+        "" => text,
+        prefix => format!("{} {}", prefix, text),
+    })
+}
+
+/// Run the new subcommand, refusing to add a note that duplicates a recent open note unless forced.
+/// Secret notes are encrypted and skip the duplicate check.
+async fn new_note(
+    store: &NoteStore,
+    config: &Config,
+    body: String,
+    day: Option<i32>,
+    force: bool,
+    secret: bool,
+    fetch_title: bool,
+) -> Result<(NaiveDate, Note)> {
+    let target_day = map_day(Local::now(), day);
+    if secret {
+        let recipient = config.age_recipient.as_deref().ok_or(anyhow!(
+            "Set age_recipient in the config to add secret notes."
+        ))?;
+        let body = secret::encrypt(&body, recipient)?;
+        let note = store.insert_note_on(target_day, NewNote::new(body)).await?;
+        return Ok((target_day, note));
+    }
+    if !force {
+        let since = target_day
+            .checked_sub_days(Days::new(config.duplicate_window_days as u64))
+            .expect("Don't account for leap");
+        if let Some((date, dup)) = store.find_open_duplicate(&body, since).await? {
+            eprintln!("Found open note on {}:\n{}", date, dup.pretty());
+            if date != target_day
+                && confirm(&format!("Move note {} to {} instead?", dup.id, target_day))?
+            {
+                store.move_note(dup.id, target_day).await?;
+                return Ok((target_day, dup));
+            }
+            return Err(anyhow!(
+                "Not adding duplicate note, use --force to add it anyway."
+            ));
+        }
+    }
+    let url = (fetch_title && bookmark::is_url(&body)).then(|| body.trim().to_string());
+    let body = match &url {
+        Some(url) => match bookmark::fetch_title(url).await {
+            Ok(Some(title)) => format!("{} {}", title, url),
+            Ok(None) => body,
+            Err(e) => {
+                tracing::warn!("Saving the url without a title: {:#}", e);
+                body
+            }
+        },
+        None => body,
+    };
+    let note = store.insert_note_on(target_day, NewNote::new(body)).await?;
+    store.index_note_body(&note).await?;
+    if let Some(url) = url {
+        store.set_meta(note.id, bookmark::KEY, &url).await?;
+    }
+    Ok((target_day, note))
+}
+
+/// Add the notes of a template from the config directory.
+async fn new_from_template(
+    store: &NoteStore,
+    name: &str,
+    vars: &[(String, String)],
+    day: Option<i32>,
+) -> Result<Vec<(NaiveDate, Note)>> {
+    let target_day = map_day(Local::now(), day);
+    let source = template::load(&config::config_dir()?.join(template::DIR_NAME), name)?;
+    let notes = template::expand(&source, vars, target_day)?;
+    if notes.is_empty() {
+        return Err(anyhow!("Template {} has no notes.", name));
+    }
+    let mut added = Vec::new();
+    for new in notes {
+        let note = store.insert_note_on(target_day, new).await?;
+        store.index_note_body(&note).await?;
+        added.push((target_day, note));
+    }
+    Ok(added)
+}
+
+/// Add a note for each non-empty line of `path`, `-` for stdin, all or none of them.
+async fn new_from_file(
+    store: &NoteStore,
+    path: &Path,
+    day: Option<i32>,
+) -> Result<Vec<(NaiveDate, Note)>> {
+    let target_day = map_day(Local::now(), day);
+    let text = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).context("Failed reading stdin.")?
+    } else {
+        std::fs::read_to_string(path).context(format!("Failed reading {}.", path.display()))?
+    };
+    let notes = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| NewNote::builder().body(l).build())
+        .collect::<Vec<_>>();
+    if notes.is_empty() {
+        return Err(anyhow!("{} has no notes.", path.display()));
+    }
+    let notes = store.insert_notes_on(target_day, notes).await?;
+    for note in &notes {
+        store.index_note_body(note).await?;
+    }
+    Ok(notes.into_iter().map(|n| (target_day, n)).collect())
+}
+
+/// The day offset of `--day` or `--date`. An absolute date becomes an offset so every
+/// command sees the same day for it.
+fn day_arg(day: Option<i32>, date: Option<&str>) -> Result<Option<i32>> {
+    match date {
+        Some(date) => {
+            let date = query::parse_date(date, Local::now().date_naive())?;
+            Ok(Some((date - map_day(Local::now(), None)).num_days() as i32))
+        }
+        None => Ok(day),
+    }
+}
+
+/// Print every note that's a url, or was saved as one with its page title.
+async fn bookmarks(store: &NoteStore) -> Result<()> {
+    let saved = store.meta_values(bookmark::KEY).await?;
+    let notes = store.filtered_notes(&filter::NoteFilter::default()).await?;
+    for (date, note) in notes {
+        match saved.get(&note.id) {
+            Some(url) => {
+                let title = note.body.strip_suffix(url.as_str()).unwrap_or(&note.body);
+                println!("{} :{}: {} <{}>", date, note.id, title.trim(), url);
+            }
+            None if bookmark::is_url(&note.body) => {
+                println!("{} :{}: {}", date, note.id, note.body.trim());
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// Run the jira subcommands.
+async fn jira(store: &NoteStore, config: &Config, action: JiraAction) -> Result<()> {
+    let client = jira::JiraClient::from_config(&config.jira)?;
+    match action {
+        JiraAction::Add { key, day } => {
+            let key = key.to_uppercase();
+            if let Some(id) = store.linked_note(jira::SOURCE, &key).await? {
+                return Err(anyhow!("{} is already linked to note {}.", key, id));
+            }
+            let issue = client.issue(&key).await?;
+            let new = NewNote::builder()
+                .body(issue.note_body())
+                .completed(issue.is_done())
+                .build();
+            let note = store
+                .insert_note_on(map_day(Local::now(), day), new)
+                .await?;
+            store.link_note(note.id, jira::SOURCE, &issue.key).await?;
+            println!("{}", note.pretty());
+        }
+        JiraAction::Sync => {
+            for (id, key) in store.open_linked_notes(jira::SOURCE).await? {
+                let issue = match client.issue(&key).await {
+                    Ok(issue) => issue,
+                    Err(e) => {
+                        tracing::warn!("Skipping note {}: {:#}", id, e);
+                        continue;
+                    }
+                };
+                if issue.is_done() {
+                    let note = store.set_status(id, Status::Done).await?;
+                    println!(
+                        "{} is {}.\n{}",
+                        key,
+                        issue.fields.status.name,
+                        note.pretty()
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add today's commits by the user in `repos`, or the configured repositories, as completed
+/// notes. Commits already captured are found by the hash kept in the note's metadata.
+async fn git_capture(store: &NoteStore, config: &Config, repos: &[String]) -> Result<()> {
+    let patterns = if repos.is_empty() {
+        &config.git_repos
+    } else {
+        repos
+    };
+    if patterns.is_empty() {
+        return Err(anyhow!(
+            "No repositories to read, pass --repos or set git_repos in the config."
+        ));
+    }
+    let today = Local::now().date_naive();
+    let captured = store
+        .meta_values(git::COMMIT_KEY)
+        .await?
+        .into_values()
+        .collect::<HashSet<_>>();
+    let mut count = 0;
+    for repo in git::expand_repos(patterns)? {
+        let commits = match git::commits_since(&repo, today) {
+            Ok(commits) => commits,
+            Err(e) => {
+                tracing::warn!("Skipping {}: {:#}", repo.display(), e);
+                continue;
+            }
+        };
+        for commit in commits.into_iter().filter(|c| !captured.contains(&c.hash)) {
+            let new = NewNote::builder()
+                .body(commit.note_body())
+                .completed(true)
+                .build();
+            let note = store.insert_note_on(today, new).await?;
+            store
+                .set_meta(note.id, git::COMMIT_KEY, &commit.hash)
+                .await?;
+            println!("{}", note.pretty());
+            count += 1;
+        }
+    }
+    println!("Captured {} commits.", count);
+    Ok(())
+}
+
+/// Add a note for each message sent to the mail address, with the subject as its body and a
+/// link to the message in its metadata. Messages already ingested are found by that link.
+async fn ingest_mail(
+    store: &NoteStore,
+    config: &Config,
+    path: Option<&Path>,
+    address: Option<&str>,
+) -> Result<()> {
+    let path = path.or(config.mail.path.as_deref()).ok_or(anyhow!(
+        "No mailbox to read, pass a path or set path in the [mail] config."
+    ))?;
+    let address = address.or(config.mail.address.as_deref()).ok_or(anyhow!(
+        "No address to match, pass --address or set address in the [mail] config."
+    ))?;
+    let today = Local::now().date_naive();
+    let mut ingested = store
+        .meta_values(mail::LINK_KEY)
+        .await?
+        .into_values()
+        .collect::<HashSet<_>>();
+    let mut count = 0;
+    for message in mail::read_messages(path)? {
+        let Some(day) = message.day_for(address) else {
+            continue;
+        };
+        let link = message.link();
+        if link.as_ref().is_some_and(|l| ingested.contains(l)) || message.subject.is_empty() {
+            continue;
+        }
+        let date = match day.as_str() {
+            "" => today,
+            day => match query::parse_date(day, today) {
+                Ok(date) => date,
+                Err(e) => {
+                    tracing::warn!("Filing \"{}\" today: {:#}", message.subject, e);
+                    today
+                }
+            },
+        };
+        let note = store
+            .insert_note_on(date, NewNote::new(message.subject.clone()))
+            .await?;
+        store.index_note_body(&note).await?;
+        if let Some(link) = link {
+            store.set_meta(note.id, mail::LINK_KEY, &link).await?;
+            ingested.insert(link);
+        }
+        println!("{} {}", date, note.pretty());
+        count += 1;
+    }
+    println!("Ingested {} messages.", count);
+    Ok(())
+}
+
+/// Run the replace subcommand, printing every change made.
+async fn replace(
+    store: &NoteStore,
+    old: &str,
+    new: &str,
+    (start_day, end_day): (Option<NaiveDate>, Option<NaiveDate>),
+    dry_run: bool,
+    out: WriteOutput,
+    format: Format,
+) -> Result<()> {
+    let changes = store
+        .replace_text(old, new, start_day, end_day, dry_run)
+        .await?;
+    if format == Format::Json {
+        // Day text isn't a note, only the notes with their new bodies are printed.
+        let mut notes = vec![];
+        for (id, after) in changes
+            .iter()
+            .filter_map(|c| c.note_id.map(|id| (id, &c.after)))
+        {
+            let (date, note) = store.get_dated_note(id).await?;
+            notes.push((
+                date,
+                Note {
+                    body: after.clone(),
+                    ..note
+                },
+            ));
+        }
+        return out.print(&notes, format);
+    }
+    if out.quiet {
+        return Ok(());
+    }
+    for change in &changes {
+        match change.note_id {
+            Some(id) => println!(
+                "{} note {}: {} -> {}",
+                change.date, id, change.before, change.after
+            ),
+            None => {
+                let lines = change.before.lines().zip(change.after.lines());
+                for (before, after) in lines.filter(|(b, a)| b != a) {
+                    println!("{} text: {} -> {}", change.date, before, after);
+                }
+            }
+        }
+    }
+    let verb = if dry_run { "Would change" } else { "Changed" };
+    println!("{} {} entries.", verb, changes.len());
+    Ok(())
+}
+
+/// Run the bulk subcommand, previewing the matching notes and asking before changing them.
+async fn bulk(
+    store: &NoteStore,
+    action: BulkAction,
+    out: WriteOutput,
+    format: Format,
+) -> Result<()> {
+    let filter = match &action {
+        BulkAction::Done { filter } | BulkAction::Delete { filter } => filter,
+        BulkAction::Tag { filter, .. } => filter,
+    };
+    let notes = store.filtered_notes(filter).await?;
+    if notes.is_empty() {
+        return out.summarise(&[], format, "No notes match.");
+    }
+    for (date, note) in &notes {
+        eprintln!("{} {}", date, note.pretty());
+    }
+    if !confirm(&format!("Apply to {} notes?", notes.len()))? {
+        return Ok(());
+    }
+    let (changed, updated) = match &action {
+        BulkAction::Done { filter } => {
+            let updated = store.bulk_complete(filter).await?;
+            (updated.len() as u64, updated)
+        }
+        BulkAction::Delete { filter } => {
+            let updated = store.bulk_delete(filter).await?;
+            (updated.len() as u64, updated)
+        }
+        BulkAction::Tag { tag, filter } => (store.bulk_tag(filter, tag).await?, vec![]),
+    };
+    // Print the notes as they are after the change, with the day each was listed on.
+    let notes = notes
+        .into_iter()
+        .map(
+            |(date, note)| match updated.iter().find(|n| n.id == note.id) {
+                Some(n) => (
+                    date,
+                    Note {
+                        date: note.date,
+                        ..n.clone()
+                    },
+                ),
+                None => (date, note),
+            },
+        )
+        .collect::<Vec<_>>();
+    out.summarise(&notes, format, &format!("Changed {} notes.", changed))
+}
+
+/// `--quiet` of the commands changing notes.
+#[derive(Args, Debug, Default, Clone, Copy)]
+struct WriteOutput {
+    /// Don't print the changed notes.
+    #[arg(short, long)]
+    quiet: bool,
+}
+impl WriteOutput {
+    /// Print the changed notes in the global `--format`.
+    fn print(&self, notes: &[(NaiveDate, Note)], format: Format) -> Result<()> {
+        if self.quiet {
+            return Ok(());
+        }
+        print_notes(notes, format)
+    }
+    /// Print the changed notes for `--format json`, otherwise a summary of the change.
+    fn summarise(&self, notes: &[(NaiveDate, Note)], format: Format, summary: &str) -> Result<()> {
+        match format {
+            Format::Json => self.print(notes, format)?,
+            _ if !self.quiet => println!("{}", summary),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Print notes with the date they belong to.
+fn print_notes(notes: &[(NaiveDate, Note)], format: Format) -> Result<()> {
+    match format {
+        Format::Json => {
+            let out = notes
+                .iter()
+                .map(|(date, note)| Note {
+                    date: Some(*date),
+                    ..note.clone()
+                })
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string_pretty(&out)?);
+        }
+        _ => {
+            for (date, note) in notes {
+                println!("{} {}", date, note.pretty());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Hand the global `--format` to the commands with formats of their own, returning the format
+/// the other commands print notes in.
+fn with_format(mut mode: Mode, format: Option<Format>) -> Result<(Mode, Format)> {
+    let unsupported = |command: &str, format: Format| {
+        let name = format.to_possible_value().expect("no skipped formats");
+        anyhow!("{} can't use --format {}.", command, name.get_name())
+    };
+    match &mut mode {
+        Mode::Report {
+            format: report_format,
+            ..
+        } => {
+            *report_format = match format {
+                None | Some(Format::Text) => ReportFormat::Text,
+                Some(Format::Md) => ReportFormat::Md,
+                Some(Format::Html) => ReportFormat::Html,
+                Some(f) => return Err(unsupported("report", f)),
+            }
+        }
+        Mode::Export {
+            format: export_format,
+            ..
+        } => {
+            *export_format = match format {
+                None | Some(Format::Md) => ExportFormat::Md,
+                Some(Format::Html) => ExportFormat::Html,
+                Some(f) => return Err(unsupported("export", f)),
+            }
+        }
+        Mode::Import {
+            format: import_format,
+            ..
+        } => {
+            *import_format = match format {
+                None | Some(Format::Csv) => ImportFormat::Csv,
+                Some(f) => return Err(unsupported("import", f)),
+            }
+        }
+        _ => {
+            return match format {
+                None => Ok((mode, Format::Text)),
+                Some(f @ (Format::Text | Format::Json)) => Ok((mode, f)),
+                Some(f) => {
+                    let name = f.to_possible_value().expect("no skipped formats");
+                    Err(anyhow!(
+                        "Notes print as text or json, not {}.",
+                        name.get_name()
+                    ))
+                }
+            };
+        }
+    }
+    Ok((mode, Format::Text))
+}
+
+/// Run the report subcommand, summarising the notes of the last period.
+async fn report(
+    store: &NoteStore,
+    project: Option<String>,
+    period: Period,
+    format: ReportFormat,
+    include_private: bool,
+) -> Result<()> {
+    let today = Local::now().date_naive();
+    let start_day = map_day(Local::now(), Some(-(period.to_day_count() as i32)));
+    let mut terms = vec![Term::Day(Cmp::Ge, start_day)];
+    if let Some(project) = &project {
+        terms.push(Term::Project(
+            project.trim_start_matches('+').to_lowercase(),
+        ));
+    }
+    let mut notes = store.query_notes(&Query { terms }).await?;
+    if !include_private {
+        let private = store.private_note_ids().await?;
+        notes.retain(|(_, n)| !private.contains(&n.id));
+    }
+    // Group by project, falling back to the first tag.
+    let mut groups = store
+        .tags_by_note()
+        .await?
+        .into_iter()
+        .filter_map(|(id, tags)| Some((id, format!("#{}", tags.into_iter().next()?))))
+        .collect::<HashMap<_, _>>();
+    for (id, project) in store.projects_by_note().await? {
+        groups.insert(id, format!("+{}", project));
+    }
+    let days = store.get_day_notes_in_range(start_day, today).await?;
+    let title = match &project {
+        Some(p) => format!(
+            "+{} from {} to {}",
+            p.trim_start_matches('+'),
+            start_day,
+            today
+        ),
+        None => format!("From {} to {}", start_day, today),
+    };
+    let report = Report::new(title, today, notes, &groups, &days);
+    match format {
+        ReportFormat::Text => print!("{}", report.pretty()),
+        ReportFormat::Md => print!("{}", report.markdown()),
+        ReportFormat::Html => print!("{}", report.html()),
+    }
+    Ok(())
+}
+
+/// Run the share subcommand, saving a printable snapshot of the days without private notes
+/// for `serve` to hand out until it expires.
+async fn share(
+    store: &NoteStore,
+    config: &Config,
+    day: Option<i32>,
+    period: Option<Period>,
+    expires: &str,
+) -> Result<()> {
+    let expires_at = Utc::now() + query::parse_duration(expires)?;
+    let end_day = map_day(Local::now(), day);
+    let span = period.map(Period::to_day_count).unwrap_or(1);
+    let start_day = end_day
+        .checked_sub_days(Days::new(span as u64 - 1))
+        .expect("Don't account for leap");
+    let mut days = store.get_day_notes_in_range(start_day, end_day).await?;
+    let private = store.private_note_ids().await?;
+    for day in days.iter_mut() {
+        day.retain_notes(|n| !private.contains(&n.id));
+    }
+    let html = export::days_html(&days, start_day, end_day, true);
+    let token = server::random_token();
+    store.create_share(&token, &html, expires_at).await?;
+    println!("{}", server::share_url(&config.server, &token));
+    println!(
+        "Expires {}, served while `fh serve` runs.",
+        expires_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+    );
+    Ok(())
+}
+
+/// Run an incremental export, printing a JSON line per note changed since the last run
+/// and then moving the high-water mark up to the latest change printed.
+async fn export_since_last(store: &NoteStore, include_private: bool) -> Result<()> {
+    let mark = store.export_mark(EXPORT_MARK).await?;
+    let changed = store.notes_changed_since(mark.as_deref()).await?;
+    let private = if include_private {
+        HashSet::new()
+    } else {
+        store.private_note_ids().await?
+    };
+    let mut stdout = std::io::stdout().lock();
+    for c in changed.iter().filter(|c| !private.contains(&c.note.id)) {
+        let line = serde_json::json!({
+            "id": c.note.id,
+            "date": c.date,
+            "body": c.note.body,
+            "status": c.note.status.as_str(),
+            "section": c.note.section,
+            "due": c.note.due,
+            "changed_at": c.changed_at,
+            "deleted": c.deleted,
+        });
+        writeln!(stdout, "{}", line)?;
     }
-    if !db_path.exists() {
-        File::create(&db_path)?;
+    stdout.flush()?;
+    if let Some(last) = changed.last() {
+        store.set_export_mark(EXPORT_MARK, &last.changed_at).await?;
     }
-    let store = setup_db(&format!("sqlite:///{}", &db_path.to_str().unwrap())).await;
-    env_logger::init_from_env(Env::new().default_filter_or("critical"));
+    Ok(())
+}
 
-    match args {
-        Mode::Edit { day } => {
-            edit(&store, day).await?;
-            show(&store, day).await?;
+/// Run the import subcommand, adding a note for each CSV row on the day in its date column.
+async fn import_csv(
+    store: &NoteStore,
+    file: &Path,
+    map: &ColumnMap,
+    header: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let text =
+        std::fs::read_to_string(file).context(format!("Failed reading {}.", file.display()))?;
+    let records = import::parse_csv(&text)?;
+    let skip = if header { 1 } else { 0 };
+    let rows = records
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .map(|(i, record)| map.row(record, i + 1))
+        .collect::<Result<Vec<_>>>()?;
+    for row in &rows {
+        let note = NewNote::builder()
+            .body(row.body.clone())
+            .status(row.status)
+            .section(row.section.clone())
+            .build();
+        if dry_run {
+            println!("{} {}", row.date, note.to_note(0).pretty());
+            continue;
         }
-        Mode::Check => {
-            let day = Local::now().date_naive();
-            let notes = store.get_days_notes(day).await?;
-            if notes.note_count == 0 {
-                edit(&store, None).await?
-            } else {
-                show_range(&store, None, Period::Week.to_day_count()).await?
+        let note = store.insert_note_on(row.date, note).await?;
+        store.index_note_body(&note).await?;
+    }
+    let verb = if dry_run { "Would import" } else { "Imported" };
+    println!("{} {} notes.", verb, rows.len());
+    Ok(())
+}
+
+/// Run the next subcommand, listing the most urgent open notes across all days.
+async fn next(store: &NoteStore, config: &Config, top: usize) -> Result<()> {
+    let today = Local::now().date_naive();
+    let open = NoteFilter {
+        status: Some(StatusMatch::Open),
+        ..Default::default()
+    };
+    let notes = store.list_notes(&open, None).await?;
+    let tags = store.tags_by_note().await?;
+    let priorities = store.meta_values("priority").await?;
+    let mut scored = notes
+        .iter()
+        .map(|(date, note)| {
+            let input = UrgencyInput {
+                note,
+                date: *date,
+                tags: tags.get(&note.id).map(Vec::as_slice).unwrap_or_default(),
+                priority: priorities.get(&note.id).map(String::as_str),
+            };
+            (urgency(&input, today, &config.urgency), date, note)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (score, date, note) in scored.into_iter().take(top) {
+        println!("{:>5.1} {} {}", score, date, note.pretty_term());
+    }
+    Ok(())
+}
+
+/// Run the overdue subcommand, grouping open notes by how late they are.
+async fn overdue(store: &NoteStore) -> Result<()> {
+    let today = Local::now().date_naive();
+    let notes = store.overdue_notes(today).await?;
+    if notes.is_empty() {
+        println!("Nothing overdue.");
+        return Ok(());
+    }
+    let buckets = [
+        (1, "1 day late"),
+        (7, "Up to a week late"),
+        (30, "Up to a month late"),
+        (i64::MAX, "Over a month late"),
+    ];
+    let first = notes
+        .iter()
+        .map(|(date, note)| note.due.map_or(*date, |due| due.min(*date)))
+        .min()
+        .unwrap_or(today);
+    let skipped = store.skipped_days(first, today).await?;
+    let mut grouped: Vec<Vec<(NaiveDate, &Note)>> = vec![vec![]; buckets.len()];
+    let mut past_due = 0;
+    for (date, note) in &notes {
+        // A passed due date counts from the due date, otherwise from the day it was on.
+        let since = match note.due {
+            Some(due) if due < today => {
+                past_due += 1;
+                due
             }
+            _ => *date,
+        };
+        // Skipped days, like a vacation, don't make a note later.
+        let late =
+            (today - since).num_days() - skipped.range(since + Days::new(1)..).count() as i64;
+        let bucket = buckets.iter().position(|(max, _)| late <= *max).unwrap();
+        grouped[bucket].push((*date, note));
+    }
+    for ((_, title), notes) in buckets.iter().zip(grouped) {
+        if notes.is_empty() {
+            continue;
         }
-        Mode::Show { day, period } => match period {
-            None => show(&store, day).await?,
-            Some(p) => show_range(&store, day, p.to_day_count()).await?,
-        },
+        println!(
+            "{}",
+            color::style(Style::new().bold()).paint(format!("{}:", title))
+        );
+        for (date, note) in notes {
+            println!("{} {}", date, note.pretty());
+        }
+        println!();
     }
+    println!(
+        "{} overdue, {} past their due date, {} carried from previous days.",
+        notes.len(),
+        past_due,
+        notes.len() - past_due
+    );
     Ok(())
 }
-fn map_day<Tz>(start_datetime: DateTime<Tz>, day: Option<i32>) -> NaiveDate
-where
-    Tz: TimeZone,
-{
-    let Some(day) = day else {
-        return start_datetime.naive_utc().date();
-    };
-    let target_datetime = if day > 0 {
-        start_datetime
-            .checked_add_days(Days::new(day as u64))
-            .expect("Don't account for leap")
+
+/// Open a url with the desktop's default handler.
+fn open_url(url: &str) -> Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
     } else {
-        start_datetime
-            .checked_sub_days(Days::new(day.unsigned_abs() as u64))
-            .expect("Don't account for leap")
+        "xdg-open"
     };
-    target_datetime.naive_utc().date()
+    process::Command::new(opener)
+        .arg(url)
+        .status()
+        .context(format!("Failed running {} {}.", opener, url))?;
+    Ok(())
 }
 
-/// Run the edit subcommand open the prefered editor (should be vim)
-/// get the daily notes and update any changes made by the user.
-async fn edit(store: &NoteStore, day: Option<i32>) -> Result<()> {
+fn print_table(columns: &[String], rows: &[Vec<String>]) {
+    let mut widths = columns.iter().map(|c| c.len()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let format_row = |row: &[String]| {
+        row.iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    };
+    println!(
+        "{}",
+        color::style(Style::new().bold()).paint(format_row(columns))
+    );
+    for row in rows {
+        println!("{}", format_row(row));
+    }
+}
+fn print_csv(columns: &[String], rows: &[Vec<String>]) {
+    let escape = |value: &String| {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.clone()
+        }
+    };
+    for row in std::iter::once(columns).chain(rows.iter().map(|r| r.as_slice())) {
+        println!("{}", row.iter().map(escape).collect::<Vec<_>>().join(","));
+    }
+}
+
+/// Ask a yes/no question on the terminal, anything but y/yes is a no.
+fn confirm(question: &str) -> Result<bool> {
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Write `contents` to a temporary markdown file, open it in the prefered editor
+/// and return the file contents once the editor exits.
+fn open_in_editor(contents: &str) -> Result<String> {
     let editor = std::env::var("EDITOR").unwrap_or(String::from("vim"));
-    let target_day = map_day(Local::now(), day);
-    let notes = store.get_days_notes(target_day).await.unwrap();
     let mut file = NamedTempFile::with_suffix(".md")?;
     // Try happy path on failure clean the file.
-    file.write_all(notes.pretty_md().as_bytes())?;
+    file.write_all(contents.as_bytes())?;
     process::Command::new(editor).arg(file.path()).status()?;
-    let mut new_notes = String::new();
+    let mut out = String::new();
     file.seek(std::io::SeekFrom::Start(0))?;
-    file.read_to_string(&mut new_notes)?;
-    parse_notes_string(new_notes, store).await?;
+    file.read_to_string(&mut out)?;
+    Ok(out)
+}
+
+/// Edit the day template for `weekday`, or the default template used by every
+/// weekday without its own.
+async fn edit_template(store: &NoteStore, weekday: Option<Weekday>) -> Result<()> {
+    let name = template_name(weekday);
+    let template = store.get_template(&name).await?.unwrap_or_default();
+    let new_template = open_in_editor(&template)?;
+    store
+        .upsert_template(&name, new_template.trim_end())
+        .await?;
+    println!("Saved {} template.", name);
     Ok(())
 }
+fn template_name(weekday: Option<Weekday>) -> String {
+    weekday
+        .map(|w| w.to_string())
+        .unwrap_or(String::from(store::DEFAULT_TEMPLATE))
+}
+
+/// Days `show` prints for a period, the week holding the day starting on `week_start`,
+/// or the 30 days before it up to tomorrow.
+fn period_range(config: &Config, day: Option<i32>, period: Period) -> (NaiveDate, NaiveDate) {
+    match period {
+        Period::Week => query::week_range(map_day(Local::now(), day), config.week_start),
+        Period::Month => {
+            let day = day.unwrap_or(0);
+            let start_day = map_day(Local::now(), Some(-(period.to_day_count() as i32) + day));
+            (start_day, map_day(Local::now(), Some(1)))
+        }
+    }
+}
 
-async fn show_range(store: &NoteStore, day: Option<i32>, time_span: usize) -> Result<()> {
-    let day = day.unwrap_or(0);
-    let start_day = map_day(Local::now(), Some(-(time_span as i32) + day));
-    let end_day = map_day(Local::now(), Some(1));
-    log::info!("Fetching notes between {} and {}", start_day, end_day);
-    let all_notes = store
-        .get_day_notes_in_range(start_day, end_day)
+/// Print the days from `start_day` to `end_day`, under week headers with the week's totals
+/// when `by_week`.
+async fn show_range(
+    store: &NoteStore,
+    config: &Config,
+    start_day: NaiveDate,
+    end_day: NaiveDate,
+    by_week: bool,
+    args: &ShowArgs,
+) -> Result<()> {
+    info!("Fetching notes between {} and {}", start_day, end_day);
+    // Days are written as they're read so long ranges don't build up in memory.
+    let mut out = BufWriter::new(std::io::stdout());
+    let mut week = None;
+    let plugins = load_plugins(args)?;
+    let attachments = match args.long {
+        true => store.attachments_by_note().await?,
+        false => HashMap::new(),
+    };
+    store
+        .for_each_day_in_range(start_day, end_day, async |mut note| -> Result<()> {
+            let days = std::slice::from_mut(&mut note);
+            filter_shown(store, config, days, args).await?;
+            filter_plugin(plugins.as_ref(), days, args)?;
+            debug!("Found note {}: {}", note.date, note.note_count);
+            let note_week = query::week_range(note.date, config.week_start);
+            if by_week && week != Some(note_week) {
+                week = Some(note_week);
+                let (first, last) = (note_week.0.max(start_day), note_week.1.min(end_day));
+                let totals = store.range_totals(first, last).await?;
+                let header = format!(
+                    "Week of {}: {} created, {} completed, {} carried over",
+                    note_week.0, totals.created, totals.completed, totals.carried_over
+                );
+                write!(
+                    out,
+                    "{}\n\n",
+                    color::style(Style::new().bold().underline()).paint(header)
+                )?;
+            }
+            write!(out, "{}", pretty_day(&note, args, plugins.as_ref())?)?;
+            write!(out, "{}", pretty_attachments(&note, &attachments))?;
+            Ok(())
+        })
         .await
         .context("Failed querying all notes.")?;
-    let mut out = String::new();
-    for note in all_notes {
-        log::debug!("Found note {}: {}", note.date, note.note_count);
-        out.push_str(&note.pretty())
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+/// Write the days from `start_day` to `end_day` as markdown or html, one day at a time as
+/// they're read, so exporting years of notes keeps memory flat.
+async fn export_range(
+    store: &NoteStore,
+    start_day: NaiveDate,
+    end_day: NaiveDate,
+    html: bool,
+    printable: bool,
+    include_private: bool,
+) -> Result<()> {
+    let private = if include_private {
+        HashSet::new()
+    } else {
+        store.private_note_ids().await?
+    };
+    let mut out = BufWriter::new(std::io::stdout());
+    if html {
+        write!(out, "{}", export::html_start(start_day, end_day, printable))?;
     }
-    println!("{}", out);
+    let mut first = true;
+    store
+        .for_each_day_in_range(start_day, end_day, async |mut day| -> Result<()> {
+            day.retain_notes(|n| !private.contains(&n.id));
+            if day.is_empty() {
+                return Ok(());
+            }
+            match (html, first) {
+                (true, _) => write!(out, "{}", export::day_html(&day))?,
+                (false, true) => writeln!(out, "{}", day.pretty_md())?,
+                (false, false) => write!(out, "\n{}\n", day.pretty_md())?,
+            }
+            first = false;
+            Ok(())
+        })
+        .await?;
+    if html {
+        write!(out, "{}", export::html_end(printable))?;
+    }
+    out.flush()?;
     Ok(())
 }
+
 /// Run show sucommand, print current state to terminal.
-async fn show(store: &NoteStore, day: Option<i32>) -> Result<()> {
+async fn show(store: &NoteStore, config: &Config, day: Option<i32>, args: &ShowArgs) -> Result<()> {
     let target_day = map_day(Local::now(), day);
 
-    let notes = store.get_days_notes(target_day).await?;
+    let plugins = load_plugins(args)?;
+    let mut notes = store.get_days_notes(target_day).await?;
+    filter_shown(store, config, std::slice::from_mut(&mut notes), args).await?;
+    filter_plugin(plugins.as_ref(), std::slice::from_mut(&mut notes), args)?;
     info!("found {} notes for {}", notes.note_count, notes.date);
-    println!("{}", notes.pretty());
+    println!("{}", pretty_day(&notes, args, plugins.as_ref())?);
+    if args.long {
+        print!(
+            "{}",
+            pretty_attachments(&notes, &store.attachments_by_note().await?)
+        );
+    }
     Ok(())
 }
 
-/// Compare the current database state to that input by the user, perform the inserts and soft deltes required to
-/// maintain the state between the frontend (notes) and db.
-/// Would be much better to maintain a diff state and commit at the end,
-/// However I am a lazy man and sqlite is fast enough.
-/// Might actually write a better version of this. Its quite fun.
-async fn parse_notes_string(s: String, store: &NoteStore) -> Result<DayNotes> {
-    let mut line_iter = s.lines();
-    let mut date: Option<&str> = None;
-    while date.is_none() {
-        let Some(line) = line_iter.next() else {
-            return Err(anyhow!("Couldn't find text."));
-        };
-        if line.trim().is_empty() {
-            continue;
+/// The attachments of the day's notes, one line each, nothing when there are none.
+fn pretty_attachments(
+    day: &DayNotes,
+    attachments: &HashMap<u32, Vec<store::Attachment>>,
+) -> String {
+    let lines = day
+        .notes
+        .iter()
+        .flat_map(|n| attachments.get(&n.id).into_iter().flatten())
+        .map(|a| format!(" - :{}: {}\n", a.note_id, a.name))
+        .collect::<String>();
+    if lines.is_empty() {
+        return lines;
+    }
+    format!(
+        "{}\n{}",
+        color::style(Style::new().bold()).paint("Attachments:"),
+        lines
+    )
+}
+
+/// A day as `show` prints it, empty when `--skip-empty` leaves it out.
+fn pretty_day(notes: &DayNotes, args: &ShowArgs, plugins: Option<&lua::Plugins>) -> Result<String> {
+    if args.skip_empty && notes.is_empty() {
+        return Ok(String::new());
+    }
+    if let (Some(name), Some(plugins)) = (&args.render, plugins) {
+        return plugins.render(name, notes);
+    }
+    let today = args.ago.then(|| Local::now().date_naive());
+    Ok(match args.summary {
+        true => notes.pretty_summary(today),
+        false => notes.pretty_ago(today),
+    })
+}
+
+/// The Lua plugins when `--render` or `--plugin-filter` asks for them.
+fn load_plugins(args: &ShowArgs) -> Result<Option<lua::Plugins>> {
+    if args.render.is_none() && args.plugin_filter.is_none() {
+        return Ok(None);
+    }
+    let dir = config::config_dir()?.join(lua::DIR_NAME);
+    Ok(Some(lua::Plugins::load(&dir)?))
+}
+
+/// Drop the notes the `--plugin-filter` filter doesn't keep.
+fn filter_plugin(
+    plugins: Option<&lua::Plugins>,
+    days: &mut [DayNotes],
+    args: &ShowArgs,
+) -> Result<()> {
+    let (Some(name), Some(plugins)) = (&args.plugin_filter, plugins) else {
+        return Ok(());
+    };
+    for day in days.iter_mut() {
+        let mut kept = HashSet::new();
+        for note in &day.notes {
+            if plugins.keep(name, note)? {
+                kept.insert(note.id);
+            }
+        }
+        day.retain_notes(|n| kept.contains(&n.id));
+    }
+    Ok(())
+}
+
+/// Drop the notes the show options filter out.
+async fn filter_shown(
+    store: &NoteStore,
+    config: &Config,
+    days: &mut [DayNotes],
+    args: &ShowArgs,
+) -> Result<()> {
+    if args.include_archives {
+        fill_from_archives(store, days).await?;
+    }
+    if args.reveal {
+        reveal(config, days)?;
+    }
+    if let Some(person) = &args.person {
+        let ids = store.mentioned_note_ids(person).await?;
+        for day in days.iter_mut() {
+            day.retain_notes(|n| ids.contains(&n.id));
+        }
+    }
+    if let Some(context) = &args.at {
+        let ids = store.context_note_ids(context).await?;
+        for day in days.iter_mut() {
+            day.retain_notes(|n| ids.contains(&n.id));
+        }
+    }
+    if let Some(tag) = &args.tag {
+        let ids = store.tagged_note_ids(tag).await?;
+        for day in days.iter_mut() {
+            day.retain_notes(|n| ids.contains(&n.id));
         }
-        date = line.strip_prefix("# Today: ");
-        if date.is_none() {
-            date = line.strip_prefix("# Day: ")
-        }
-    }
-    let date = date.ok_or(anyhow!("Couldn't find text."))?;
-    let day = NaiveDate::from_str(date)?;
-    let mut day_notes = store.get_days_notes(day).await?;
-    let day_note_ids = day_notes.notes.iter().map(|n| n.id).collect::<Vec<u32>>();
-    let mut seen_notes = Vec::with_capacity(day_note_ids.len());
-    let mut free_text = String::new();
-    // Update notes by line.
-    for line in line_iter {
-        let line = line.trim();
-        if line.is_empty() {
+    }
+    Ok(())
+}
+
+/// Decrypt encrypted note bodies in place, notes that fail to decrypt stay hidden.
+fn reveal(config: &Config, days: &mut [DayNotes]) -> Result<()> {
+    let identity = config
+        .age_identity
+        .as_deref()
+        .ok_or(anyhow!("Set age_identity in the config to reveal notes."))?;
+    for note in days.iter_mut().flat_map(|d| d.notes.iter_mut()) {
+        if !secret::is_encrypted(&note.body) {
             continue;
         }
-        if &line[..3] == "---" {
-            break;
+        match secret::decrypt(&note.body, identity) {
+            Ok(body) => note.body = body.trim_end().to_string(),
+            Err(e) => tracing::warn!("Failed decrypting note {}: {:#}", note.id, e),
         }
-        match line.chars().next().unwrap() {
-            '-' => {
-                let Some(n) = Note::from_pretty(store, line)
-                    .await
-                    .context(format!("Failed parsing line {} to note.", &line))?
-                else {
-                    continue;
-                };
-                seen_notes.push(n.id);
-            }
-            _ => {
-                free_text.push_str(line);
-                free_text.push('\n');
+    }
+    Ok(())
+}
+
+/// Replace empty days with the same days from the archives.
+async fn fill_from_archives(store: &NoteStore, days: &mut [DayNotes]) -> Result<()> {
+    let (Some(first), Some(last)) = (days.first(), days.last()) else {
+        return Ok(());
+    };
+    let (start_day, end_day) = (first.date, last.date);
+    for path in store.archives().await? {
+        if !path.exists() {
+            tracing::warn!("Archive {} is missing.", path.display());
+            continue;
+        }
+        let archive = NoteStore::open(&path)
+            .await?
+            .with_workspace(store.workspace());
+        let archived = archive.get_day_notes_in_range(start_day, end_day).await?;
+        for found in archived.into_iter().filter(|a| !a.is_empty()) {
+            if let Some(day) = days
+                .iter_mut()
+                .find(|d| d.date == found.date && d.is_empty())
+            {
+                *day = found;
             }
         }
     }
-    if !free_text.is_empty() && free_text != day_notes.day_text {
-        day_notes.day_text = free_text;
-        store
-            .update_day_text(day_notes.date, &day_notes.day_text)
-            .await?;
+    Ok(())
+}
+
+/// Compare the current database state to that input by the user, perform the inserts and soft deltes required to
+/// maintain the state between the frontend (notes) and db.
+/// Each day runs from its `# ` header to its `---`, text after the last day is ignored.
+/// Every day is saved in one transaction. Removing more than `edit_delete_limit` notes from a
+/// day asks before deleting them, unless `yes` is given.
+#[tracing::instrument(skip_all)]
+async fn parse_notes_string(
+    s: String,
+    store: &NoteStore,
+    config: &Config,
+    yes: bool,
+) -> Result<Vec<DayNotes>> {
+    let header_format = config.header_format.as_deref();
+    let mut lines = s.lines();
+    let mut days = vec![ParsedDayNotes::parse_pretty_md_with_prompts(
+        &mut lines,
+        &config.prompts,
+        header_format,
+    )?];
+    while lines.clone().any(|l| l.trim().starts_with("# ")) {
+        days.push(ParsedDayNotes::parse_pretty_md_with_prompts(
+            &mut lines,
+            &config.prompts,
+            header_format,
+        )?);
     }
-    // Delete notes that have been removed.
-    for note_id in day_note_ids {
-        if !seen_notes.contains(&note_id) {
-            store.soft_delte_note_by_id(note_id).await?;
+    // A truncated buffer shouldn't empty the day unasked.
+    let mut keep = vec![];
+    for day in &days {
+        let before = store.get_days_notes(day.date).await?;
+        let removed = before
+            .notes
+            .iter()
+            .filter(|n| !day.all_notes().any(|p| p.id() == Some(n.id)))
+            .collect::<Vec<_>>();
+        if yes || removed.len() <= config.edit_delete_limit {
+            continue;
+        }
+        for note in &removed {
+            println!("{}", note.pretty());
+        }
+        let question = format!("Delete these {} notes from {}?", removed.len(), day.date);
+        if !confirm(&question)? {
+            keep.extend(removed.iter().map(|n| n.id));
+            println!("Kept the notes, pass --yes to delete them without asking.");
         }
     }
-    store.get_days_notes(day).await
+    Ok(store.persist_parsed_days(days, &keep).await?)
 }
 
+#[derive(Clone, Copy, Debug, Default)]
+enum ReportFormat {
+    #[default]
+    Text,
+    Md,
+    Html,
+}
+#[derive(Clone, Copy, Debug, Default)]
+enum ImportFormat {
+    #[default]
+    Csv,
+}
+#[derive(Clone, Copy, Debug, Default)]
+enum ExportFormat {
+    #[default]
+    Md,
+    Html,
+}
+/// Format of the global `--format`. Notes print as text or json, `report` also takes md or
+/// html, `export` md or html and `import` reads csv.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Md,
+    Html,
+    Csv,
+}
+#[derive(Subcommand, Debug)]
+enum BulkAction {
+    /// Complete matching notes.
+    Done {
+        #[arg(short, long)]
+        filter: NoteFilter,
+    },
+    /// Delete matching notes.
+    Delete {
+        #[arg(short, long)]
+        filter: NoteFilter,
+    },
+    /// Tag matching notes.
+    Tag {
+        #[arg(add = ArgValueCandidates::new(complete::tags))]
+        tag: String,
+        #[arg(short, long)]
+        filter: NoteFilter,
+    },
+}
+#[derive(Subcommand, Debug)]
+enum DayAction {
+    /// Delete a day's notes, kept in the trash, and clear its text.
+    Delete {
+        /// Day to delete as a date like `2025-04-01`, or `yesterday`.
+        #[arg(long)]
+        date: String,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Mark days as intentionally empty, so they don't break streaks or make notes overdue.
+    Skip {
+        /// First day to skip, a date or `today`, `tomorrow`, `eow`, `eom`.
+        #[arg(long)]
+        from: String,
+        /// Last day to skip.
+        #[arg(long)]
+        to: String,
+        /// Why the days are skipped, e.g. `vacation`.
+        #[arg(long)]
+        reason: Option<String>,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+}
+#[derive(Subcommand, Debug)]
+enum FocusAction {
+    /// Focus notes in order of importance, replacing the day's focus.
+    Set {
+        #[arg(required = true)]
+        ids: Vec<u32>,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+}
+#[derive(Subcommand, Debug)]
+enum MetaAction {
+    /// Set a metadata key on a note.
+    Set {
+        id: u32,
+        key: String,
+        value: String,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Print a note's metadata.
+    Get { id: u32 },
+}
+#[derive(Subcommand, Debug)]
+enum GtasksAction {
+    /// Authorise fh in the browser, storing a token in the config directory.
+    Login,
+    /// Push open notes as tasks, pull completions, renames and new tasks back.
+    Sync,
+}
+#[derive(Subcommand, Debug)]
+enum JiraAction {
+    /// Add a note for an issue with its summary, linked to the issue.
+    Add {
+        /// Issue key, e.g. `PROJ-123`.
+        key: String,
+        #[arg(short, long, default_value=None, allow_hyphen_values=true)]
+        day: Option<i32>,
+    },
+    /// Mark open linked notes done once their issue is done.
+    Sync,
+}
+#[derive(Subcommand, Debug)]
+enum AttachAction {
+    /// Open a note's attachments with the desktop's default programs.
+    Open { id: u32 },
+}
+#[derive(Subcommand, Debug)]
+enum MoodAction {
+    /// Sparklines of mood and energy, one bar per day.
+    Chart {
+        #[arg(long, value_enum, default_value = "week")]
+        period: Period,
+    },
+}
+#[derive(Subcommand, Debug)]
+enum GoalAction {
+    /// Add a goal for a quarter or a year.
+    Add {
+        title: String,
+        /// Quarter of the goal, e.g. `2025Q3`.
+        #[arg(long, value_parser = query::parse_goal_period, required_unless_present = "year", conflicts_with = "year")]
+        quarter: Option<String>,
+        #[arg(long)]
+        year: Option<u16>,
+    },
+    /// List the goals with the slug notes link to them by.
+    List {
+        /// Only goals for a quarter like `2025Q3` or a year.
+        #[arg(long, value_parser = query::parse_goal_period)]
+        period: Option<String>,
+    },
+    /// Open and completed notes linked to each goal, with the time logged on them.
+    Progress {
+        /// Only goals for a quarter like `2025Q3` or a year.
+        #[arg(long, value_parser = query::parse_goal_period)]
+        period: Option<String>,
+    },
+}
 #[derive(Subcommand, Debug)]
+enum PluginAction {
+    /// Copy a Lua plugin into the plugins directory, after checking it loads.
+    Install { file: PathBuf },
+    /// List the installed plugins with the renderers and filters each registers.
+    List,
+}
+#[derive(Subcommand, Debug)]
+enum TemplateAction {
+    /// Edit the template used to fill empty days, `{{date}}` expands to the day's date.
+    Edit {
+        /// Weekday the template applies to, all weekdays if omitted.
+        weekday: Option<Weekday>,
+    },
+}
+/// Options shared by the views printing days of notes.
+#[derive(Args, Debug, Default)]
+struct ShowArgs {
+    /// Only show notes mentioning a person.
+    #[arg(long, add = ArgValueCandidates::new(complete::people))]
+    person: Option<String>,
+    /// Only show open notes for a context like `office`, set by an `@office` token.
+    #[arg(long, value_name = "CONTEXT")]
+    at: Option<String>,
+    /// Only show notes with a tag, from `bulk tag` or a `#tag` word.
+    #[arg(long, add = ArgValueCandidates::new(complete::tags))]
+    tag: Option<String>,
+    /// Also read days moved out by `archive`.
+    #[arg(long)]
+    include_archives: bool,
+    /// Decrypt secret notes with the configured age identity.
+    #[arg(long)]
+    reveal: bool,
+    /// List each day's attachments after its notes.
+    #[arg(long)]
+    long: bool,
+    /// Add how long ago each day was next to its date, e.g. `3 days ago`.
+    #[arg(long, global = true)]
+    ago: bool,
+    /// Leave out days without notes or text.
+    #[arg(long, global = true)]
+    skip_empty: bool,
+    /// Print one line per day with its open and done counts, listing notes only for days with any.
+    #[arg(long, global = true)]
+    summary: bool,
+    /// Print each day with a renderer registered by a Lua plugin.
+    #[arg(long, value_name = "NAME", conflicts_with = "summary", global = true)]
+    render: Option<String>,
+    /// Only show notes a filter registered by a Lua plugin keeps.
+    #[arg(long, value_name = "NAME", global = true)]
+    plugin_filter: Option<String>,
+}
+#[derive(Subcommand, ValueEnum, Clone, Copy, Debug)]
 enum Period {
     Week,
     Month,
 }
 impl Period {
-    fn to_day_count(&self) -> usize {
-        match *self {
+    fn to_day_count(self) -> usize {
+        match self {
             Self::Week => 7,
             Self::Month => 30,
         }
     }
 }
-/// Mode enum descibes state that the program runs in, write or read mode.
+/// Command line with the flags shared by every subcommand.
 #[derive(Parser, Debug)]
+#[command(
+    after_help = "Exit codes: 0 success, 1 error, 2 no notes today (check --quiet), 3 parse failure, 4 day or note not found.\nAny other command runs the fh-<command> plugin from the PATH with FH_DB and FH_CONFIG_DIR set."
+)]
+struct Cli {
+    /// Log more, -v for info and -vv for debug with SQL statement timings. RUST_LOG overrides it.
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Append logs to this file instead of stderr.
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+    /// Use a fresh in-memory database, nothing is read from or written to the real one.
+    #[arg(long, global = true)]
+    ephemeral: bool,
+    /// Database file to use instead of the configured one.
+    #[arg(long, global = true, value_name = "PATH")]
+    db: Option<PathBuf>,
+    /// Server started with `fh serve` to use instead of the database, e.g.
+    /// `fh --remote https://fh.example.com new "call mum"`. Only new, show and cancel work with
+    /// it, the token comes from the [remote] config.
+    #[arg(long, global = true, value_name = "URL", conflicts_with_all = ["ephemeral", "db", "workspace"])]
+    remote: Option<String>,
+    /// Workspace of the database to read and write instead of the configured one, e.g.
+    /// `fh --workspace home show`. Notes of other workspaces are never shown or changed.
+    #[arg(long, global = true, value_name = "NAME")]
+    workspace: Option<String>,
+    /// When to style output, `auto` styles it for a terminal unless NO_COLOR is set.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    color: ColorChoice,
+    /// Print notes as text or json, e.g. `fh list --format json`. `report` also prints md or
+    /// html, `export` md (the default) or html, and `import` reads csv.
+    #[arg(long, global = true, value_enum)]
+    format: Option<Format>,
+    #[command(subcommand)]
+    mode: Mode,
+}
+/// Mode enum descibes state that the program runs in, write or read mode.
+#[derive(Subcommand, Debug)]
 enum Mode {
+    /// Create the config directory, config file and database.
+    Init,
+    /// Print a completion script, e.g. `fh completions bash > ~/.local/share/bash-completion/completions/fh`.
+    /// It asks fh for note ids, people and projects as you type.
+    Completions {
+        shell: Shell,
+        /// Print a script that only completes commands and flags, without running fh.
+        #[arg(long)]
+        r#static: bool,
+    },
     /// Check if new notes need to be added.
-    Check,
+    Check {
+        /// Print nothing, exit with 2 when today has no notes instead of opening the editor.
+        #[arg(short, long)]
+        quiet: bool,
+    },
     /// Edit current day's notes.
     ///
+    /// A frontmatter block of `key: value` lines between `---` above the header, or TOML
+    /// between `+++`, sets metadata on the day, e.g. its location or weather.
     Edit {
         #[arg(short, long, default_value=None, allow_hyphen_values=true)]
         day: Option<i32>,
+        /// Day to edit as a date like `2025-04-01`, or `yesterday`, `eow`, `eom`.
+        #[arg(long, conflicts_with = "day")]
+        date: Option<String>,
+        /// Edit the day even when another edit session has it open.
+        #[arg(long)]
+        force: bool,
+        /// Delete notes removed from the buffer without asking, however many there are.
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Edit several days in one buffer, one `# ` header and `---` per day.
+    Plan {
+        /// First day, relative to today.
+        #[arg(short, long, default_value=None, allow_hyphen_values=true)]
+        day: Option<i32>,
+        /// Plan the whole week holding the day, starting on the configured week_start, instead of
+        /// the 7 days from it.
+        #[arg(long)]
+        week: bool,
+        /// How many days to plan instead of a week.
+        #[arg(long, conflicts_with = "week", value_parser = clap::value_parser!(u32).range(1..=31))]
+        days: Option<u32>,
+        /// Delete notes removed from the buffer without asking, however many there are.
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Show current day's notes.
     Show {
@@ -215,14 +2865,589 @@ enum Mode {
         day: Option<i32>,
         #[command(subcommand)]
         period: Option<Period>,
+        /// Show an ISO week, Monday to Sunday, e.g. `2025-W14`.
+        #[arg(long, value_parser = query::parse_iso_week, conflicts_with = "day")]
+        week: Option<NaiveDate>,
+        /// First day of a range to page through, a date or `today`, `yesterday`, `eow`, `eom`.
+        #[arg(long, conflicts_with_all = ["day", "week"])]
+        from: Option<String>,
+        /// Last day of the range.
+        #[arg(long, default_value = "today", requires = "from")]
+        to: String,
+        /// Page of the range to show, counted from 1, only its days are loaded.
+        #[arg(long, default_value_t = 1, requires = "from")]
+        page: u32,
+        /// Days on each page.
+        #[arg(long, default_value_t = 31, value_parser = clap::value_parser!(u32).range(1..), requires = "from")]
+        per_page: u32,
+        #[command(flatten)]
+        args: ShowArgs,
+    },
+    /// Show the days a month, a year and two years ago, configured by `on_this_day`.
+    #[command(name = "onthisday")]
+    OnThisDay,
+    /// List the most urgent open notes across all days.
+    Next {
+        #[arg(short, long, default_value_t = 5)]
+        top: usize,
+    },
+    /// Print today's focused notes.
+    Focus {
+        #[command(subcommand)]
+        action: Option<FocusAction>,
+    },
+    /// Mark a note as cancelled, it stays in the history unlike a deleted note.
+    Cancel {
+        #[arg(add = ArgValueCandidates::new(complete::open_note_ids))]
+        id: u32,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Assign a note to a project, also set by a `+project` word in a note.
+    Assign {
+        #[arg(add = ArgValueCandidates::new(complete::open_note_ids))]
+        id: u32,
+        #[arg(add = ArgValueCandidates::new(complete::projects))]
+        project: String,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// List projects with their open and completed note counts.
+    Projects,
+    /// Summarise the notes of the last week or month, as text, markdown or html.
+    Report {
+        #[arg(long, add = ArgValueCandidates::new(complete::projects))]
+        project: Option<String>,
+        #[arg(long, value_enum, default_value = "week")]
+        period: Period,
+        /// Set from the global --format.
+        #[arg(skip)]
+        format: ReportFormat,
+        /// Include notes marked private.
+        #[arg(long)]
+        include_private: bool,
+    },
+    /// Export the days in a range, e.g. `--from 2025-10-01 --to 2025-10-31 --format html --printable`.
+    Export {
+        /// First day to export, a date or `today`, `yesterday`, `eow`, `eom`.
+        #[arg(long, required_unless_present = "since_last")]
+        from: Option<String>,
+        /// Last day to export.
+        #[arg(long, default_value = "today")]
+        to: String,
+        /// Set from the global --format.
+        #[arg(skip)]
+        format: ExportFormat,
+        /// Write a standalone html document with a print stylesheet, implies html.
+        #[arg(long)]
+        printable: bool,
+        /// Print notes changed since the last `--since-last` run as JSON lines, ignoring the range.
+        #[arg(long, conflicts_with_all = ["from", "printable"])]
+        since_last: bool,
+        /// Include notes marked private.
+        #[arg(long)]
+        include_private: bool,
+    },
+    /// List the notes that are urls, with the page titles saved by `new --fetch-title`.
+    Bookmarks,
+    /// Add each `.txt` or `.md` file dropped in a directory as a note in today's Inbox section,
+    /// moving it to the `archive` subdirectory. The daemon does this every minute for `capture_dir`.
+    CaptureDir {
+        /// Directory to read, `capture_dir` in the config when omitted.
+        dir: Option<PathBuf>,
+    },
+    /// Add today's commits in git repositories as completed notes, once per commit.
+    GitCapture {
+        /// Repositories to read, `git_repos` in the config when omitted. A path ending in `/*`
+        /// reads every repository in the directory.
+        #[arg(long, num_args = 1..)]
+        repos: Vec<String>,
+    },
+    /// Add notes from messages sent to the `[mail]` address, read from an mbox file or Maildir.
+    /// A `+day` suffix on the address, like `fh+tomorrow@me.com`, picks the note's day.
+    IngestMail {
+        /// mbox file or Maildir directory, `path` in the `[mail]` config when omitted.
+        path: Option<PathBuf>,
+        /// Address to match, `address` in the `[mail]` config when omitted.
+        #[arg(long)]
+        address: Option<String>,
+    },
+    /// Two-way sync of open notes with a Google Tasks list, configured in the `[gtasks]` table.
+    Gtasks {
+        #[command(subcommand)]
+        action: GtasksAction,
+    },
+    /// Notes linked to Jira issues, configured in the `[jira]` table.
+    Jira {
+        #[command(subcommand)]
+        action: JiraAction,
+    },
+    /// Keep the database open and answer `new` and `show` over a unix socket,
+    /// the CLI uses it whenever it is running.
+    Daemon,
+    /// Serve notes over HTTP to holders of a configured bearer token.
+    Serve {
+        /// Print a new token and the config lines enabling it, then exit.
+        #[arg(long)]
+        gen_token: bool,
+        /// Scope of the generated token.
+        #[arg(long, value_enum, default_value = "read", requires = "gen_token")]
+        scope: server::Scope,
+        /// Serve notes marked private.
+        #[arg(long)]
+        include_private: bool,
+    },
+    /// Share a read-only html view of a day or week, private notes left out, printing its url.
+    Share {
+        #[arg(short, long, default_value=None, allow_hyphen_values=true)]
+        day: Option<i32>,
+        /// Share the week or month up to the day rather than the day alone.
+        #[arg(long, value_enum)]
+        period: Option<Period>,
+        /// How long the link works, e.g. `30m`, `24h`, `7d`.
+        #[arg(long, default_value = "24h")]
+        expires: String,
+    },
+    /// Continuously back the database up to S3 compatible storage with litestream.
+    Replicate {
+        /// Replica as `bucket/path` or a litestream url, defaults to `replica` in the config.
+        #[arg(long)]
+        bucket: Option<String>,
+        /// Restore the database from the replica instead, it mustn't exist locally.
+        #[arg(long)]
+        restore: bool,
+    },
+    /// Exchange note changes with another fh database, the later change wins when both edited a note.
+    Sync {
+        /// Database to sync with, created if missing.
+        peer: PathBuf,
+    },
+    /// Mark a note private, leaving it out of exports and reports. Also set by `!!private` in a note.
+    Private {
+        #[arg(add = ArgValueCandidates::new(complete::open_note_ids))]
+        id: u32,
+        /// Make the note public again.
+        #[arg(long)]
+        off: bool,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// List deleted notes, most recently deleted first, as the day deleted, the note's day and note.
+    Trash {
+        /// Never purge this note once deleted.
+        #[arg(long, value_name = "ID")]
+        keep_forever: Option<u32>,
+        /// Let the note be purged again.
+        #[arg(long, requires = "keep_forever")]
+        undo: bool,
+        /// Purge notes deleted longer than `trash_retention_days` ago now.
+        #[arg(long, conflicts_with = "keep_forever")]
+        purge: bool,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Import notes from a file, creating days as needed.
+    Import {
+        file: PathBuf,
+        /// Set from the global --format.
+        #[arg(skip)]
+        format: ImportFormat,
+        /// Columns holding each field counting from 1, e.g. `body=2,date=1,completed=3`.
+        /// `section` is also accepted, dates are YYYY-MM-DD.
+        #[arg(long)]
+        map: ColumnMap,
+        /// Skip the first row.
+        #[arg(long)]
+        header: bool,
+        /// Print the notes without saving them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move days before a date into an archive database, `show --include-archives` still reads them.
+    Archive {
+        /// First day to keep, a date or `today`, `yesterday`, `eow`, `eom`.
+        #[arg(long)]
+        before: String,
+        /// Database to archive into, created if missing.
+        #[arg(long)]
+        to: PathBuf,
+    },
+    /// Print a one line summary of today for a shell prompt or status bar.
+    Status {
+        /// Template filled with `{open}`, `{done}` and `{total}` notes today, the `{streak}`
+        /// of days in a row with a completed note and the `{overdue}` note count.
+        #[arg(long, default_value = "{open}/{total}")]
+        template: String,
+        /// Print JSON for a waybar custom module, with today's notes as the tooltip and an
+        /// `overdue` class while notes are overdue. Use `"return-type": "json"` in the module.
+        #[arg(long)]
+        waybar: bool,
+    },
+    /// Send desktop notifications for notes due within `notify_window_days`, once per due date,
+    /// and for reminders that are due. Meant to run from cron or a systemd timer, the daemon
+    /// sends reminders on time.
+    Notify {
+        /// Print the notes that would be notified without notifying.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remind about a note at a time today, or tomorrow once it has passed. The daemon or
+    /// `notify` send a desktop notification, and a push when `[push]` is configured.
+    Remind {
+        #[arg(add = ArgValueCandidates::new(complete::open_note_ids))]
+        id: u32,
+        /// Time like `15:30`, `none` clears the reminder.
+        time: String,
+        /// Day to remind on, a date or `tomorrow`, `eow`, `eom`.
+        #[arg(long)]
+        date: Option<String>,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// List upcoming reminders of open notes, soonest first.
+    Reminders,
+    /// Install systemd units, or launchd agents on macOS, running the daemon and hourly
+    /// notifications, plus the server and replication when configured.
+    InstallService {
+        /// Install for the current user rather than system wide.
+        #[arg(long)]
+        user: bool,
+        /// Print the files instead of writing and enabling them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Run commands like `new …`, `cancel 3` and `show` from a prompt with history and tab
+    /// completion, keeping the database open between them.
+    Repl,
+    /// List people mentioned with `@name` and how many notes mention them.
+    People,
+    /// Copy notes from one day to another.
+    Copy {
+        /// Day to copy from, relative to today.
+        #[arg(short, long, allow_hyphen_values = true)]
+        from: i32,
+        /// Day to copy to, relative to today.
+        #[arg(short, long, default_value_t = 0, allow_hyphen_values = true)]
+        to: i32,
+        /// Only copy notes that are still open.
+        #[arg(long)]
+        open_only: bool,
+        /// Reset copied notes to todo.
+        #[arg(long)]
+        reset: bool,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Add a note.
+    New {
+        #[arg(required_unless_present_any = ["clipboard", "template", "file"])]
+        body: Vec<String>,
+        /// Add the clipboard's text as the note, after the body when one is given.
+        #[arg(long)]
+        clipboard: bool,
+        /// When the body is a url, save the page's title before it, as `fetch_titles` does.
+        #[arg(long)]
+        fetch_title: bool,
+        /// Add the notes of `~/.fuckhead/templates/<NAME>.md` instead of a body.
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["body", "clipboard", "secret"])]
+        template: Option<String>,
+        /// Value for a `{{key}}` placeholder of the template, as `key=value`.
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = template::parse_var, requires = "template")]
+        vars: Vec<(String, String)>,
+        /// Add a note for each non-empty line of the file, `-` reads stdin.
+        #[arg(long, conflicts_with_all = ["body", "clipboard", "template", "secret"])]
+        file: Option<PathBuf>,
+        #[arg(short, long, default_value=None, allow_hyphen_values=true)]
+        day: Option<i32>,
+        /// Day to add to as a date like `2025-04-01`, or `tomorrow`, `eow`, `eom`.
+        #[arg(long, conflicts_with = "day")]
+        date: Option<String>,
+        /// Add the note even if an open note with the same text exists.
+        #[arg(long)]
+        force: bool,
+        /// Encrypt the note to the configured age recipient, shown as `[encrypted]` until revealed.
+        #[arg(long)]
+        secret: bool,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Replace text in note bodies and day text.
+    Replace {
+        old: String,
+        new: String,
+        /// First day to replace in, relative to today, defaults to the first note.
+        #[arg(short, long, allow_hyphen_values = true)]
+        from: Option<i32>,
+        /// Last day to replace in, relative to today, defaults to the last note.
+        #[arg(short, long, allow_hyphen_values = true)]
+        to: Option<i32>,
+        /// Print the changes without saving them.
+        #[arg(long)]
+        dry_run: bool,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Change every note matching a filter, e.g. `tag=errands status=open before=2025-01-01`.
+    Bulk {
+        #[command(subcommand)]
+        action: BulkAction,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Find notes across all days, e.g. `status:open tag:work created:>2025-01-01 due:<eow`.
+    Query {
+        query: String,
+        /// Save the query under a name to run later with `q`.
+        #[arg(long)]
+        save: Option<String>,
+    },
+    /// Run a saved query.
+    Q { name: String },
+    /// Set a note's due date, `none` clears it.
+    Due {
+        #[arg(add = ArgValueCandidates::new(complete::open_note_ids))]
+        id: u32,
+        date: Option<String>,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// List notes across all days, oldest first.
+    List {
+        /// Only list open notes, the default.
+        #[arg(long, conflicts_with_all = ["done", "all"])]
+        open: bool,
+        /// Only list completed notes.
+        #[arg(long, conflicts_with = "all")]
+        done: bool,
+        /// List open and completed notes.
+        #[arg(long)]
+        all: bool,
+        #[arg(short, long)]
+        limit: Option<u32>,
+    },
+    /// List open notes from previous days and notes past their due date.
+    Overdue,
+    /// Delete days or skip them for holidays.
+    Day {
+        #[command(subcommand)]
+        action: DayAction,
+    },
+    /// Key value metadata on notes, also set by `key:value` words in a note.
+    Meta {
+        #[command(subcommand)]
+        action: MetaAction,
+    },
+    /// List links in a day's notes and text.
+    Links {
+        #[arg(short, long, default_value=None, allow_hyphen_values=true)]
+        day: Option<i32>,
+    },
+    /// Open a link from a note in the browser.
+    Open {
+        #[arg(add = ArgValueCandidates::new(complete::open_note_ids))]
+        id: u32,
+        /// Which link to open, counting from 1.
+        #[arg(default_value_t = 1)]
+        index: usize,
+    },
+    /// Run a read only SQL query against the notes database.
+    Sql {
+        sql: String,
+        /// Print CSV rather than a table.
+        #[arg(long)]
+        csv: bool,
+    },
+    /// Merge a duplicate note into another, deleting the duplicate.
+    Merge {
+        #[arg(add = ArgValueCandidates::new(complete::open_note_ids))]
+        keep_id: u32,
+        #[arg(add = ArgValueCandidates::new(complete::open_note_ids))]
+        dup_id: u32,
+        #[command(flatten)]
+        out: WriteOutput,
+    },
+    /// Manage day templates.
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Record the day's mood, energy and tags, also set by a `Mood: 4 | Energy: 3 | Tags: rainy`
+    /// line under the header in `edit`. Prints the day's metrics without arguments.
+    #[command(args_conflicts_with_subcommands = true)]
+    Mood {
+        #[arg(short, long, default_value=None, allow_hyphen_values=true)]
+        day: Option<i32>,
+        /// Mood from 1 to 5.
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=5))]
+        mood: Option<u8>,
+        /// Energy from 1 to 5.
+        #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=5))]
+        energy: Option<u8>,
+        /// Tags for the day, comma separated, replacing any set before.
+        #[arg(short, long)]
+        tags: Option<String>,
+        #[command(subcommand)]
+        action: Option<MoodAction>,
+    },
+    /// Attach a file to a note, copied into the attachments directory and listed by
+    /// `show --long`.
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Attach {
+        #[arg(required = true)]
+        id: Option<u32>,
+        #[arg(required = true)]
+        file: Option<PathBuf>,
+        #[command(flatten)]
+        out: WriteOutput,
+        #[command(subcommand)]
+        action: Option<AttachAction>,
+    },
+    /// Quarterly and yearly goals, notes link to one with `goal:<slug>` and log time with
+    /// `time:<duration>`, e.g. `time:90m`.
+    Goal {
+        #[command(subcommand)]
+        action: GoalAction,
+    },
+    /// Manage Lua plugins adding renderers and note filters to `show`.
+    Plugin {
+        #[command(subcommand)]
+        action: PluginAction,
     },
+    /// Any other command runs the `fh-<command>` plugin from the PATH.
+    #[command(external_subcommand)]
+    External(Vec<String>),
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::map_day;
+    use std::path::Path;
+
+    use crate::{
+        Format, NewNote, clipboard_body,
+        error::{Exit, FhError},
+        execute, exit_code, map_day, parse_notes_string,
+    };
+    use anyhow::Context;
     use chrono::{Days, Local, Timelike};
 
+    #[test]
+    fn test_exit_code() {
+        let err = Err::<(), _>(FhError::NoteNotFound { id: 3 })
+            .context("Failed showing note.")
+            .unwrap_err();
+        assert_eq!(exit_code(&err), 4);
+        assert_eq!(exit_code(&FhError::parse("- [?", "Bad box.").into()), 3);
+        assert_eq!(exit_code(&anyhow::anyhow!("other")), 1);
+    }
+
+    #[tokio::test]
+    async fn test_check_quiet() {
+        let store = crate::NoteStore::open_in_memory().await.unwrap();
+        let config = crate::Config::default();
+        let check = || crate::Mode::Check { quiet: true };
+        let (db, socket) = (Path::new("db.db"), Path::new("fh.sock"));
+        let err = execute(&store, &config, db, socket, check(), Format::Text)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref::<Exit>(), Some(Exit(2))));
+        store.insert_note(NewNote::new("milk")).await.unwrap();
+        execute(&store, &config, db, socket, check(), Format::Text)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_parse_notes_string() {
+        let store = crate::NoteStore::open_in_memory().await.unwrap();
+        let config = crate::Config::default();
+        let buffer = "# Day: 2025-10-13\n\n- [ ] plan\n\n---\n\n# Day: 2025-10-14\n\n- [ ] dentist\n- [ ] \n\n---\n\n- [ ] after the end\n";
+        let saved = parse_notes_string(buffer.to_string(), &store, &config, true)
+            .await
+            .unwrap();
+        assert_eq!(saved[1].notes.len(), 1);
+        let days = saved
+            .iter()
+            .map(|d| (d.date.to_string(), d.notes[0].body.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            days,
+            [
+                (String::from("2025-10-13"), "plan"),
+                (String::from("2025-10-14"), "dentist")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_notes_string_all_or_nothing() {
+        let store = crate::NoteStore::open_in_memory().await.unwrap();
+        let config = crate::Config::default();
+        // The second day names a note that doesn't exist, so the first day isn't saved either.
+        let buffer = "# Day: 2025-10-13\n\n- [ ] plan\n\n---\n\n# Day: 2025-10-14\n\n- [ ] ghost <!-- fh:999 -->\n\n---\n";
+        assert!(
+            parse_notes_string(buffer.to_string(), &store, &config, true)
+                .await
+                .is_err()
+        );
+        let notes = store
+            .filtered_notes(&crate::NoteFilter::default())
+            .await
+            .unwrap();
+        assert!(notes.is_empty());
+    }
+    #[tokio::test]
+    async fn test_parse_notes_string_clears_day_text() {
+        let store = crate::NoteStore::open_in_memory().await.unwrap();
+        let config = crate::Config::default();
+        let buffer = "# Day: 2025-10-13\n\nQuiet day.\n\n- [ ] plan\n\n---\n";
+        let saved = parse_notes_string(buffer.to_string(), &store, &config, true)
+            .await
+            .unwrap();
+        assert_eq!(saved[0].day_text, "Quiet day.\n");
+        let buffer = format!(
+            "# Day: 2025-10-13\n\n- [ ] plan <!-- fh:{} -->\n\n---\n",
+            saved[0].notes[0].id
+        );
+        let saved = parse_notes_string(buffer, &store, &config, true)
+            .await
+            .unwrap();
+        assert_eq!(saved[0].day_text, "");
+    }
+    #[tokio::test]
+    async fn test_parse_notes_string_unchanged() {
+        let store = crate::NoteStore::open_in_memory().await.unwrap();
+        let config = crate::Config::default();
+        let buffer = "# Day: 2025-10-13\n\n- [ ] plan @sam\n\n---\n";
+        let saved = parse_notes_string(buffer.to_string(), &store, &config, true)
+            .await
+            .unwrap();
+        let note = saved[0].notes[0].clone();
+        let buffer = saved[0].pretty_md();
+        let saved = parse_notes_string(buffer, &store, &config, true)
+            .await
+            .unwrap();
+        assert!(note.updated_at.is_none());
+        assert!(saved[0].notes[0].updated_at.is_none());
+        let buffer = format!(
+            "# Day: 2025-10-13\n\n- [x] plan @sam <!-- fh:{} -->\n\n---\n",
+            note.id
+        );
+        let saved = parse_notes_string(buffer, &store, &config, true)
+            .await
+            .unwrap();
+        assert!(saved[0].notes[0].updated_at.is_some());
+    }
+
+    #[test]
+    fn test_clipboard_body() {
+        assert_eq!(
+            clipboard_body("read later:", " https://example.com/a\n").unwrap(),
+            "read later: https://example.com/a"
+        );
+        assert_eq!(clipboard_body("", "two\nlines").unwrap(), "two lines");
+        assert!(clipboard_body("read later:", " \n").is_err());
+    }
+
     #[test]
     fn test_date() {
         let time = Local::now();
@@ -0,0 +1,131 @@
+use anyhow::{Result, anyhow};
+
+use crate::store::DayMetrics;
+
+/// Bars for the values 1 to 5.
+const BARS: [char; 5] = ['▁', '▃', '▄', '▆', '█'];
+
+/// The edit buffer line holding a day's metrics, e.g. `Mood: 4 | Energy: 3 | Tags: rainy, tired`.
+pub fn metrics_line(metrics: &DayMetrics) -> String {
+    let mut parts = Vec::new();
+    if let Some(mood) = metrics.mood {
+        parts.push(format!("Mood: {}", mood));
+    }
+    if let Some(energy) = metrics.energy {
+        parts.push(format!("Energy: {}", energy));
+    }
+    if !metrics.tags.is_empty() {
+        parts.push(format!("Tags: {}", metrics.tags.join(", ")));
+    }
+    parts.join(" | ")
+}
+
+/// Read a metrics line, `None` when the line doesn't start with `Mood:`, `Energy:` or `Tags:`.
+pub fn parse_metrics_line(line: &str) -> Option<Result<DayMetrics>> {
+    let (first, _) = line.split_once(':')?;
+    matches!(
+        first.trim().to_lowercase().as_str(),
+        "mood" | "energy" | "tags"
+    )
+    .then(|| parse_metrics(line))
+}
+
+fn parse_metrics(line: &str) -> Result<DayMetrics> {
+    let mut metrics = DayMetrics::default();
+    for part in line.split('|') {
+        let (name, value) = part
+            .split_once(':')
+            .ok_or(anyhow!("Expected key: value in {}.", line))?;
+        let (name, value) = (name.trim().to_lowercase(), value.trim());
+        let level = || match value.parse::<u8>() {
+            Ok(v @ 1..=5) => Ok(Some(v)),
+            _ => Err(anyhow!("{} should be 1 to 5, not {}.", name, value)),
+        };
+        match name.as_str() {
+            "mood" => metrics.mood = level()?,
+            "energy" => metrics.energy = level()?,
+            "tags" => metrics.tags = parse_tags(value),
+            _ => return Err(anyhow!("Unknown day metric {}.", name)),
+        }
+    }
+    Ok(metrics)
+}
+
+/// Comma separated tags, lowercased.
+pub fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Add the metrics line below the `# ` header of an edit buffer, unless there are none.
+pub fn insert_metrics_line(buffer: &str, metrics: &DayMetrics) -> String {
+    match buffer.split_once('\n') {
+        Some((header, rest)) if !metrics.is_empty() => {
+            format!("{}\n\n{}\n{}", header, metrics_line(metrics), rest)
+        }
+        _ => buffer.to_string(),
+    }
+}
+
+/// Take the metrics line out of an edited buffer, it's the first line after the `# ` header
+/// that isn't blank.
+pub fn take_metrics_line(buffer: &str) -> Result<(String, Option<DayMetrics>)> {
+    let mut lines = buffer.lines().enumerate();
+    let found = lines
+        .by_ref()
+        .find(|(_, l)| l.trim_start().starts_with("# "))
+        .and_then(|_| lines.find(|(_, l)| !l.trim().is_empty()))
+        .and_then(|(i, l)| Some((i, parse_metrics_line(l)?)));
+    let Some((index, metrics)) = found else {
+        return Ok((buffer.to_string(), None));
+    };
+    let rest = buffer
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, l)| l)
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok((rest, Some(metrics?)))
+}
+
+/// One bar per day for values from 1 to 5, days without a value are blank.
+pub fn sparkline(values: &[Option<u8>]) -> String {
+    values
+        .iter()
+        .map(|v| match v {
+            Some(v @ 1..=5) => BARS[*v as usize - 1],
+            _ => ' ',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_metrics_line, parse_metrics_line, sparkline, take_metrics_line};
+    use crate::store::DayMetrics;
+
+    #[test]
+    fn test_metrics_line_round_trip() {
+        let metrics = DayMetrics {
+            mood: Some(4),
+            energy: None,
+            tags: vec![String::from("rainy"), String::from("tired")],
+        };
+        let buffer = "# Today: Sunday, 2025-10-12\n\n- [ ] walk\n\n---";
+        let edited = insert_metrics_line(buffer, &metrics);
+        assert_eq!(
+            edited,
+            "# Today: Sunday, 2025-10-12\n\nMood: 4 | Tags: rainy, tired\n\n- [ ] walk\n\n---"
+        );
+        let (rest, found) = take_metrics_line(&edited).unwrap();
+        assert_eq!(found, Some(metrics));
+        assert!(!rest.contains("Mood"));
+        assert_eq!(take_metrics_line(buffer).unwrap().1, None);
+        assert!(parse_metrics_line("energy: 9").unwrap().is_err());
+        assert!(parse_metrics_line("- [ ] mood: 3").is_none());
+        assert_eq!(sparkline(&[Some(1), None, Some(5)]), "▁ █");
+    }
+}
@@ -1,11 +1,127 @@
-use std::str::{FromStr, Lines};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    str::{FromStr, Lines},
+};
 
-use crate::store::{NoteRow, NoteRowDate, NoteStore};
+use crate::color;
+use crate::error::{FhError, Result};
+use crate::humanize::relative_day;
+use crate::secret;
+use crate::store::{NoteRow, NoteRowDate};
 use ansi_term::{Color, Style};
-use anyhow::{Context, Result, anyhow};
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+/// Return the inner text of a `<!-- comment -->` buffer line.
+pub fn strip_comment(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("<!--")
+        .and_then(|l| l.strip_suffix("-->"))
+        .map(str::trim)
+}
+
+/// True for a trimmed buffer line holding a note.
+/// `-` lines are always notes, `*` and `+` bullets only with a `[ ]` box so plain lists stay text.
+pub fn is_note_line(line: &str) -> bool {
+    line.starts_with('-') || strip_bullet(line).is_some_and(|rest| rest.starts_with('['))
+}
+
+/// Strip a `- `, `* ` or `+ ` list bullet.
+fn strip_bullet(line: &str) -> Option<&str> {
+    line.strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+}
+
+/// Find `key:value` metadata tokens in a note body, e.g. `effort:3`.
+/// Keys are lowercase words so urls and ordinary colons in text are left alone.
+pub fn meta_tokens(body: &str) -> Vec<(&str, &str)> {
+    body.split_whitespace()
+        .filter_map(|word| word.split_once(':'))
+        .filter(|(key, value)| {
+            key.starts_with(|c: char| c.is_ascii_lowercase())
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+                && !value.is_empty()
+                && !value.starts_with("//")
+        })
+        .collect()
+}
+
+/// Find http(s) urls in text, including those inside markdown links.
+pub fn extract_urls(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("https://").or(word.find("http://"))?;
+            let url = word[start..].trim_end_matches([')', ']', '>', '.', ',', ';', '"', '\'']);
+            Some(url)
+        })
+        .collect()
+}
+
+/// Find `@name` mentions in a note body, names are lowercased.
+pub fn mention_tokens(body: &str) -> Vec<String> {
+    body.split_whitespace()
+        .filter_map(|word| word.strip_prefix('@'))
+        .map(|name| name.trim_end_matches(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')))
+        .filter(|name| !name.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The first `@name` token in a note body naming one of `contexts`, like `@home` or `@office`.
+pub fn context_token(body: &str, contexts: &[String]) -> Option<String> {
+    mention_tokens(body)
+        .into_iter()
+        .find(|name| contexts.iter().any(|c| c.eq_ignore_ascii_case(name)))
+}
+
+/// Find the first `+project` token in a note body, names are lowercased.
+pub fn project_token(body: &str) -> Option<String> {
+    body.split_whitespace()
+        .filter_map(|word| word.strip_prefix('+'))
+        .find(|name| {
+            name.starts_with(|c: char| c.is_alphabetic())
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        })
+        .map(str::to_lowercase)
+}
+
+/// Find `#tag` tokens in a note body, tags are lowercased. Words like `#1` aren't tags.
+pub fn tag_tokens(body: &str) -> Vec<String> {
+    body.split_whitespace()
+        .filter_map(|word| word.strip_prefix('#'))
+        .map(|tag| tag.trim_end_matches(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')))
+        .filter(|tag| tag.starts_with(|c: char| c.is_alphabetic()))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Slug a note links to a goal with, `Ship v2!` becomes `ship-v2`.
+pub fn goal_slug(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Token marking a note private.
+pub const PRIVATE_TOKEN: &str = "!!private";
+
+/// True when a note body contains the `!!private` token.
+pub fn has_private_token(body: &str) -> bool {
+    body.split_whitespace().any(|word| word == PRIVATE_TOKEN)
+}
+
+/// A note read from the day buffer, tagged `"kind": "note"` or `"kind": "newnote"` in JSON.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
 pub enum ParsedNote {
     Note(Note),
     NewNote(NewNote),
@@ -32,66 +148,234 @@ impl ParsedNote {
     pub fn is_note(&self) -> bool {
         !self.is_new_note()
     }
+    /// Id of a note already saved, `None` for a new one.
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            ParsedNote::NewNote(_) => None,
+            ParsedNote::Note(n) => Some(n.id),
+        }
+    }
+    /// Set the section heading the note was found under.
+    pub fn set_section(&mut self, section: Option<String>) {
+        match self {
+            ParsedNote::NewNote(n) => n.section = section,
+            ParsedNote::Note(n) => n.section = section,
+        }
+    }
     pub fn parse_pretty_md(s: impl AsRef<str>) -> Result<Option<ParsedNote>> {
-        let s = s.as_ref();
-        let s = s.trim();
-        if s.len() < 7 {
-            return Err(anyhow!("Invalid note start, not long enough. {}", &s));
-        }
-        if !(&s[..7] == "- [ ] :" || &s[..7] == "- [x] :") {
-            return Err(anyhow!("Invalid note start. {}", &s[..7]));
-        }
-        let tick_char = s.chars().nth(3).ok_or(anyhow!(
-            "Invalid format for note string expect 3th char to be tick box."
-        ))?;
-        let completed = tick_char == 'x';
-        let idx = s
-            .find(":")
-            .ok_or(anyhow!("Malformed note string expect :"))?;
-        match s[idx + 1..].split_once(':') {
-            Some((id_string, text)) => {
-                let body = String::from(text.trim());
-                if body.is_empty() {
-                    return Ok(None);
-                }
-                let id = id_string.parse::<u32>().context(format!(
-                    "Parsing {} failed. {}",
-                    id_string,
-                    &s[idx + 1..]
-                ))?;
-                Ok(Some(ParsedNote::Note(Note {
-                    id,
-                    body,
-                    completed,
-                })))
-            }
-            None => {
-                let new_note_text = s[idx + 1..].trim();
-                if new_note_text.is_empty() {
-                    return Ok(None);
-                }
-                Ok(Some(ParsedNote::NewNote(NewNote {
-                    body: String::from(new_note_text),
-                    completed,
-                    created_at: Utc::now(),
-                })))
-            }
+        let (status, id, text) = split_note(s.as_ref().trim())?;
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let body = String::from(text);
+        match id {
+            Some(id) => Ok(Some(ParsedNote::Note(Note {
+                id,
+                body,
+                status,
+                section: None,
+                due: None,
+                focus_rank: None,
+                ..Default::default()
+            }))),
+            None => Ok(Some(ParsedNote::NewNote(
+                NewNote::builder().body(body).status(status).build(),
+            ))),
         }
     }
 }
 
-#[derive(Debug)]
+/// How notes are written in the editor buffer, either format is read back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BufferFormat {
+    /// Standard Markdown tasks with the id in a trailing comment, `- [ ] body <!-- fh:42 -->`.
+    #[default]
+    Markdown,
+    /// The original ` - [ ] :42: body` lines.
+    Legacy,
+}
+
+/// Split a trimmed note line into its status, id and body, the id is `None` for a new note.
+/// Markdown tasks carry their id as a trailing `<!-- fh:42 -->`, a `(#42)` reference is body text,
+/// lines with a `:` after the box are read in the legacy `:42:` format.
+fn split_note(s: &str) -> Result<(Status, Option<u32>, &str)> {
+    let (status, rest) = split_tick_box(s)?;
+    // A trailing `<!-- fh:42 -->` wins, so Markdown bodies starting with `:` aren't read as legacy.
+    let commented = split_trailing_id(rest.trim()).is_some();
+    if !commented && let Some(legacy) = rest.strip_prefix(" :") {
+        return Ok(match split_id(legacy) {
+            Some((id, text)) => (status, Some(id), text.trim()),
+            None => (status, None, legacy.trim()),
+        });
+    }
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return Err(FhError::parse(s, "Invalid note start."));
+    }
+    let text = rest.trim();
+    Ok(match split_trailing_id(text) {
+        Some((id, body)) => (status, Some(id), body),
+        None => (status, None, text),
+    })
+}
+
+/// Split a trailing `<!-- fh:42 -->` id off the text of a Markdown task.
+fn split_trailing_id(text: &str) -> Option<(u32, &str)> {
+    let (body, id) = text.strip_suffix("-->")?.rsplit_once("<!--")?;
+    let id = id.trim().strip_prefix("fh:")?;
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((id.parse().ok()?, body.trim_end()))
+}
+
+/// Split the id off the text after the `- [?] :` prefix, `None` for a new note.
+/// Text before the first `:` only counts as an id when it's all digits, so new notes can hold colons.
+fn split_id(rest: &str) -> Option<(u32, &str)> {
+    let (id_string, text) = rest.split_once(':')?;
+    if id_string.is_empty() || !id_string.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((id_string.parse().ok()?, text))
+}
+
+/// Split a trimmed note line into its status and the text after the `- [?]` box.
+/// The box is read by chars, so emoji markers and multibyte text right after it are kept whole.
+fn split_tick_box(s: &str) -> Result<(Status, &str)> {
+    let rest = strip_bullet(s)
+        .and_then(|rest| rest.strip_prefix('['))
+        .ok_or(FhError::parse(s, "Invalid note start."))?;
+    let mut chars = rest.chars();
+    let marker = chars
+        .next()
+        .ok_or(FhError::parse(s, "Invalid note start, not long enough."))?;
+    let status = Status::from_marker(marker).ok_or(FhError::parse(
+        s,
+        format!("Unknown status box [{}].", marker),
+    ))?;
+    let rest = chars.as_str();
+    // Emoji like ✔️ carry a variation selector after the marker.
+    let rest = rest.strip_prefix('\u{FE0F}').unwrap_or(rest);
+    let rest = rest
+        .strip_prefix(']')
+        .ok_or(FhError::parse(s, "Invalid note start."))?;
+    Ok((status, rest))
+}
+
+/// Progress of a note, written as the character in its `[ ]` box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    #[default]
+    Todo,
+    Doing,
+    Done,
+    Blocked,
+    Cancelled,
+}
+impl Status {
+    pub const ALL: [Status; 5] = [
+        Status::Todo,
+        Status::Doing,
+        Status::Done,
+        Status::Blocked,
+        Status::Cancelled,
+    ];
+    pub fn marker(&self) -> char {
+        match self {
+            Status::Todo => ' ',
+            Status::Doing => '>',
+            Status::Done => 'x',
+            Status::Blocked => '!',
+            Status::Cancelled => '-',
+        }
+    }
+    /// Status for a box marker, emoji like ✅ are accepted as well as the plain markers.
+    pub fn from_marker(c: char) -> Option<Status> {
+        match c {
+            'x' | 'X' | '✅' | '✔' | '✓' => Some(Status::Done),
+            '🚧' | '⏳' => Some(Status::Doing),
+            '⛔' | '🚫' => Some(Status::Blocked),
+            '❌' | '✗' => Some(Status::Cancelled),
+            c => Status::ALL.into_iter().find(|s| s.marker() == c),
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::Doing => "doing",
+            Status::Done => "done",
+            Status::Blocked => "blocked",
+            Status::Cancelled => "cancelled",
+        }
+    }
+    /// Whether the note still needs doing, i.e. it is neither done nor cancelled.
+    pub fn is_open(&self) -> bool {
+        matches!(self, Status::Todo | Status::Doing | Status::Blocked)
+    }
+}
+impl FromStr for Status {
+    type Err = FhError;
+    fn from_str(s: &str) -> Result<Self> {
+        Status::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or(FhError::Invalid(format!(
+                "Unknown status {}, expect todo, doing, done, blocked or cancelled.",
+                s
+            )))
+    }
+}
+/// SQL condition matching open notes of `note as n`, kept in line with [`Status::is_open`].
+pub const OPEN_STATUS_SQL: &str = "n.status IN ('todo', 'doing', 'blocked')";
+
+/// Status selection used by filters and queries, `open` matches any open status.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatusMatch {
+    Open,
+    Is(Status),
+}
+impl FromStr for StatusMatch {
+    type Err = FhError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "open" => Ok(StatusMatch::Open),
+            s => Status::from_str(s).map(StatusMatch::Is),
+        }
+    }
+}
+
+/// A stored note, the timestamps and date are `None` when the note wasn't read from the store.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Note {
     pub id: u32,
     pub body: String,
-    pub completed: bool,
+    pub status: Status,
+    pub section: Option<String>,
+    pub due: Option<NaiveDate>,
+    /// Position in the day's focus list, starting at 1.
+    pub focus_rank: Option<u32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Set once the note is soft deleted.
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Day the note is on.
+    pub date: Option<NaiveDate>,
 }
 impl From<NoteRow> for Note {
     fn from(value: NoteRow) -> Self {
         Note {
             id: value.id,
             body: value.body,
-            completed: value.completed,
+            status: value.status,
+            section: value.section,
+            due: value.due,
+            focus_rank: value.focus_rank,
+            created_at: Some(value.created_at),
+            updated_at: value.updated_at,
+            deleted_at: value.deleted_at,
+            date: None,
         }
     }
 }
@@ -100,69 +384,70 @@ impl From<NoteRowDate> for Note {
         Note {
             id: value.id,
             body: value.body,
-            completed: value.completed,
+            status: value.status,
+            section: value.section,
+            due: value.due,
+            focus_rank: value.focus_rank,
+            created_at: Some(value.created_at),
+            updated_at: value.updated_at,
+            deleted_at: value.deleted_at,
+            date: Some(value.date),
         }
     }
 }
 impl Note {
-    pub fn pretty_empty() -> String {
-        String::from(" - [ ] :")
+    /// Placeholder line for a new note in the editor buffer.
+    pub fn pretty_empty(format: BufferFormat) -> String {
+        match format {
+            BufferFormat::Markdown => String::from("- [ ] "),
+            BufferFormat::Legacy => String::from(" - [ ] :"),
+        }
+    }
+    /// Editor buffer line for the note.
+    pub fn pretty_md(&self, format: BufferFormat) -> String {
+        match format {
+            BufferFormat::Markdown => format!(
+                "- [{}] {} <!-- fh:{} -->",
+                self.status.marker(),
+                self.display_body(),
+                self.id
+            ),
+            BufferFormat::Legacy => self.pretty(),
+        }
     }
     pub fn pretty(&self) -> String {
-        let tick = if self.completed { "x" } else { " " };
-        format!(" - [{tick}] :{}: {}", self.id, self.body)
-    }
-    /// Insert and build note from string.
-    pub async fn from_pretty(store: &NoteStore, s: impl AsRef<str>) -> Result<Option<Note>> {
-        let s = s.as_ref();
-        let s = s.trim();
-        if !(&s[..7] == "- [ ] :" || &s[..7] == "- [x] :") {
-            return Err(anyhow!("Invalid note start. {}", &s[..7]));
-        }
-        let tick_char = s.chars().nth(3).ok_or(anyhow!(
-            "Invalid format for note string expect 3th char to be tick box."
-        ))?;
-        let completed = tick_char == 'x';
-        let idx = s
-            .find(":")
-            .ok_or(anyhow!("Malformed note string expect :"))?;
-        match s[idx + 1..].split_once(':') {
-            Some((id_string, text)) => {
-                let body = String::from(text.trim());
-                let id = id_string.parse::<u32>().context(format!(
-                    "Parsing {} failed. {}",
-                    id_string,
-                    &s[idx + 1..]
-                ))?;
-                let note = Note {
-                    id,
-                    body,
-                    completed,
-                };
-                return store.update_note(&note).await.map(Some);
-            }
-            None => {
-                let new_note_text = s[idx + 1..].trim();
-                if new_note_text.is_empty() {
-                    return Ok(None);
-                }
-                return store
-                    .insert_note(NewNote {
-                        body: String::from(new_note_text),
-                        completed,
-                        created_at: Utc::now(),
-                    })
-                    .await
-                    .map(Some);
-            }
-        };
+        format!(
+            " - [{}] :{}: {}",
+            self.status.marker(),
+            self.id,
+            self.display_body()
+        )
+    }
+    /// Body for display, a placeholder while the body is encrypted.
+    pub fn display_body(&self) -> &str {
+        if secret::is_encrypted(&self.body) {
+            secret::ENCRYPTED_BODY
+        } else {
+            &self.body
+        }
+    }
+    /// Terminal rendering, cancelled notes are struck through so they read as decided against.
+    pub fn pretty_term(&self) -> String {
+        match self.status {
+            Status::Cancelled => color::style(Style::new().strikethrough().dimmed())
+                .paint(self.pretty())
+                .to_string(),
+            _ => self.pretty(),
+        }
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NewNote {
     pub body: String,
-    pub completed: bool,
+    pub status: Status,
     pub created_at: DateTime<Utc>,
+    pub section: Option<String>,
+    pub due: Option<NaiveDate>,
 }
 impl NewNote {
     pub fn date_created(&self) -> NaiveDate {
@@ -172,19 +457,68 @@ impl NewNote {
         Note {
             id,
             body: self.body,
-            completed: self.completed,
+            status: self.status,
+            section: self.section,
+            due: self.due,
+            focus_rank: None,
+            created_at: Some(self.created_at),
+            ..Default::default()
         }
     }
     pub fn new(body: impl Into<String>) -> NewNote {
         NewNote {
             body: body.into(),
-            completed: false,
+            status: Status::Todo,
             created_at: Utc::now(),
+            section: None,
+            due: None,
         }
     }
+    /// Builder for a todo note created now, set only the options that differ.
+    pub fn builder() -> NewNoteBuilder {
+        NewNoteBuilder(NewNote::new(""))
+    }
 }
 
+/// Sets the options of a [`NewNote`], see [`NewNote::builder`].
 #[derive(Debug)]
+pub struct NewNoteBuilder(NewNote);
+impl NewNoteBuilder {
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.0.body = body.into();
+        self
+    }
+    pub fn status(mut self, status: Status) -> Self {
+        self.0.status = status;
+        self
+    }
+    /// Mark the note done, or back to todo.
+    pub fn completed(self, completed: bool) -> Self {
+        self.status(if completed {
+            Status::Done
+        } else {
+            Status::Todo
+        })
+    }
+    /// When the note was created, which also decides the day it's added to.
+    pub fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.0.created_at = created_at;
+        self
+    }
+    pub fn section(mut self, section: Option<impl Into<String>>) -> Self {
+        self.0.section = section.map(Into::into);
+        self
+    }
+    pub fn due(mut self, due: Option<NaiveDate>) -> Self {
+        self.0.due = due;
+        self
+    }
+    pub fn build(self) -> NewNote {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DayNotes {
     pub notes: Vec<Note>,
     pub note_count: u32,
@@ -199,27 +533,120 @@ impl DayNotes {
             "Day"
         }
     }
-    pub fn pretty_md(&self) -> String {
-        let mut out = format!("# {}: {}\n\n", self.day_prefix(), self.date);
+    /// Keep only the notes matching `f`.
+    pub fn retain_notes(&mut self, f: impl FnMut(&Note) -> bool) {
+        self.notes.retain(f);
+        self.note_count = self.notes.len() as u32;
+    }
+    /// Focused notes in focus order.
+    pub fn focused(&self) -> Vec<&Note> {
+        let mut focused = self
+            .notes
+            .iter()
+            .filter(|n| n.focus_rank.is_some())
+            .collect::<Vec<_>>();
+        focused.sort_by_key(|n| n.focus_rank);
+        focused
+    }
+    /// Terminal rendering of the focused notes as a highlighted block.
+    pub fn pretty_focus(&self) -> String {
+        let mut out = String::new();
+        let focused = self.focused();
+        if focused.is_empty() {
+            return out;
+        }
+        out.push_str(
+            &color::style(Color::Yellow.bold())
+                .paint("Focus:\n")
+                .to_string(),
+        );
+        for note in focused {
+            out.push_str(&format!(
+                "{}\n",
+                color::style(Color::Yellow.normal()).paint(note.pretty())
+            ));
+        }
+        out.push('\n');
+        out
+    }
+    /// True when the day has neither notes nor text.
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty() && self.day_text.is_empty()
+    }
+    /// Date header of the editor buffer, `# Today: <date>` unless a strftime `header_format` is given.
+    /// An invalid format falls back to the default header.
+    pub fn header_md(&self, header_format: Option<&str>) -> String {
+        let mut header = String::new();
+        match header_format {
+            Some(f) if write!(header, "# {}", self.date.format(f)).is_ok() => header,
+            _ => format!("# {}: {}", self.day_prefix(), self.date_label()),
+        }
+    }
+    /// Editor buffer for an empty day built from a template, `{{date}}` is replaced by the day's date.
+    pub fn template_md(&self, template: &str, header_format: Option<&str>) -> String {
+        let body = template.replace("{{date}}", &self.date.to_string());
+        format!("{}\n\n{}\n---", self.header_md(header_format), body)
+    }
+    /// Group notes by section, unsectioned notes first then sections in order of appearance.
+    pub fn sections(&self) -> Vec<(Option<&str>, Vec<&Note>)> {
+        let mut out: Vec<(Option<&str>, Vec<&Note>)> = vec![(None, vec![])];
         for note in &self.notes {
-            out.push_str(&format!("{}\n", note.pretty()));
+            let section = note.section.as_deref();
+            match out.iter_mut().find(|(s, _)| *s == section) {
+                Some((_, notes)) => notes.push(note),
+                None => out.push((section, vec![note])),
+            }
+        }
+        out
+    }
+    pub fn pretty_md(&self) -> String {
+        self.pretty_md_with_prompts(&[], BufferFormat::default(), None)
+    }
+    /// Editor buffer with notes written in `format`, for an empty day `prompts` are added as comments.
+    /// An answered prompt is kept in the day text, unanswered ones are dropped on parse.
+    pub fn pretty_md_with_prompts(
+        &self,
+        prompts: &[String],
+        format: BufferFormat,
+        header_format: Option<&str>,
+    ) -> String {
+        let mut out = format!("{}\n\n", self.header_md(header_format));
+        for (section, notes) in self.sections() {
+            if let Some(section) = section {
+                out.push_str(&format!("\n## {}\n\n", section));
+            }
+            for note in notes {
+                out.push_str(&format!("{}\n", note.pretty_md(format)));
+            }
+            out.push_str(&format!("{}\n", Note::pretty_empty(format)));
         }
-        out.push_str(&format!("{}\n", Note::pretty_empty()));
         out.push('\n');
+        if self.is_empty() {
+            for prompt in prompts {
+                out.push_str(&format!("<!-- {} -->\n\n", prompt));
+            }
+        }
         out.push_str(&self.day_text);
+        // Text set outside the editor may lack the newline, keep `---` on its own line.
+        if !self.day_text.is_empty() && !self.day_text.ends_with('\n') {
+            out.push('\n');
+        }
         out.push_str("---");
         out
     }
+    /// The date with its weekday, e.g. `Tuesday, 2025-03-04`.
+    pub fn date_label(&self) -> String {
+        format!("{}", self.date.format("%A, %Y-%m-%d"))
+    }
     pub fn pretty(&self) -> String {
-        let mut out = format!(
-            "{}: {} \n\n",
-            self.day_prefix(),
-            Color::Green.paint(self.date.to_string())
-        );
-        out = Style::new().bold().paint(out).to_string();
-        for note in &self.notes {
-            out.push_str(&format!("{}\n", note.pretty()));
-        }
+        self.pretty_ago(None)
+    }
+    /// Terminal rendering, the date is followed by how long before `today` it was when given.
+    pub fn pretty_ago(&self, today: Option<NaiveDate>) -> String {
+        let mut out = self.pretty_header(today);
+        out.push_str(" \n\n");
+        out = color::style(Style::new().bold()).paint(out).to_string();
+        out.push_str(&self.pretty_notes());
         if self.notes.is_empty() {
             out.push_str("No Notes.");
         }
@@ -227,59 +654,327 @@ impl DayNotes {
         out.push_str(&self.day_text);
         out
     }
+    /// One line with the date and the open and done counts, followed by the notes and text
+    /// only when the day has any.
+    pub fn pretty_summary(&self, today: Option<NaiveDate>) -> String {
+        let open = self.notes.iter().filter(|n| n.status.is_open()).count();
+        let done = self
+            .notes
+            .iter()
+            .filter(|n| n.status == Status::Done)
+            .count();
+        let mut out = color::style(Style::new().bold())
+            .paint(self.pretty_header(today))
+            .to_string();
+        out.push_str(&format!("  {} open, {} done\n", open, done));
+        if !self.is_empty() {
+            out.push_str(&self.pretty_notes());
+            out.push_str(&self.day_text);
+            out.push('\n');
+        }
+        out
+    }
+    /// `Day: <date>`, with how long before `today` it was when given.
+    fn pretty_header(&self, today: Option<NaiveDate>) -> String {
+        let ago = today
+            .map(|today| format!(" ({})", relative_day(self.date, today)))
+            .unwrap_or_default();
+        format!(
+            "{}: {}{}",
+            self.day_prefix(),
+            color::style(Color::Green.normal()).paint(self.date_label()),
+            ago
+        )
+    }
+    /// Focused notes then the notes of each section, one per line.
+    fn pretty_notes(&self) -> String {
+        let mut out = self.pretty_focus();
+        for (section, notes) in self.sections() {
+            if let Some(section) = section {
+                let header = format!("\n{}:\n", section);
+                out.push_str(&color::style(Style::new().bold()).paint(header).to_string());
+            }
+            for note in notes {
+                out.push_str(&format!("{}\n", note.pretty_term()));
+            }
+        }
+        out
+    }
+}
+/// Date shapes read from a day header after the configured format, day first for `/` and `.` dates.
+const HEADER_DATE_FORMATS: [&str; 10] = [
+    "%Y-%m-%d",
+    "%d/%m/%Y",
+    "%m/%d/%Y",
+    "%d.%m.%Y",
+    "%b %d %Y",
+    "%b %d, %Y",
+    "%B %d %Y",
+    "%B %d, %Y",
+    "%d %b %Y",
+    "%d %B %Y",
+];
+
+/// Skip to the `# ` date header of a day buffer and read its date.
+/// Lines before the header are ignored.
+pub fn find_date(line_iter: &mut Lines<'_>, header_format: Option<&str>) -> Result<NaiveDate> {
+    for line in line_iter.by_ref() {
+        if let Some(header) = line.trim().strip_prefix("# ") {
+            return parse_header_date(header, header_format);
+        }
+    }
+    Err(FhError::parse("", "Couldn't find text."))
+}
+
+/// Read the date of a header like `Today: 2025-10-12`, `2025-10-12 (Sunday)` or `Sunday, Oct 12 2025`.
+/// `header_format` is tried first, then the common shapes, text after the date is ignored.
+pub fn parse_header_date(header: &str, header_format: Option<&str>) -> Result<NaiveDate> {
+    let header = header.trim();
+    let text = header
+        .strip_prefix("Today:")
+        .or(header.strip_prefix("Day:"))
+        .unwrap_or(header)
+        .trim();
+    if let Some(f) = header_format
+        && let Ok(date) = NaiveDate::parse_from_str(header, f)
+    {
+        return Ok(date);
+    }
+    if let Ok(date) = NaiveDate::from_str(text) {
+        return Ok(date);
+    }
+    // Drop a leading weekday like `Sunday,`.
+    let text = match text.split_once(' ') {
+        Some((day, rest)) if day.trim_end_matches(',').parse::<Weekday>().is_ok() => rest.trim(),
+        _ => text,
+    };
+    HEADER_DATE_FORMATS
+        .iter()
+        .find_map(|f| NaiveDate::parse_and_remainder(text, f).ok())
+        .map(|(date, _)| date)
+        .ok_or(FhError::parse(
+            header,
+            "Invalid day date, expected a date like 2025-10-12, 12/10/2025 or Oct 12 2025.",
+        ))
 }
+
+/// Frontmatter block of a day's metadata written above the buffer's header, YAML `key: value`
+/// lines between `---`, empty when the day has none.
+pub fn frontmatter_md(meta: &BTreeMap<String, String>) -> String {
+    if meta.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("---\n");
+    for (key, value) in meta {
+        // Quote values YAML would read differently.
+        let plain = !value.is_empty()
+            && value.trim() == value
+            && !value.starts_with(['"', '\'', '#', '[', '{']);
+        if plain {
+            out.push_str(&format!("{}: {}\n", key, value));
+        } else {
+            out.push_str(&format!("{}: {}\n", key, serde_json::json!(value)));
+        }
+    }
+    out.push_str("---\n\n");
+    out
+}
+
+/// Split a leading frontmatter block off a day buffer, YAML between `---` lines or TOML between
+/// `+++` lines. YAML is read as flat `key: value` lines.
+pub fn split_frontmatter(buffer: &str) -> Result<(BTreeMap<String, String>, &str)> {
+    let trimmed = buffer.trim_start();
+    let Some(fence) = ["---", "+++"]
+        .into_iter()
+        .find(|f| trimmed.lines().next().is_some_and(|l| l.trim_end() == *f))
+    else {
+        return Ok((BTreeMap::new(), buffer));
+    };
+    let block = &trimmed[fence.len()..];
+    let (block, rest) = block
+        .split_once(&format!("\n{}", fence))
+        .ok_or(FhError::parse(fence, "Frontmatter isn't closed."))?;
+    let rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+    let meta = if fence == "+++" {
+        let table = toml::from_str::<toml::Table>(block).map_err(|e| {
+            FhError::parse(block.trim(), format!("Invalid TOML frontmatter, {}", e))
+        })?;
+        table
+            .into_iter()
+            .map(|(k, v)| match v {
+                toml::Value::String(s) => (k, s),
+                v => (k, v.to_string()),
+            })
+            .collect()
+    } else {
+        block
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|line| {
+                let (key, value) = line
+                    .split_once(':')
+                    .ok_or(FhError::parse(line, "Expected key: value in frontmatter."))?;
+                let value = value.trim();
+                let value = match serde_json::from_str::<String>(value) {
+                    Ok(quoted) => quoted,
+                    Err(_) => value.trim_matches('\'').to_string(),
+                };
+                Ok((key.trim().to_string(), value))
+            })
+            .collect::<Result<_>>()?
+    };
+    Ok((meta, rest))
+}
+
+/// What a line of the day buffer after the date header holds.
+#[derive(Debug, PartialEq)]
+pub enum BufferLine<'a> {
+    /// A trimmed note line.
+    Note(&'a str),
+    /// A `## <name>` section heading.
+    Section(&'a str),
+    /// Day text, blank lines and prompts, collected by the reader.
+    Text,
+    /// The closing `---`.
+    End,
+}
+
+/// Reads the day buffer line by line, collecting the day text.
+/// Once the text starts it's kept verbatim up to `---`, blank lines, indentation and code blocks
+/// included, only task lines outside code blocks are still read as notes.
+/// A prompt comment is kept as the line before its answer, unanswered ones are dropped.
+/// Other `<!-- comment -->` lines and lines starting with `//` are skipped.
+#[derive(Debug, Default)]
+pub struct BufferReader<'a> {
+    day_text: String,
+    prompts: &'a [String],
+    prompt: Option<&'a str>,
+    in_code: bool,
+}
+impl<'a> BufferReader<'a> {
+    /// Reader keeping the answers to `prompts`.
+    pub fn new(prompts: &'a [String]) -> Self {
+        BufferReader {
+            prompts,
+            ..Default::default()
+        }
+    }
+    pub fn read(&mut self, line: &'a str) -> BufferLine<'a> {
+        let trimmed = line.trim();
+        let in_notes = self.day_text.is_empty();
+        if !self.in_code {
+            if trimmed.starts_with("---") {
+                return BufferLine::End;
+            }
+            if trimmed.starts_with("//") {
+                return BufferLine::Text;
+            }
+            if let Some(comment) = strip_comment(trimmed) {
+                if self.prompts.iter().any(|p| p.trim() == comment) {
+                    self.prompt = Some(comment);
+                }
+                return BufferLine::Text;
+            }
+            if is_note_line(trimmed) && (in_notes || split_tick_box(trimmed).is_ok()) {
+                self.prompt = None;
+                return BufferLine::Note(trimmed);
+            }
+            if in_notes {
+                if trimmed.is_empty() {
+                    return BufferLine::Text;
+                }
+                if let Some(name) = trimmed.strip_prefix("## ") {
+                    self.prompt = None;
+                    return BufferLine::Section(name.trim());
+                }
+            }
+        }
+        if trimmed.starts_with("```") {
+            self.in_code = !self.in_code;
+        }
+        if let Some(prompt) = self.prompt.take_if(|_| !trimmed.is_empty()) {
+            self.day_text.push_str(prompt);
+            self.day_text.push('\n');
+        }
+        self.day_text.push_str(line);
+        self.day_text.push('\n');
+        BufferLine::Text
+    }
+    /// The day text read, without the blank lines before `---`.
+    pub fn day_text(self) -> String {
+        match self.day_text.trim_end() {
+            "" => String::new(),
+            text => format!("{}\n", text),
+        }
+    }
+}
+
+/// Notes found under a `## <name>` heading in the day buffer.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParsedSection {
+    pub name: String,
+    pub notes: Vec<ParsedNote>,
+}
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ParsedDayNotes {
+    /// Notes found before any section heading.
     pub notes: Vec<ParsedNote>,
+    pub sections: Vec<ParsedSection>,
     pub note_count: u32,
     pub date: NaiveDate,
     pub day_text: String,
 }
 impl ParsedDayNotes {
+    /// Every note of the day in buffer order, those under a section after those before any.
+    pub fn all_notes(&self) -> impl Iterator<Item = &ParsedNote> {
+        self.notes
+            .iter()
+            .chain(self.sections.iter().flat_map(|s| &s.notes))
+    }
     pub fn parse_pretty_md(line_iter: &mut Lines<'_>) -> Result<ParsedDayNotes> {
-        let mut date: Option<&str> = None;
-        // Iterate through lines till find the date prefix!
-        while date.is_none() {
-            let Some(line) = line_iter.next() else {
-                return Err(anyhow!("Couldn't find text."));
-            };
-            if line.trim().is_empty() {
-                continue;
-            }
-            date = line.strip_prefix("# Today: ");
-            if date.is_none() {
-                date = line.strip_prefix("# Day: ")
-            }
-        }
-        let date = date.ok_or(anyhow!("Couldn't find text."))?;
-        let date = NaiveDate::from_str(date)?;
-        let mut day_text = String::new();
+        Self::parse_pretty_md_with_prompts(line_iter, &[], None)
+    }
+    /// Parse the day starting at the next header of `line_iter`, leaving it after the day's `---`.
+    /// The answers to `prompts` are kept in the day text, the header is read with `header_format`
+    /// when given.
+    pub fn parse_pretty_md_with_prompts(
+        line_iter: &mut Lines<'_>,
+        prompts: &[String],
+        header_format: Option<&str>,
+    ) -> Result<ParsedDayNotes> {
+        let date = find_date(line_iter, header_format)?;
         let mut notes = vec![];
-        // Update notes by line.
+        let mut sections: Vec<ParsedSection> = vec![];
+        let mut reader = BufferReader::new(prompts);
         for line in line_iter {
-            // exit the iteration if end of day note is found.
-            if &line[..3] == "---" {
-                break;
-            }
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-            match line.chars().next().unwrap() {
-                '-' => {
-                    let Ok(Some(n)) = ParsedNote::parse_pretty_md(line) else {
+            match reader.read(line) {
+                BufferLine::End => break,
+                BufferLine::Text => {}
+                BufferLine::Section(name) => sections.push(ParsedSection {
+                    name: String::from(name),
+                    notes: vec![],
+                }),
+                BufferLine::Note(line) => {
+                    let Some(mut n) = ParsedNote::parse_pretty_md(line)? else {
                         continue;
                     };
-                    notes.push(n);
-                }
-                _ => {
-                    day_text.push_str(line);
-                    day_text.push('\n');
+                    match sections.last_mut() {
+                        Some(section) => {
+                            n.set_section(Some(section.name.clone()));
+                            section.notes.push(n);
+                        }
+                        None => notes.push(n),
+                    }
                 }
             }
         }
-        let note_count = notes.len() as u32;
+        let day_text = reader.day_text();
+        let note_count =
+            (notes.len() + sections.iter().map(|s| s.notes.len()).sum::<usize>()) as u32;
         Ok(ParsedDayNotes {
             notes,
+            sections,
             note_count,
             date,
             day_text,
@@ -289,76 +984,456 @@ impl ParsedDayNotes {
 
 #[cfg(test)]
 mod tests {
-    use std::{fs::File, io::Read, str::FromStr};
+    use std::{collections::BTreeMap, fs::File, io::Read, str::FromStr};
 
     use crate::{
-        notes::{NewNote, Note},
-        store::setup_db,
+        notes::{DayNotes, NewNote, Note},
+        store::{NoteStore, setup_db},
     };
     use chrono::{NaiveDate, Utc};
     use sqlx::migrate;
 
-    use super::{ParsedDayNotes, ParsedNote};
+    use super::{
+        BufferFormat, ParsedDayNotes, ParsedNote, Status, frontmatter_md, split_frontmatter,
+    };
 
-    async fn setup_sqlitedb() -> crate::store::NoteStore {
-        let s = setup_db("sqlite://:memory:").await;
+    async fn setup_sqlitedb() -> NoteStore {
+        let s = setup_db("sqlite://:memory:").await.unwrap();
         migrate!().run(&s.pool).await.unwrap();
         s.insert_day(Utc::now().date_naive(), None, "")
             .await
             .unwrap();
         s
     }
+    /// Parse `line` and save it, as the editor does.
+    async fn save_line(store: &NoteStore, line: &str) -> crate::error::Result<Option<Note>> {
+        match ParsedNote::parse_pretty_md(line)? {
+            Some(parsed) => store.save_parsed_note(parsed).await.map(Some),
+            None => Ok(None),
+        }
+    }
     #[tokio::test]
     async fn test_parse_note() {
         let store = setup_sqlitedb().await;
-        let n = Note::from_pretty(&store, "- [ ] : test")
-            .await
-            .unwrap()
-            .unwrap();
+        let n = save_line(&store, "- [ ] : test").await.unwrap().unwrap();
         assert_eq!(n.body, "test");
     }
+    #[test]
+    fn test_parse_unicode_corpus() {
+        let corpus = [
+            ("- [ ] : 🎉 party", Status::Todo, None, "🎉 party"),
+            ("- [✅] :3: ship 🚀", Status::Done, Some(3), "ship 🚀"),
+            ("- [✔️] :4: 完成", Status::Done, Some(4), "完成"),
+            ("- [🚧] : 会议: 10時", Status::Doing, None, "会议: 10時"),
+            (
+                "- [⛔] :7: ждём ответа",
+                Status::Blocked,
+                Some(7),
+                "ждём ответа",
+            ),
+            ("- [❌] : مرحبا", Status::Cancelled, None, "مرحبا"),
+            (
+                "- [x] :12: café naïve",
+                Status::Done,
+                Some(12),
+                "café naïve",
+            ),
+            ("- [ ] : meet at 10:30", Status::Todo, None, "meet at 10:30"),
+            ("- [>] :2:👍", Status::Doing, Some(2), "👍"),
+        ];
+        for (line, status, id, body) in corpus {
+            let parsed = ParsedNote::parse_pretty_md(line).unwrap().unwrap();
+            let (got_status, got_id, got_body) = match &parsed {
+                ParsedNote::Note(n) => (n.status, Some(n.id), n.body.as_str()),
+                ParsedNote::NewNote(n) => (n.status, None, n.body.as_str()),
+            };
+            assert_eq!(
+                (got_status, got_id, got_body),
+                (status, id, body),
+                "{}",
+                line
+            );
+        }
+        for line in ["- [🙂] : unknown", "- [", "- [✅"] {
+            assert!(ParsedNote::parse_pretty_md(line).is_err(), "{}", line);
+        }
+    }
     #[tokio::test]
     async fn test_parse_note_none() {
         let store = setup_sqlitedb().await;
-        let n = Note::from_pretty(&store, "- [ ] :    ").await.unwrap();
+        let n = save_line(&store, "- [ ] :    ").await.unwrap();
         assert!(n.is_none());
     }
     #[tokio::test]
     async fn test_parse_note_not_exist() {
         let store = setup_sqlitedb().await;
-        let n = Note::from_pretty(&store, "- [x] :10: hi").await;
+        let n = save_line(&store, "- [x] :10: hi").await;
         assert!(n.is_err())
     }
     #[tokio::test]
     async fn test_parse_note_exist() {
         let store = setup_sqlitedb().await;
         let n_base = store.insert_note(NewNote::new("test")).await.unwrap();
-        let n = Note::from_pretty(&store, "- [x] :1: hi")
-            .await
-            .unwrap()
-            .unwrap();
+        let n = save_line(&store, "- [x] :1: hi").await.unwrap().unwrap();
         assert_eq!(n.body, "hi", "Expect body to update.");
         assert_eq!(n.id, n_base.id);
-        assert!(n.completed)
+        assert_eq!(n.status, Status::Done)
     }
     #[tokio::test]
     async fn test_parse_dirty() {
         let store = setup_sqlitedb().await;
         store.insert_note(NewNote::new("test")).await.unwrap();
-        let n = Note::from_pretty(&store, "text\n- [x] :1: hi").await;
+        let n = save_line(&store, "text\n- [x] :1: hi").await;
         assert!(n.is_err())
     }
     #[tokio::test]
     async fn test_update_completion() {
         let store = setup_sqlitedb().await;
         let mut to_insert = NewNote::new("test");
-        to_insert.completed = true;
+        to_insert.status = Status::Done;
         store.insert_note(to_insert).await.unwrap();
-        let n = Note::from_pretty(&store, " - [ ] :1: hi")
-            .await
-            .unwrap()
-            .unwrap();
-        assert!(!n.completed)
+        let n = save_line(&store, " - [ ] :1: hi").await.unwrap().unwrap();
+        assert_eq!(n.status, Status::Todo)
+    }
+    #[test]
+    fn test_meta_tokens() {
+        let table = vec![
+            ("plain text", vec![]),
+            (
+                "estimate effort:3 ticket:ABC-1",
+                vec![("effort", "3"), ("ticket", "ABC-1")],
+            ),
+            ("read https://example.com later", vec![]),
+            ("Note: colon in text", vec![]),
+            ("trailing key:", vec![]),
+        ];
+        for (input, expected) in table {
+            assert_eq!(super::meta_tokens(input), expected, "{}", input);
+        }
+    }
+    #[test]
+    fn test_extract_urls() {
+        let table = vec![
+            ("no links here", vec![]),
+            ("look at https://a.com/x, later", vec!["https://a.com/x"]),
+            (
+                "[docs](http://b.org/y) and <https://c.io>.",
+                vec!["http://b.org/y", "https://c.io"],
+            ),
+        ];
+        for (input, expected) in table {
+            assert_eq!(super::extract_urls(input), expected, "{}", input);
+        }
+    }
+    #[test]
+    fn test_mention_tokens() {
+        let table = vec![
+            ("no mentions, me@mail.com", vec![]),
+            ("1:1 with @Alice, ask @bob_s.", vec!["alice", "bob_s"]),
+            ("lone @ sign", vec![]),
+        ];
+        for (input, expected) in table {
+            assert_eq!(super::mention_tokens(input), expected, "{}", input);
+        }
+    }
+    #[test]
+    fn test_tag_tokens() {
+        let table = vec![
+            ("no tags, issue #12", vec![]),
+            ("bins out #Notify, #home.", vec!["notify", "home"]),
+            ("lone # sign", vec![]),
+        ];
+        for (input, expected) in table {
+            assert_eq!(super::tag_tokens(input), expected, "{}", input);
+        }
+    }
+    #[test]
+    fn test_has_private_token() {
+        assert!(super::has_private_token("salary talk !!private"));
+        assert!(!super::has_private_token("not!!private"));
+    }
+    #[test]
+    fn test_project_token() {
+        let table = vec![
+            ("no project, 1+1", None),
+            ("fix dns +Infra +later", Some("infra")),
+            ("+1 for this", None),
+        ];
+        for (input, expected) in table {
+            assert_eq!(
+                super::project_token(input).as_deref(),
+                expected,
+                "{}",
+                input
+            );
+        }
+    }
+    #[test]
+    fn test_status_round_trip() {
+        for status in Status::ALL {
+            let note = Note {
+                id: 1,
+                body: String::from("hi"),
+                status,
+                section: None,
+                due: None,
+                focus_rank: None,
+                ..Default::default()
+            };
+            let parsed = ParsedNote::parse_pretty_md(note.pretty())
+                .unwrap()
+                .unwrap()
+                .note()
+                .unwrap();
+            assert_eq!(parsed.status, status);
+            assert_eq!(Status::from_str(status.as_str()).unwrap(), status);
+        }
+    }
+    #[test]
+    fn test_parse_markdown() {
+        let table = vec![
+            ("- [ ] buy milk", Status::Todo, None, "buy milk"),
+            (
+                "- [x] ship it <!-- fh:42 -->",
+                Status::Done,
+                Some(42),
+                "ship it",
+            ),
+            (
+                "- [ ] ratio 1:2 <!--fh:7-->",
+                Status::Todo,
+                Some(7),
+                "ratio 1:2",
+            ),
+            (
+                "- [>] review upstream fix (#12)",
+                Status::Doing,
+                None,
+                "review upstream fix (#12)",
+            ),
+            ("- [ ] see (#abc)", Status::Todo, None, "see (#abc)"),
+            ("* [ ] starred", Status::Todo, None, "starred"),
+            (
+                "    + [x] nested <!-- fh:8 -->",
+                Status::Done,
+                Some(8),
+                "nested",
+            ),
+            (
+                "- [ ] <!-- other --> note",
+                Status::Todo,
+                None,
+                "<!-- other --> note",
+            ),
+        ];
+        for (input, status, id, body) in table {
+            let parsed = ParsedNote::parse_pretty_md(input).unwrap().unwrap();
+            let parsed_id = match &parsed {
+                ParsedNote::Note(n) => Some(n.id),
+                ParsedNote::NewNote(_) => None,
+            };
+            assert_eq!(parsed_id, id, "{}", input);
+            let (parsed_status, parsed_body) = match parsed {
+                ParsedNote::Note(n) => (n.status, n.body),
+                ParsedNote::NewNote(n) => (n.status, n.body),
+            };
+            assert_eq!(parsed_status, status, "{}", input);
+            assert_eq!(parsed_body, body, "{}", input);
+        }
+        assert!(ParsedNote::parse_pretty_md("- [ ] ").unwrap().is_none());
+        assert!(ParsedNote::parse_pretty_md("- [x]").unwrap().is_none());
+        assert!(ParsedNote::parse_pretty_md("- [ ]buy").is_err());
+    }
+    #[test]
+    fn test_serde_round_trip() {
+        let day = DayNotes {
+            notes: vec![Note {
+                id: 3,
+                body: String::from("ship"),
+                status: Status::Blocked,
+                ..Default::default()
+            }],
+            note_count: 1,
+            date: NaiveDate::from_ymd_opt(2025, 10, 12).unwrap(),
+            day_text: String::from("Quiet."),
+        };
+        let json = serde_json::to_string(&day).unwrap();
+        let back: DayNotes = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.notes[0].status, Status::Blocked);
+        assert_eq!(back.date, day.date);
+        let parsed = serde_json::to_value(ParsedNote::NewNote(NewNote::new("call"))).unwrap();
+        assert_eq!(parsed["kind"], "newnote");
+        assert!(matches!(
+            serde_json::from_value(parsed).unwrap(),
+            ParsedNote::NewNote(n) if n.body == "call"
+        ));
+    }
+    #[test]
+    fn test_buffer_format_round_trip() {
+        let notes = DayNotes {
+            notes: vec![Note {
+                id: 3,
+                body: String::from("call @sam: re (#4)"),
+                status: Status::Blocked,
+                section: Some(String::from("Work")),
+                due: None,
+                focus_rank: None,
+                ..Default::default()
+            }],
+            note_count: 1,
+            date: NaiveDate::from_str("2025-10-12").unwrap(),
+            day_text: String::new(),
+        };
+        for format in [BufferFormat::Markdown, BufferFormat::Legacy] {
+            let md = notes.pretty_md_with_prompts(&[], format, None);
+            let parsed = ParsedDayNotes::parse_pretty_md(&mut md.lines()).unwrap();
+            assert_eq!(parsed.note_count, 1, "{}", md);
+            let note = parsed.sections.into_iter().next().unwrap().notes;
+            let note = note.into_iter().next().unwrap().note().unwrap();
+            assert_eq!(note.id, 3);
+            assert_eq!(note.status, Status::Blocked);
+            assert_eq!(note.body, "call @sam: re (#4)");
+        }
+        assert!(
+            notes
+                .pretty_md()
+                .contains("- [!] call @sam: re (#4) <!-- fh:3 -->")
+        );
+    }
+    #[test]
+    fn test_parse_bullets() {
+        let md = "# Day: 2025-10-12\n* [ ] starred\n\t+ [x] plus <!-- fh:2 -->\n  - [ ] indented\n* plain list\n---";
+        let parsed = ParsedDayNotes::parse_pretty_md(&mut md.lines()).unwrap();
+        assert_eq!(parsed.note_count, 3);
+        assert!(parsed.notes[1].is_note());
+        assert_eq!(parsed.day_text, "* plain list\n");
+    }
+    #[test]
+    fn test_frontmatter() {
+        let meta = BTreeMap::from([
+            (String::from("location"), String::from("Lisbon: home")),
+            (String::from("weather"), String::from("'sunny'")),
+        ]);
+        let buffer = format!(
+            "{}# Today: 2025-10-12\n\n- [ ] walk\n\n---",
+            frontmatter_md(&meta)
+        );
+        assert_eq!(
+            buffer,
+            "---\nlocation: Lisbon: home\nweather: \"'sunny'\"\n---\n\n# Today: 2025-10-12\n\n- [ ] walk\n\n---"
+        );
+        let (parsed, rest) = split_frontmatter(&buffer).unwrap();
+        assert_eq!(parsed, meta);
+        assert!(rest.starts_with("\n# Today"));
+        let toml = "+++\nmood = 4\nweather = \"rain\"\n+++\n# Today: 2025-10-12\n";
+        let (parsed, rest) = split_frontmatter(toml).unwrap();
+        assert_eq!(parsed["mood"], "4");
+        assert_eq!(parsed["weather"], "rain");
+        assert_eq!(rest, "# Today: 2025-10-12\n");
+        assert!(split_frontmatter("---\nopen: block\n# Today: 2025-10-12").is_err());
+        let plain = "# Today: 2025-10-12\n\n---";
+        assert_eq!(split_frontmatter(plain).unwrap(), (BTreeMap::new(), plain));
+    }
+    #[test]
+    fn test_day_text_round_trip() {
+        let day_text = "Morning:\n\n  indented line\n\n```\n- [ ] not a task\n---\n```\n## Not a section\n- plain list\n";
+        let notes = DayNotes {
+            notes: vec![Note {
+                id: 5,
+                body: String::from("ship"),
+                status: Status::Todo,
+                section: None,
+                due: None,
+                focus_rank: None,
+                ..Default::default()
+            }],
+            note_count: 1,
+            date: NaiveDate::from_str("2025-10-12").unwrap(),
+            day_text: String::from(day_text),
+        };
+        let md = notes.pretty_md();
+        let parsed = ParsedDayNotes::parse_pretty_md(&mut md.lines()).unwrap();
+        assert_eq!(parsed.day_text, day_text);
+        let reparsed = DayNotes {
+            notes: parsed.notes.into_iter().filter_map(|n| n.note()).collect(),
+            note_count: parsed.note_count,
+            date: parsed.date,
+            day_text: parsed.day_text,
+        };
+        assert_eq!(reparsed.pretty_md(), md);
+    }
+    #[test]
+    fn test_parse_header_date() {
+        let date = NaiveDate::from_str("2025-10-12").unwrap();
+        let table = [
+            "Today: 2025-10-12",
+            "Day: 12/10/2025",
+            "10/13/2025",
+            "Oct 12 2025",
+            "Sunday, October 12, 2025",
+            "2025-10-12 (Sunday)",
+            "12 Oct 2025 notes",
+        ];
+        for header in table {
+            let parsed = super::parse_header_date(header, None).unwrap();
+            let expected = if header == "10/13/2025" {
+                date.succ_opt().unwrap()
+            } else {
+                date
+            };
+            assert_eq!(parsed, expected, "{}", header);
+        }
+        let custom = "%A the %d of %B %Y";
+        assert_eq!(
+            super::parse_header_date("Sunday the 12 of October 2025", Some(custom)).unwrap(),
+            date
+        );
+        let err = super::parse_header_date("Today: someday", None).unwrap_err();
+        assert!(err.to_string().contains("2025-10-12"), "{}", err);
+    }
+    #[test]
+    fn test_header_format_round_trip() {
+        let notes = DayNotes {
+            notes: vec![],
+            note_count: 0,
+            date: NaiveDate::from_str("2025-10-12").unwrap(),
+            day_text: String::from("text\n"),
+        };
+        let md = notes.pretty_md_with_prompts(&[], BufferFormat::Markdown, Some("%Y-%m-%d (%A)"));
+        assert!(md.starts_with("# 2025-10-12 (Sunday)\n"), "{}", md);
+        let parsed = ParsedDayNotes::parse_pretty_md(&mut md.lines()).unwrap();
+        assert_eq!(parsed.date, notes.date);
+        assert_eq!(notes.header_md(Some("%Q")), "# Day: Sunday, 2025-10-12");
+        let md = notes.pretty_md();
+        assert!(md.starts_with("# Day: Sunday, 2025-10-12\n"), "{}", md);
+        let parsed = ParsedDayNotes::parse_pretty_md(&mut md.lines()).unwrap();
+        assert_eq!(parsed.date, notes.date);
+    }
+    #[test]
+    fn test_pretty_summary() {
+        let mut notes = DayNotes {
+            notes: vec![],
+            note_count: 0,
+            date: NaiveDate::from_str("2025-10-12").unwrap(),
+            day_text: String::new(),
+        };
+        assert_eq!(notes.pretty_summary(None).lines().count(), 1);
+        notes.notes = [Status::Todo, Status::Doing, Status::Done]
+            .into_iter()
+            .enumerate()
+            .map(|(id, status)| Note {
+                id: id as u32,
+                body: String::from("hi"),
+                status,
+                section: None,
+                due: None,
+                focus_rank: None,
+                ..Default::default()
+            })
+            .collect();
+        let summary = notes.pretty_summary(None);
+        assert!(summary.contains("2 open, 1 done"), "{}", summary);
+        assert_eq!(summary.trim_end().lines().count(), 4, "{}", summary);
     }
     #[test]
     fn test_parse_none() {
@@ -372,9 +1447,15 @@ mod tests {
     #[test]
     fn test_parse_new_notes() {
         let table = vec![
-            ((false, "hi"), " - [ ] : hi"),
-            ((true, "hi"), " - [x] :hi "),
-            ((true, "1 text with spaces"), " - [x] :1 text with spaces"),
+            ((Status::Todo, "hi"), " - [ ] : hi"),
+            ((Status::Done, "hi"), " - [x] :hi "),
+            (
+                (Status::Done, "1 text with spaces"),
+                " - [x] :1 text with spaces",
+            ),
+            ((Status::Doing, "hi"), " - [>] : hi"),
+            ((Status::Blocked, "hi"), " - [!] : hi"),
+            ((Status::Cancelled, "hi"), " - [-] : hi"),
         ];
         for ((comp, text), input) in table {
             println!("{}", input);
@@ -383,17 +1464,18 @@ mod tests {
                 .unwrap()
                 .new_note()
                 .unwrap();
-            assert_eq!(note.completed, comp, "{}", input);
+            assert_eq!(note.status, comp, "{}", input);
             assert_eq!(note.body, text, "{}", input);
         }
     }
     #[test]
     fn test_parse_notes() {
         let table = vec![
-            ((false, 42, "hi"), " - [ ] :42: hi"),
-            ((true, 34, "hi"), " - [x] :34: hi"),
+            ((Status::Todo, 42, "hi"), " - [ ] :42: hi"),
+            ((Status::Done, 34, "hi"), " - [x] :34: hi"),
+            ((Status::Blocked, 34, "hi"), " - [!] :34: hi"),
             (
-                (true, 123456908, "text with spaces"),
+                (Status::Done, 123456908, "text with spaces"),
                 " - [x] :123456908: text with spaces",
             ),
         ];
@@ -403,7 +1485,7 @@ mod tests {
                 .unwrap()
                 .note()
                 .unwrap();
-            assert_eq!(note.completed, comp);
+            assert_eq!(note.status, comp);
             assert_eq!(note.id, id);
             assert_eq!(note.body, text);
         }
@@ -419,6 +1501,7 @@ mod tests {
             " - [ ];34: test",
             " - [ ]:34; test",
             " - [ ]:hi: test",
+            " - [?] : unknown status",
         ];
         for input in table {
             let note = ParsedNote::parse_pretty_md(input);
@@ -454,4 +1537,156 @@ mod tests {
         assert!(notes.notes[0].is_note(), "{:?}", notes.notes);
         assert!(notes.notes[1].is_new_note());
     }
+    #[test]
+    fn test_template_md() {
+        let notes = DayNotes {
+            notes: vec![],
+            note_count: 0,
+            date: NaiveDate::from_str("2025-10-12").unwrap(),
+            day_text: String::new(),
+        };
+        let md = notes.template_md("- [ ] : plan {{date}}", None);
+        let mut lines = md.lines();
+        let parsed = ParsedDayNotes::parse_pretty_md(&mut lines).unwrap();
+        let note = parsed.notes.into_iter().next().unwrap().new_note().unwrap();
+        assert_eq!(note.body, "plan 2025-10-12");
+    }
+    #[test]
+    fn test_parse_prompts() {
+        let notes = DayNotes {
+            notes: vec![],
+            note_count: 0,
+            date: NaiveDate::from_str("2025-10-12").unwrap(),
+            day_text: String::new(),
+        };
+        let prompts = vec![String::from("One thing?"), String::from("Blockers?")];
+        let md = notes.pretty_md_with_prompts(&prompts, BufferFormat::Markdown, None);
+        let parsed =
+            ParsedDayNotes::parse_pretty_md_with_prompts(&mut md.lines(), &prompts, None).unwrap();
+        assert_eq!(parsed.day_text, "");
+        let md = md.replace("<!-- One thing? -->\n", "<!-- One thing? -->\nShip it\n");
+        let parsed =
+            ParsedDayNotes::parse_pretty_md_with_prompts(&mut md.lines(), &prompts, None).unwrap();
+        assert_eq!(parsed.day_text, "One thing?\nShip it\n");
+    }
+    #[test]
+    fn test_parse_comments() {
+        let md = "# Day: 2025-10-12\n// add notes above the line\n- [ ] call Sam\n<!-- Line 4: bad box -->\nWent well.\n```\n// kept in code\n```\n---";
+        let parsed = ParsedDayNotes::parse_pretty_md(&mut md.lines()).unwrap();
+        assert_eq!(parsed.notes.len(), 1);
+        assert_eq!(parsed.day_text, "Went well.\n```\n// kept in code\n```\n");
+    }
+    #[test]
+    fn test_parse_day_note_sections() {
+        let mut input = String::new();
+        File::open("test/day_notes_sections.md")
+            .unwrap()
+            .read_to_string(&mut input)
+            .unwrap();
+        let mut lines = input.lines();
+        let notes = ParsedDayNotes::parse_pretty_md(&mut lines).unwrap();
+        assert_eq!(notes.notes.len(), 1);
+        assert_eq!(notes.sections.len(), 2);
+        assert_eq!(notes.sections[0].name, "Work");
+        assert_eq!(notes.sections[0].notes.len(), 2);
+        assert_eq!(notes.sections[1].name, "Personal");
+        assert_eq!(notes.sections[1].notes.len(), 1);
+        assert_eq!(notes.note_count, 4);
+        let note = notes
+            .sections
+            .into_iter()
+            .nth(1)
+            .unwrap()
+            .notes
+            .into_iter()
+            .next();
+        let note = note.unwrap().new_note().unwrap();
+        assert_eq!(note.section.as_deref(), Some("Personal"));
+    }
+
+    mod props {
+        use chrono::NaiveDate;
+        use proptest::prelude::*;
+
+        use super::super::{
+            BufferFormat, DayNotes, Note, ParsedDayNotes, ParsedNote, Status, parse_header_date,
+        };
+
+        fn status() -> impl Strategy<Value = Status> {
+            proptest::sample::select(Status::ALL.to_vec())
+        }
+        /// Single line bodies as the buffer holds them, trimmed and non-empty.
+        fn body() -> impl Strategy<Value = String> {
+            "[^\\n]{1,40}"
+                .prop_map(|b| b.trim().to_string())
+                .prop_filter("blank body", |b| !b.is_empty())
+        }
+        fn note() -> impl Strategy<Value = Note> {
+            (1..u32::MAX, body(), status()).prop_map(|(id, body, status)| Note {
+                id,
+                body,
+                status,
+                ..Default::default()
+            })
+        }
+        fn format() -> impl Strategy<Value = BufferFormat> {
+            prop_oneof![Just(BufferFormat::Markdown), Just(BufferFormat::Legacy)]
+        }
+        fn parsed(line: &str) -> (Status, Option<u32>, String) {
+            match ParsedNote::parse_pretty_md(line).unwrap().unwrap() {
+                ParsedNote::Note(n) => (n.status, Some(n.id), n.body),
+                ParsedNote::NewNote(n) => (n.status, None, n.body),
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn note_round_trip(note in note(), format in format()) {
+                let line = note.pretty_md(format);
+                prop_assert_eq!(parsed(&line), (note.status, Some(note.id), note.body));
+            }
+            #[test]
+            fn day_round_trip(
+                notes in proptest::collection::vec(note(), 0..8),
+                day_text in "[a-zA-Z0-9 ,.]{0,40}",
+                days in 0..20_000u64,
+            ) {
+                let day = DayNotes {
+                    note_count: notes.len() as u32,
+                    notes,
+                    date: NaiveDate::from_ymd_opt(1980, 1, 1).unwrap() + chrono::Days::new(days),
+                    // Saved day text ends in a newline, as the buffer reads it.
+                    day_text: match day_text.trim() {
+                        "" => String::new(),
+                        text => format!("{}\n", text),
+                    },
+                };
+                let md = day.pretty_md();
+                let back = ParsedDayNotes::parse_pretty_md(&mut md.lines()).unwrap();
+                prop_assert_eq!(back.date, day.date);
+                prop_assert_eq!(&back.day_text, &day.day_text);
+                let notes = back
+                    .notes
+                    .into_iter()
+                    .map(|n| match n {
+                        ParsedNote::Note(n) => (n.status, n.id, n.body),
+                        ParsedNote::NewNote(n) => (n.status, 0, n.body),
+                    })
+                    .collect::<Vec<_>>();
+                let expected = day
+                    .notes
+                    .into_iter()
+                    .map(|n| (n.status, n.id, n.body))
+                    .collect::<Vec<_>>();
+                prop_assert_eq!(notes, expected);
+            }
+            #[test]
+            fn parse_never_panics(line in "\\PC{0,30}", buffer in "(\\PC{0,20}\\n){0,8}") {
+                let _ = ParsedNote::parse_pretty_md(&line);
+                let _ = parse_header_date(&line, Some("%Y-%m-%d %A"));
+                let _ = ParsedDayNotes::parse_pretty_md(&mut buffer.lines());
+                let _ = ParsedDayNotes::parse_pretty_md(&mut format!("# {}\n{}", line, buffer).lines());
+            }
+        }
+    }
 }
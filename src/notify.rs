@@ -0,0 +1,152 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, TimeZone, Utc};
+use notify_rust::Notification;
+
+use crate::{notes::Note, push::PushConfig, store::NoteStore};
+
+/// Send a desktop notification for each open note due within `window_days` of `today` that
+/// wasn't notified for its due date yet, returning the notes notified.
+/// With `dry_run` the notes are returned without notifying or marking them.
+pub async fn notify_due(
+    store: &NoteStore,
+    today: NaiveDate,
+    window_days: u32,
+    dry_run: bool,
+) -> Result<Vec<Note>> {
+    let until = today + Days::new(window_days as u64);
+    let notes = store.unnotified_due(until).await?;
+    if dry_run {
+        return Ok(notes);
+    }
+    for note in &notes {
+        let Some(due) = note.due else {
+            continue;
+        };
+        let notification = Notification::new()
+            .appname("fh")
+            .summary(&summary(due, today))
+            .body(note.display_body())
+            .finalize();
+        tokio::task::spawn_blocking(move || notification.show())
+            .await?
+            .context("Failed showing notification, is a notification daemon running?")?;
+        store.mark_notified(note.id, due).await?;
+    }
+    Ok(notes)
+}
+
+/// Notify about every reminder due by `now` on the desktop, and through ntfy or Gotify when
+/// `[push]` is configured, removing each once sent. Returns the notes reminded about.
+/// With `dry_run` the notes are returned without notifying or removing the reminders.
+pub async fn send_reminders(
+    store: &NoteStore,
+    push: &PushConfig,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<Vec<Note>> {
+    let notes = store
+        .reminders(now, true)
+        .await?
+        .into_iter()
+        .map(|(_, note)| note)
+        .collect::<Vec<_>>();
+    if dry_run {
+        return Ok(notes);
+    }
+    for note in &notes {
+        if push.is_enabled()
+            && let Err(e) = push
+                .send(&format!("Reminder: {}", note.display_body()))
+                .await
+        {
+            tracing::warn!("Push for note {} failed: {:#}", note.id, e);
+        }
+        let notification = Notification::new()
+            .appname("fh")
+            .summary("Reminder")
+            .body(note.display_body())
+            .finalize();
+        tokio::task::spawn_blocking(move || notification.show())
+            .await?
+            .context("Failed showing notification, is a notification daemon running?")?;
+        store.set_reminder(note.id, None).await?;
+    }
+    Ok(notes)
+}
+
+/// When a reminder at `time`, like `15:30`, goes off. On `date` when given, otherwise the next
+/// time it comes after `now`, today or tomorrow.
+pub fn remind_at<Tz: TimeZone>(
+    time: &str,
+    date: Option<NaiveDate>,
+    now: DateTime<Tz>,
+) -> Result<DateTime<Utc>> {
+    let time = NaiveTime::parse_from_str(time, "%H:%M")
+        .context(format!("Invalid time {}, expect HH:MM like 15:30.", time))?;
+    let today = now.date_naive();
+    let at = |day: NaiveDate| {
+        now.timezone()
+            .from_local_datetime(&day.and_time(time))
+            .earliest()
+            .map(|at| at.with_timezone(&Utc))
+            .ok_or(anyhow!(
+                "{} {} doesn't exist in the local time zone.",
+                day,
+                time
+            ))
+    };
+    match date {
+        Some(day) => {
+            let at = at(day)?;
+            if at <= now.with_timezone(&Utc) {
+                return Err(anyhow!("{} {} has already passed.", day, time));
+            }
+            Ok(at)
+        }
+        None => match at(today)? {
+            at if at > now.with_timezone(&Utc) => Ok(at),
+            _ => at(today + Days::new(1)),
+        },
+    }
+}
+
+fn summary(due: NaiveDate, today: NaiveDate) -> String {
+    match (due - today).num_days() {
+        0 => String::from("Due today"),
+        1 => String::from("Due tomorrow"),
+        days if days < 0 => format!("Overdue since {}", due),
+        _ => format!("Due {}", due),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use super::{remind_at, summary};
+
+    #[test]
+    fn test_summary() {
+        let today = NaiveDate::from_ymd_opt(2025, 10, 13).unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 10, d).unwrap();
+        assert_eq!(summary(day(13), today), "Due today");
+        assert_eq!(summary(day(14), today), "Due tomorrow");
+        assert_eq!(summary(day(20), today), "Due 2025-10-20");
+        assert_eq!(summary(day(10), today), "Overdue since 2025-10-10");
+    }
+
+    #[test]
+    fn test_remind_at() {
+        let now = Utc.with_ymd_and_hms(2025, 10, 13, 12, 0, 0).unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 10, d).unwrap();
+        let at = |d, h, m| Utc.with_ymd_and_hms(2025, 10, d, h, m, 0).unwrap();
+        assert_eq!(remind_at("15:30", None, now).unwrap(), at(13, 15, 30));
+        assert_eq!(remind_at("09:00", None, now).unwrap(), at(14, 9, 0));
+        assert_eq!(
+            remind_at("09:00", Some(day(20)), now).unwrap(),
+            at(20, 9, 0)
+        );
+        assert!(remind_at("09:00", Some(day(13)), now).is_err());
+        assert!(remind_at("9am", None, now).is_err());
+    }
+}
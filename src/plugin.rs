@@ -0,0 +1,64 @@
+use std::{
+    ffi::OsStr,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, anyhow};
+
+/// Prefix of plugin binaries, `fh foo` runs `fh-foo` from the PATH.
+pub const PREFIX: &str = "fh-";
+
+/// The executable plugin for the subcommand `name` in the directories of `path`, the first found wins.
+pub fn find(name: &str, path: &OsStr) -> Option<PathBuf> {
+    std::env::split_paths(path)
+        .map(|dir| dir.join(format!("{}{}", PREFIX, name)))
+        .find(|p| {
+            p.metadata()
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        })
+}
+
+/// Run the plugin for `args[0]` with the rest of `args`, returning its exit code.
+/// It finds the database in `FH_DB`, the workspace in `FH_WORKSPACE` and the config directory
+/// in `FH_CONFIG_DIR`.
+pub fn run(args: &[String], db_path: &Path, workspace: &str, config_dir: &Path) -> Result<i32> {
+    let (name, rest) = args.split_first().ok_or(anyhow!("Missing subcommand."))?;
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let plugin = find(name, &path).ok_or(anyhow!(
+        "Unknown command {}, and no {}{} on the PATH.",
+        name,
+        PREFIX,
+        name
+    ))?;
+    let status = Command::new(&plugin)
+        .args(rest)
+        .env("FH_DB", db_path)
+        .env("FH_WORKSPACE", workspace)
+        .env("FH_CONFIG_DIR", config_dir)
+        .status()
+        .context(format!("Failed running {}.", plugin.display()))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, os::unix::fs::PermissionsExt};
+
+    use super::find;
+
+    #[test]
+    fn test_find() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let script = first.path().join("fh-stats");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        let executable = second.path().join("fh-stats");
+        fs::write(&executable, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+        let path = env::join_paths([first.path(), second.path()]).unwrap();
+        assert_eq!(find("stats", &path), Some(executable));
+        assert_eq!(find("missing", &path), None);
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{notes::Note, store::NoteStore};
+
+/// The `[push]` table of the config, phone notifications sent through ntfy or Gotify when a
+/// note carrying `tag` is completed.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PushConfig {
+    /// Tag marking the notes to push, from `bulk tag` or a `#tag` word in the body.
+    pub tag: String,
+    /// ntfy topic url, e.g. `https://ntfy.sh/our-house`.
+    pub ntfy: Option<String>,
+    /// Token sent to a protected ntfy topic.
+    pub ntfy_token: Option<String>,
+    /// Base url of a Gotify server with the application token to post as.
+    pub gotify_url: Option<String>,
+    pub gotify_token: Option<String>,
+}
+impl Default for PushConfig {
+    fn default() -> Self {
+        PushConfig {
+            tag: String::from("notify"),
+            ntfy: None,
+            ntfy_token: None,
+            gotify_url: None,
+            gotify_token: None,
+        }
+    }
+}
+impl PushConfig {
+    /// Whether an ntfy topic or a Gotify server is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.ntfy.is_some() || self.gotify_url.is_some()
+    }
+    /// Whether the note is marked for pushing, by its tags or a `#tag` word in its body.
+    pub fn wants(&self, note: &Note, tags: &[String]) -> bool {
+        let word = format!("#{}", self.tag);
+        tags.contains(&self.tag) || note.body.split_whitespace().any(|w| w == word)
+    }
+    /// Push the completion of a tagged note, a failing endpoint is logged so the change stands.
+    pub async fn note_completed(&self, store: &NoteStore, note: &Note) {
+        if !self.is_enabled() {
+            return;
+        }
+        let tags = match store.get_tags(note.id).await {
+            Ok(tags) => tags,
+            Err(e) => {
+                tracing::warn!("Push for note {} failed: {:#}", note.id, e);
+                return;
+            }
+        };
+        if !self.wants(note, &tags) {
+            return;
+        }
+        if let Err(e) = self.send(&format!("Done: {}", note.body)).await {
+            tracing::warn!("Push for note {} failed: {:#}", note.id, e);
+        }
+    }
+    /// Send `message` to every configured endpoint.
+    pub async fn send(&self, message: &str) -> Result<()> {
+        let client = reqwest::Client::new();
+        if let Some(url) = &self.ntfy {
+            let mut request = client
+                .post(url)
+                .header("Title", "fh")
+                .body(message.to_string());
+            if let Some(token) = &self.ntfy_token {
+                request = request.bearer_auth(token);
+            }
+            check(request.send().await, url)?;
+        }
+        if let Some(url) = &self.gotify_url {
+            let token = self
+                .gotify_token
+                .as_ref()
+                .ok_or(anyhow!("Set gotify_token in the [push] config."))?;
+            let url = format!("{}/message", url.trim_end_matches('/'));
+            let request = client
+                .post(&url)
+                .header("X-Gotify-Key", token)
+                .json(&json!({ "title": "fh", "message": message }));
+            check(request.send().await, &url)?;
+        }
+        Ok(())
+    }
+}
+
+fn check(response: reqwest::Result<reqwest::Response>, url: &str) -> Result<()> {
+    let status = response
+        .context(format!("Failed reaching {}.", url))?
+        .status();
+    if !status.is_success() {
+        return Err(anyhow!("{} answered {}.", url, status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{Router, http::HeaderMap, routing::post};
+    use tokio::net::TcpListener;
+
+    use super::PushConfig;
+    use crate::{
+        notes::{NewNote, Note, Status},
+        store::NoteStore,
+    };
+
+    #[tokio::test]
+    async fn test_send() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = |path: &'static str| {
+            let received = received.clone();
+            post(move |headers: HeaderMap, body: String| async move {
+                let key = headers
+                    .get("X-Gotify-Key")
+                    .map(|v| v.to_str().unwrap().to_string());
+                received.lock().unwrap().push((path, key, body));
+            })
+        };
+        let app = Router::new()
+            .route("/house", log("ntfy"))
+            .route("/message", log("gotify"));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let config = PushConfig {
+            ntfy: Some(format!("{}/house", url)),
+            gotify_url: Some(format!("{}/", url)),
+            gotify_token: Some(String::from("secret")),
+            ..Default::default()
+        };
+        config.send("Done: bins out").await.unwrap();
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received[0], ("ntfy", None, String::from("Done: bins out")));
+        assert_eq!(received[1].0, "gotify");
+        assert_eq!(received[1].1.as_deref(), Some("secret"));
+        assert!(received[1].2.contains("bins out"));
+        let note = |body: &str| Note {
+            body: body.to_string(),
+            ..Default::default()
+        };
+        assert!(config.wants(&note("bins out #notify"), &[]));
+        assert!(config.wants(&note("bins out"), &[String::from("notify")]));
+        assert!(!config.wants(&note("bins out #notifyme"), &[]));
+    }
+
+    #[tokio::test]
+    async fn test_store_pushes_completions() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        let app = Router::new().route(
+            "/house",
+            post(move |body: String| async move { log.lock().unwrap().push(body) }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let store = NoteStore::open_in_memory()
+            .await
+            .unwrap()
+            .with_push(PushConfig {
+                ntfy: Some(format!("{}/house", url)),
+                ..Default::default()
+            });
+        let tagged = store
+            .insert_note(NewNote::new("bins out #notify"))
+            .await
+            .unwrap();
+        let plain = store.insert_note(NewNote::new("dishes")).await.unwrap();
+        store.set_status(tagged.id, Status::Done).await.unwrap();
+        store.set_status(tagged.id, Status::Done).await.unwrap();
+        store.set_status(plain.id, Status::Done).await.unwrap();
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![String::from("Done: bins out #notify")]
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{Datelike, Days, Months, NaiveDate, TimeDelta, Weekday};
+use sqlx::{QueryBuilder, Sqlite};
+
+use crate::{filter::push_status, notes::StatusMatch};
+
+/// Comparison applied to a date term, written as a prefix of the value (`created:>2025-01-01`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+impl Cmp {
+    /// Split a leading comparison off a value, no prefix means equality.
+    fn split(value: &str) -> (Cmp, &str) {
+        for (prefix, cmp) in [
+            ("<=", Cmp::Le),
+            (">=", Cmp::Ge),
+            ("<", Cmp::Lt),
+            (">", Cmp::Gt),
+            ("=", Cmp::Eq),
+        ] {
+            if let Some(rest) = value.strip_prefix(prefix) {
+                return (cmp, rest);
+            }
+        }
+        (Cmp::Eq, value)
+    }
+    fn sql(&self) -> &'static str {
+        match self {
+            Cmp::Lt => " < ",
+            Cmp::Le => " <= ",
+            Cmp::Eq => " = ",
+            Cmp::Ge => " >= ",
+            Cmp::Gt => " > ",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Status(StatusMatch),
+    Tag(String),
+    Section(String),
+    Project(String),
+    /// Date the note was created.
+    Created(Cmp, NaiveDate),
+    /// Day the note belongs to.
+    Day(Cmp, NaiveDate),
+    Due(Cmp, NaiveDate),
+    /// Bare words are matched against the note body.
+    Text(String),
+}
+
+/// A note query such as `status:open tag:work created:>2025-01-01 due:<eow`,
+/// every term has to match.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+impl Query {
+    /// Parse a query, relative dates (`today`, `eow`, ...) are resolved against `today`.
+    /// Words with an unknown key, like `http://x` or `10:30`, are searched for as text.
+    pub fn parse(s: &str, today: NaiveDate) -> Result<Query> {
+        let mut terms = vec![];
+        for word in s.split_whitespace() {
+            let Some((key, value)) = word.split_once(':') else {
+                terms.push(Term::Text(String::from(word)));
+                continue;
+            };
+            let term = match key {
+                "status" => Term::Status(StatusMatch::from_str(value)?),
+                "tag" => Term::Tag(String::from(value)),
+                "section" => Term::Section(String::from(value)),
+                "project" => Term::Project(value.trim_start_matches('+').to_lowercase()),
+                "created" | "day" | "due" => {
+                    let (cmp, value) = Cmp::split(value);
+                    let date = parse_date(value, today)?;
+                    match key {
+                        "created" => Term::Created(cmp, date),
+                        "day" => Term::Day(cmp, date),
+                        _ => Term::Due(cmp, date),
+                    }
+                }
+                _ => Term::Text(String::from(word)),
+            };
+            terms.push(term);
+        }
+        Ok(Query { terms })
+    }
+    /// Push the conditions for the query, expects `note as n` joined to `day as d` in the query.
+    pub fn push_conditions(&self, qb: &mut QueryBuilder<'static, Sqlite>) {
+        qb.push(" WHERE n.deleted_at IS NULL");
+        for term in &self.terms {
+            match term {
+                Term::Status(status) => push_status(qb, *status),
+                Term::Tag(tag) => {
+                    qb.push(
+                        " AND EXISTS (SELECT 1 FROM note_tag as t WHERE t.note_id = n.id AND t.tag = ",
+                    )
+                    .push_bind(tag.clone())
+                    .push(")");
+                }
+                Term::Section(section) => {
+                    qb.push(" AND n.section = ").push_bind(section.clone());
+                }
+                Term::Project(project) => {
+                    qb.push(" AND n.project_key = (SELECT id FROM project WHERE name = ")
+                        .push_bind(project.clone())
+                        .push(")");
+                }
+                Term::Created(cmp, date) => {
+                    qb.push(" AND date(n.created_at)")
+                        .push(cmp.sql())
+                        .push_bind(*date);
+                }
+                Term::Day(cmp, date) => {
+                    qb.push(" AND d.date").push(cmp.sql()).push_bind(*date);
+                }
+                Term::Due(cmp, date) => {
+                    qb.push(" AND n.due").push(cmp.sql()).push_bind(*date);
+                }
+                Term::Text(text) => {
+                    qb.push(" AND instr(lower(n.body), ")
+                        .push_bind(text.to_lowercase())
+                        .push(") > 0");
+                }
+            }
+        }
+    }
+}
+
+/// Parse an absolute `YYYY-MM-DD` date or one of `today`, `yesterday`, `tomorrow`,
+/// `eow` (coming Sunday) and `eom` (last day of the month).
+pub fn parse_date(s: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let date = match s {
+        "today" => Some(today),
+        "yesterday" => today.checked_sub_days(Days::new(1)),
+        "tomorrow" => today.checked_add_days(Days::new(1)),
+        "eow" => {
+            today.checked_add_days(Days::new(6 - today.weekday().num_days_from_monday() as u64))
+        }
+        "eom" => {
+            let (year, month) = match today.month() {
+                12 => (today.year() + 1, 1),
+                m => (today.year(), m + 1),
+            };
+            NaiveDate::from_ymd_opt(year, month, 1).and_then(|d| d.pred_opt())
+        }
+        _ => Some(NaiveDate::from_str(s).context(format!(
+            "Invalid date {}, expect YYYY-MM-DD, today, yesterday, tomorrow, eow or eom.",
+            s
+        ))?),
+    };
+    date.ok_or(anyhow!("Date {} out of range.", s))
+}
+
+/// First and last day of the week holding `day`, weeks starting on `week_start`.
+pub fn week_range(day: NaiveDate, week_start: Weekday) -> (NaiveDate, NaiveDate) {
+    let week = day.week(week_start);
+    (week.first_day(), week.last_day())
+}
+
+/// Days of page `page`, counted from 1, when the days from `start_day` to `end_day` are split
+/// into pages of `per_page` days, `None` past the last page.
+pub fn page_range(
+    start_day: NaiveDate,
+    end_day: NaiveDate,
+    page: u32,
+    per_page: u32,
+) -> Option<(NaiveDate, NaiveDate)> {
+    let skip = page.checked_sub(1)? as u64 * per_page as u64;
+    let first = start_day.checked_add_days(Days::new(skip))?;
+    let last = first.checked_add_days(Days::new(per_page.checked_sub(1)? as u64))?;
+    (first <= end_day).then_some((first, last.min(end_day)))
+}
+
+/// Number of `per_page` day pages the days from `start_day` to `end_day` fill.
+pub fn page_count(start_day: NaiveDate, end_day: NaiveDate, per_page: u32) -> u32 {
+    let days = (end_day - start_day).num_days() + 1;
+    (days.max(0) as u64).div_ceil(per_page.max(1) as u64) as u32
+}
+
+/// Monday of an ISO week written like `2025-W14`.
+pub fn parse_iso_week(s: &str) -> Result<NaiveDate> {
+    let err = || anyhow!("Invalid week {}, expect an ISO week like 2025-W14.", s);
+    let (year, week) = s.split_once("-W").or(s.split_once("-w")).ok_or_else(err)?;
+    let year = year.parse::<i32>().map_err(|_| err())?;
+    let week = week.parse::<u32>().map_err(|_| err())?;
+    NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).ok_or_else(err)
+}
+
+/// A goal's period, a quarter like `2025Q3` or a year like `2025`.
+pub fn parse_goal_period(s: &str) -> Result<String> {
+    let err = || {
+        anyhow!(
+            "Invalid period {}, expect a quarter like 2025Q3 or a year.",
+            s
+        )
+    };
+    let (year, quarter) = match s.split_once(['Q', 'q']) {
+        Some((year, quarter)) => (year, Some(quarter)),
+        None => (s, None),
+    };
+    let year = year.parse::<u16>().map_err(|_| err())?;
+    match quarter.map(str::parse::<u8>) {
+        None => Ok(year.to_string()),
+        Some(Ok(q @ 1..=4)) => Ok(format!("{}Q{}", year, q)),
+        Some(_) => Err(err()),
+    }
+}
+
+/// Date a span like `3d`, `2w`, `1m` or `1y` before `today`.
+pub fn date_ago(span: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let err = || {
+        anyhow!(
+            "Invalid span {}, expect a count and one of d, w, m or y.",
+            span
+        )
+    };
+    let unit = span.chars().last().ok_or_else(err)?;
+    let count = span[..span.len() - unit.len_utf8()]
+        .parse::<u32>()
+        .map_err(|_| err())?;
+    let date = match unit {
+        'd' => today.checked_sub_days(Days::new(count as u64)),
+        'w' => today.checked_sub_days(Days::new(count as u64 * 7)),
+        'm' => today.checked_sub_months(Months::new(count)),
+        'y' => today.checked_sub_months(Months::new(count * 12)),
+        _ => return Err(err()),
+    };
+    date.ok_or(anyhow!("Date {} before {} out of range.", span, today))
+}
+
+/// Length of a span like `30m`, `24h`, `7d` or `2w`.
+pub fn parse_duration(span: &str) -> Result<TimeDelta> {
+    let err = || {
+        anyhow!(
+            "Invalid duration {}, expect a count and one of m, h, d or w.",
+            span
+        )
+    };
+    let unit = span.chars().last().ok_or_else(err)?;
+    let count = span[..span.len() - unit.len_utf8()]
+        .parse::<i64>()
+        .map_err(|_| err())?;
+    let duration = match unit {
+        'm' => TimeDelta::try_minutes(count),
+        'h' => TimeDelta::try_hours(count),
+        'd' => TimeDelta::try_days(count),
+        'w' => TimeDelta::try_weeks(count),
+        _ => return Err(err()),
+    };
+    match duration {
+        Some(d) if d > TimeDelta::zero() => Ok(d),
+        _ => Err(err()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeDelta, Weekday};
+
+    use super::{
+        Cmp, Query, Term, date_ago, page_count, page_range, parse_date, parse_duration,
+        parse_goal_period, parse_iso_week, week_range,
+    };
+    use crate::notes::StatusMatch;
+
+    #[test]
+    fn test_parse_query() {
+        // A Wednesday.
+        let today = NaiveDate::from_ymd_opt(2025, 10, 15).unwrap();
+        let query = Query::parse(
+            "status:open tag:work created:>2025-01-01 due:<eow milk",
+            today,
+        )
+        .unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                Term::Status(StatusMatch::Open),
+                Term::Tag(String::from("work")),
+                Term::Created(Cmp::Gt, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()),
+                Term::Due(Cmp::Lt, NaiveDate::from_ymd_opt(2025, 10, 19).unwrap()),
+                Term::Text(String::from("milk")),
+            ]
+        );
+        let query = Query::parse("http://x colour:red", today).unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                Term::Text(String::from("http://x")),
+                Term::Text(String::from("colour:red")),
+            ]
+        );
+    }
+    #[test]
+    fn test_parse_query_fail() {
+        let today = NaiveDate::from_ymd_opt(2025, 10, 15).unwrap();
+        let table = vec!["status:maybe", "due:<someday"];
+        for input in table {
+            assert!(Query::parse(input, today).is_err(), "{}", input);
+        }
+    }
+    #[test]
+    fn test_parse_date() {
+        let today = NaiveDate::from_ymd_opt(2025, 12, 15).unwrap();
+        let table = vec![
+            ("today", (2025, 12, 15)),
+            ("yesterday", (2025, 12, 14)),
+            ("eow", (2025, 12, 21)),
+            ("eom", (2025, 12, 31)),
+            ("2024-02-29", (2024, 2, 29)),
+        ];
+        for (input, (y, m, d)) in table {
+            let date = parse_date(input, today).unwrap();
+            assert_eq!(date, NaiveDate::from_ymd_opt(y, m, d).unwrap(), "{}", input);
+        }
+    }
+    #[test]
+    fn test_date_ago() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let table = vec![
+            ("3d", (2024, 3, 28)),
+            ("2w", (2024, 3, 17)),
+            ("1m", (2024, 2, 29)),
+            ("2y", (2022, 3, 31)),
+        ];
+        for (input, (y, m, d)) in table {
+            let date = date_ago(input, today).unwrap();
+            assert_eq!(date, NaiveDate::from_ymd_opt(y, m, d).unwrap(), "{}", input);
+        }
+        for input in ["", "y", "3", "3q", "-1d"] {
+            assert!(date_ago(input, today).is_err(), "{}", input);
+        }
+    }
+    #[test]
+    fn test_week_range() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 10, d).unwrap();
+        // A Wednesday.
+        assert_eq!(week_range(date(15), Weekday::Mon), (date(13), date(19)));
+        assert_eq!(week_range(date(15), Weekday::Sun), (date(12), date(18)));
+        assert_eq!(week_range(date(12), Weekday::Sun), (date(12), date(18)));
+        assert_eq!(
+            parse_iso_week("2025-W14").unwrap(),
+            NaiveDate::from_ymd_opt(2025, 3, 31).unwrap()
+        );
+        for input in ["2025-14", "2025-W54", "W14"] {
+            assert!(parse_iso_week(input).is_err(), "{}", input);
+        }
+    }
+    #[test]
+    fn test_page_range() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 10, d).unwrap();
+        assert_eq!(
+            page_range(date(1), date(31), 1, 7),
+            Some((date(1), date(7)))
+        );
+        assert_eq!(
+            page_range(date(1), date(31), 5, 7),
+            Some((date(29), date(31)))
+        );
+        assert_eq!(page_range(date(1), date(31), 6, 7), None);
+        assert_eq!(page_range(date(1), date(31), 0, 7), None);
+        assert_eq!(page_count(date(1), date(31), 7), 5);
+        assert_eq!(page_count(date(2), date(1), 7), 0);
+    }
+    #[test]
+    fn test_parse_goal_period() {
+        assert_eq!(parse_goal_period("2025q3").unwrap(), "2025Q3");
+        assert_eq!(parse_goal_period("2025").unwrap(), "2025");
+        for input in ["", "2025Q5", "2025Q", "Q3", "25x"] {
+            assert!(parse_goal_period(input).is_err(), "{}", input);
+        }
+    }
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("24h").unwrap(), TimeDelta::hours(24));
+        assert_eq!(parse_duration("2w").unwrap(), TimeDelta::days(14));
+        for input in ["", "h", "0d", "-1h", "3y"] {
+            assert!(parse_duration(input).is_err(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::notes::{DayNotes, Note, Status};
+
+/// The `[remote]` table of the config, a server started with `fh serve` to use instead of the local database.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Base url of the server, `FH_REMOTE` overrides it.
+    pub url: Option<String>,
+    /// Bearer token, it needs the write scope to change notes.
+    pub token: Option<String>,
+}
+
+/// A note as the server sends it.
+#[derive(Deserialize)]
+struct RemoteNote {
+    id: u32,
+    body: String,
+    status: String,
+    section: Option<String>,
+    due: Option<NaiveDate>,
+}
+impl TryFrom<RemoteNote> for Note {
+    type Error = anyhow::Error;
+    fn try_from(value: RemoteNote) -> Result<Self> {
+        Ok(Note {
+            id: value.id,
+            body: value.body,
+            status: Status::from_str(&value.status)?,
+            section: value.section,
+            due: value.due,
+            focus_rank: None,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RemoteDay {
+    date: NaiveDate,
+    day_text: String,
+    notes: Vec<RemoteNote>,
+}
+
+#[derive(Serialize)]
+struct DaysParams {
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+/// Notes kept by a server over its HTTP API, a thin client for machines without the database.
+pub struct RemoteStore {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+}
+impl RemoteStore {
+    /// The configured remote, `None` when no url is set.
+    pub fn from_config(config: &RemoteConfig) -> Result<Option<RemoteStore>> {
+        match std::env::var("FH_REMOTE").ok().or(config.url.clone()) {
+            Some(url) => RemoteStore::new(url, config).map(Some),
+            None => Ok(None),
+        }
+    }
+    /// The server at `url`, with the token of the `[remote]` config.
+    pub fn new(url: String, config: &RemoteConfig) -> Result<RemoteStore> {
+        let token = config
+            .token
+            .clone()
+            .ok_or(anyhow!("Set token in the [remote] config to use {}.", url))?;
+        Ok(RemoteStore {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            token,
+        })
+    }
+    async fn send<T: for<'de> Deserialize<'de>>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let response = request
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context(format!("Failed reaching {}.", self.url))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("{} answered {}: {}", self.url, status, body));
+        }
+        response
+            .json()
+            .await
+            .context(format!("Invalid response from {}.", self.url))
+    }
+    /// Add a note to the day `date`, the server skips the duplicate check.
+    pub async fn insert_note_on(&self, date: NaiveDate, body: &str) -> Result<Note> {
+        let request = self
+            .client
+            .post(format!("{}/notes", self.url))
+            .json(&json!({ "body": body, "date": date }));
+        self.send::<RemoteNote>(request).await?.try_into()
+    }
+    /// Get day notes in inclusive range.
+    pub async fn get_day_notes_in_range(
+        &self,
+        start_day: NaiveDate,
+        end_day: NaiveDate,
+    ) -> Result<Vec<DayNotes>> {
+        let request = self
+            .client
+            .get(format!("{}/days", self.url))
+            .query(&DaysParams {
+                from: start_day,
+                to: end_day,
+            });
+        self.send::<Vec<RemoteDay>>(request)
+            .await?
+            .into_iter()
+            .map(day_notes)
+            .collect()
+    }
+    pub async fn set_status(&self, id: u32, status: Status) -> Result<Note> {
+        let request = self
+            .client
+            .patch(format!("{}/notes/{}/status", self.url, id))
+            .json(&json!({ "status": status.as_str() }));
+        self.send::<RemoteNote>(request).await?.try_into()
+    }
+}
+
+fn day_notes(day: RemoteDay) -> Result<DayNotes> {
+    let notes = day
+        .notes
+        .into_iter()
+        .map(Note::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(DayNotes {
+        note_count: notes.len() as u32,
+        notes,
+        date: day.date,
+        day_text: day.day_text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RemoteDay, day_notes};
+    use crate::notes::Status;
+
+    #[test]
+    fn test_day_notes() {
+        let day: RemoteDay = serde_json::from_str(
+            r#"{"date": "2025-10-13", "day_text": "Quiet.", "notes": [
+                {"id": 4, "body": "ship", "status": "doing", "section": null, "due": "2025-10-14"}
+            ]}"#,
+        )
+        .unwrap();
+        let day = day_notes(day).unwrap();
+        assert_eq!(day.note_count, 1);
+        assert_eq!(day.notes[0].status, Status::Doing);
+        assert_eq!(day.notes[0].due.unwrap().to_string(), "2025-10-14");
+        let bad: RemoteDay = serde_json::from_str(
+            r#"{"date": "2025-10-13", "day_text": "", "notes": [
+                {"id": 4, "body": "ship", "status": "maybe", "section": null, "due": null}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(day_notes(bad).is_err());
+    }
+}
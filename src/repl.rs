@@ -0,0 +1,100 @@
+use std::{ffi::OsString, path::Path};
+
+use anyhow::{Result, anyhow};
+use rustyline::{
+    Context, Editor, Helper, completion::Completer, highlight::Highlighter, hint::Hinter,
+    history::FileHistory, validate::Validator,
+};
+
+/// File in the config directory holding the repl's history.
+pub const HISTORY_NAME: &str = "repl_history";
+
+/// A line read by the repl.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    Empty,
+    Exit,
+    /// Arguments of a command, without the program name.
+    Command(Vec<String>),
+}
+
+/// Split a line into arguments with shell quoting, `exit` and `quit` end the repl.
+pub fn parse_line(line: &str) -> Result<Input> {
+    let line = line.trim();
+    match line {
+        "" => Ok(Input::Empty),
+        "exit" | "quit" => Ok(Input::Exit),
+        line => shlex::split(line)
+            .map(Input::Command)
+            .ok_or(anyhow!("Unbalanced quotes in {}.", line)),
+    }
+}
+
+/// Completes commands, flags and values like note ids with the same completers as the shell.
+pub struct ReplHelper {
+    pub command: fn() -> clap::Command,
+}
+impl Completer for ReplHelper {
+    type Candidate = String;
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let mut args = vec![OsString::from("fh")];
+        args.extend(line[..start].split_whitespace().map(OsString::from));
+        args.push(OsString::from(&line[start..]));
+        let index = args.len() - 1;
+        let candidates = clap_complete::engine::complete(&mut (self.command)(), args, index, None)?;
+        Ok((
+            start,
+            candidates
+                .into_iter()
+                .map(|c| c.get_value().to_string_lossy().into_owned())
+                .collect(),
+        ))
+    }
+}
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+impl Highlighter for ReplHelper {}
+impl Validator for ReplHelper {}
+impl Helper for ReplHelper {}
+
+/// Line editor with completion, loading history from `history` if it exists.
+pub fn editor(
+    command: fn() -> clap::Command,
+    history: &Path,
+) -> Result<Editor<ReplHelper, FileHistory>> {
+    let mut editor = Editor::new()?;
+    editor.set_helper(Some(ReplHelper { command }));
+    if history.exists() {
+        editor.load_history(history)?;
+    }
+    Ok(editor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Input, parse_line};
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("  ").unwrap(), Input::Empty);
+        assert_eq!(parse_line("quit").unwrap(), Input::Exit);
+        assert_eq!(
+            parse_line("new 'buy milk' -d 1").unwrap(),
+            Input::Command(vec![
+                String::from("new"),
+                String::from("buy milk"),
+                String::from("-d"),
+                String::from("1")
+            ])
+        );
+        assert!(parse_line("new 'buy").is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{Context, Result, anyhow};
+
+/// Replica url for a bucket given as `bucket/path` or a full litestream url like `s3://bucket/path`.
+/// S3 compatible storage is reached with `?endpoint=` on the url.
+pub fn replica_url(bucket: &str) -> String {
+    if bucket.contains("://") {
+        String::from(bucket)
+    } else {
+        format!("s3://{}", bucket.trim_start_matches('/'))
+    }
+}
+
+/// Stream the database's WAL to the replica with the `litestream` binary until interrupted.
+/// Credentials are read by litestream from `LITESTREAM_ACCESS_KEY_ID` and `LITESTREAM_SECRET_ACCESS_KEY`.
+pub fn replicate(db: &Path, url: &str) -> Result<()> {
+    run_litestream(&["replicate", &db.to_string_lossy(), url])
+}
+
+/// Restore the latest replica into `db`, which must not exist yet.
+pub fn restore(db: &Path, url: &str) -> Result<()> {
+    if db.exists() {
+        return Err(anyhow!(
+            "{} already exists, move it away before restoring.",
+            db.display()
+        ));
+    }
+    run_litestream(&["restore", "-o", &db.to_string_lossy(), url])
+}
+
+fn run_litestream(args: &[&str]) -> Result<()> {
+    let status = Command::new("litestream")
+        .args(args)
+        .status()
+        .context("Failed running litestream, is it installed?")?;
+    if !status.success() {
+        return Err(anyhow!("litestream {} failed: {}", args[0], status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::replica_url;
+
+    #[test]
+    fn test_replica_url() {
+        assert_eq!(replica_url("notes/fh"), "s3://notes/fh");
+        assert_eq!(
+            replica_url("s3://notes/fh?endpoint=minio.lan:9000"),
+            "s3://notes/fh?endpoint=minio.lan:9000"
+        );
+    }
+}
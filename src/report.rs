@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use ansi_term::Style;
+use chrono::NaiveDate;
+
+use crate::{
+    color,
+    notes::{DayNotes, Note, Status},
+};
+
+/// Longest day text excerpt included in a report, in characters.
+const EXCERPT_LEN: usize = 200;
+
+/// Summary of a period of notes, rendered as terminal text, markdown or html.
+pub struct Report {
+    pub title: String,
+    pub note_count: usize,
+    /// Completed notes grouped by project, or tag for notes without a project.
+    pub completed: Vec<(String, Vec<(NaiveDate, Note)>)>,
+    /// Notes still open from days before the end of the report.
+    pub carried_over: Vec<(NaiveDate, Note)>,
+    pub open: Vec<(NaiveDate, Note)>,
+    pub excerpts: Vec<(NaiveDate, String)>,
+}
+impl Report {
+    /// Build a report, `groups` maps note ids to the project or tag they are reported under.
+    pub fn new(
+        title: String,
+        end_day: NaiveDate,
+        notes: Vec<(NaiveDate, Note)>,
+        groups: &HashMap<u32, String>,
+        days: &[DayNotes],
+    ) -> Report {
+        let note_count = notes.len();
+        let mut completed: Vec<(String, Vec<(NaiveDate, Note)>)> = vec![];
+        let mut carried_over = vec![];
+        let mut open = vec![];
+        for (date, note) in notes {
+            if note.status == Status::Done {
+                let group = groups
+                    .get(&note.id)
+                    .cloned()
+                    .unwrap_or(String::from("Other"));
+                match completed.iter_mut().find(|(g, _)| *g == group) {
+                    Some((_, notes)) => notes.push((date, note)),
+                    None => completed.push((group, vec![(date, note)])),
+                }
+            } else if note.status.is_open() && date < end_day {
+                carried_over.push((date, note));
+            } else if note.status.is_open() {
+                open.push((date, note));
+            }
+        }
+        completed.sort_by(|a, b| a.0.cmp(&b.0));
+        let excerpts = days
+            .iter()
+            .filter(|d| !d.day_text.trim().is_empty())
+            .map(|d| (d.date, excerpt(&d.day_text)))
+            .collect();
+        Report {
+            title,
+            note_count,
+            completed,
+            carried_over,
+            open,
+            excerpts,
+        }
+    }
+    fn completed_count(&self) -> usize {
+        self.completed.iter().map(|(_, n)| n.len()).sum()
+    }
+    fn summary(&self) -> String {
+        format!(
+            "{} notes, {} completed, {} carried over, {} open.",
+            self.note_count,
+            self.completed_count(),
+            self.carried_over.len(),
+            self.open.len()
+        )
+    }
+    fn sections(&self) -> [(&'static str, &[(NaiveDate, Note)]); 2] {
+        [("Carried over", &self.carried_over), ("Open", &self.open)]
+    }
+    pub fn pretty(&self) -> String {
+        let bold = color::style(Style::new().bold());
+        let mut out = format!("{}\n{}\n\n", bold.paint(&self.title), self.summary());
+        if !self.completed.is_empty() {
+            out.push_str(&format!("{}\n", bold.paint("Completed:")));
+            for (group, notes) in &self.completed {
+                out.push_str(&format!("  {}:\n", group));
+                for (date, note) in notes {
+                    out.push_str(&format!("{} {}\n", date, note.pretty_term()));
+                }
+            }
+            out.push('\n');
+        }
+        for (title, notes) in self.sections() {
+            if notes.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("{}\n", bold.paint(format!("{}:", title))));
+            for (date, note) in notes {
+                out.push_str(&format!("{} {}\n", date, note.pretty_term()));
+            }
+            out.push('\n');
+        }
+        for (date, text) in &self.excerpts {
+            out.push_str(&format!(
+                "{} {}\n",
+                color::style(Style::new().dimmed()).paint(date.to_string()),
+                text
+            ));
+        }
+        out
+    }
+    pub fn markdown(&self) -> String {
+        let mut out = format!("# {}\n\n{}\n", self.title, self.summary());
+        if !self.completed.is_empty() {
+            out.push_str("\n## Completed\n");
+            for (group, notes) in &self.completed {
+                out.push_str(&format!("\n### {}\n\n", group));
+                for (date, note) in notes {
+                    out.push_str(&format!("- {} ({})\n", note.display_body(), date));
+                }
+            }
+        }
+        for (title, notes) in self.sections() {
+            if notes.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n## {}\n\n", title));
+            for (date, note) in notes {
+                out.push_str(&format!("- [ ] {} ({})\n", note.display_body(), date));
+            }
+        }
+        if !self.excerpts.is_empty() {
+            out.push_str("\n## Notes\n\n");
+            for (date, text) in &self.excerpts {
+                out.push_str(&format!("- **{}** {}\n", date, text));
+            }
+        }
+        out
+    }
+    pub fn html(&self) -> String {
+        let mut out = format!(
+            "<article>\n<h1>{}</h1>\n<p>{}</p>\n",
+            escape_html(&self.title),
+            self.summary()
+        );
+        if !self.completed.is_empty() {
+            out.push_str("<h2>Completed</h2>\n");
+            for (group, notes) in &self.completed {
+                out.push_str(&format!("<h3>{}</h3>\n<ul>\n", escape_html(group)));
+                for (date, note) in notes {
+                    out.push_str(&format!(
+                        "<li>{} <time>{}</time></li>\n",
+                        escape_html(note.display_body()),
+                        date
+                    ));
+                }
+                out.push_str("</ul>\n");
+            }
+        }
+        for (title, notes) in self.sections() {
+            if notes.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("<h2>{}</h2>\n<ul>\n", title));
+            for (date, note) in notes {
+                out.push_str(&format!(
+                    "<li>{} <time>{}</time></li>\n",
+                    escape_html(note.display_body()),
+                    date
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+        if !self.excerpts.is_empty() {
+            out.push_str("<h2>Notes</h2>\n<dl>\n");
+            for (date, text) in &self.excerpts {
+                out.push_str(&format!(
+                    "<dt><time>{}</time></dt><dd>{}</dd>\n",
+                    date,
+                    escape_html(text)
+                ));
+            }
+            out.push_str("</dl>\n");
+        }
+        out.push_str("</article>\n");
+        out
+    }
+}
+
+/// First line of a day's text, cut to [`EXCERPT_LEN`] characters.
+fn excerpt(text: &str) -> String {
+    let line = text.trim().lines().next().unwrap_or_default();
+    match line.char_indices().nth(EXCERPT_LEN) {
+        Some((idx, _)) => format!("{}…", &line[..idx]),
+        None => String::from(line),
+    }
+}
+
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use super::{Report, escape_html};
+    use crate::notes::{DayNotes, Note, Status};
+
+    fn note(id: u32, body: &str, status: Status) -> Note {
+        Note {
+            id,
+            body: String::from(body),
+            status,
+            section: None,
+            due: None,
+            focus_rank: None,
+            ..Default::default()
+        }
+    }
+    #[test]
+    fn test_report() {
+        let monday = NaiveDate::from_ymd_opt(2025, 10, 13).unwrap();
+        let friday = NaiveDate::from_ymd_opt(2025, 10, 17).unwrap();
+        let notes = vec![
+            (monday, note(1, "ship <v2>", Status::Done)),
+            (monday, note(2, "write docs", Status::Todo)),
+            (friday, note(3, "plan", Status::Doing)),
+            (friday, note(4, "tidy", Status::Done)),
+        ];
+        let groups = HashMap::from([(1, String::from("infra"))]);
+        let days = vec![DayNotes {
+            notes: vec![],
+            note_count: 0,
+            date: monday,
+            day_text: String::from("Good day.\nMore detail."),
+        }];
+        let report = Report::new(String::from("Week"), friday, notes, &groups, &days);
+        assert_eq!(report.completed.len(), 2);
+        assert_eq!(report.completed[0].0, "Other");
+        assert_eq!(report.completed[1].0, "infra");
+        assert_eq!(report.carried_over.len(), 1);
+        assert_eq!(report.open.len(), 1);
+        assert_eq!(report.excerpts, vec![(monday, String::from("Good day."))]);
+        let md = report.markdown();
+        assert!(
+            md.contains("### infra\n\n- ship <v2> (2025-10-13)"),
+            "{}",
+            md
+        );
+        assert!(md.contains("## Carried over\n\n- [ ] write docs"), "{}", md);
+        let html = report.html();
+        assert!(html.contains("ship &lt;v2&gt;"), "{}", html);
+    }
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
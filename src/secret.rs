@@ -0,0 +1,66 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result, anyhow};
+
+/// Shown in place of an encrypted note body, the edit buffer keeps the stored body for it.
+pub const ENCRYPTED_BODY: &str = "[encrypted]";
+const AGE_HEADER: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// True for bodies stored as armored age ciphertext.
+pub fn is_encrypted(body: &str) -> bool {
+    body.starts_with(AGE_HEADER)
+}
+
+/// Encrypt `plain` to an age recipient with the `age` binary, returning armored ciphertext.
+pub fn encrypt(plain: &str, recipient: &str) -> Result<String> {
+    run_age(&["--encrypt", "--armor", "--recipient", recipient], plain)
+}
+
+/// Decrypt armored ciphertext with the age identity file at `identity`.
+pub fn decrypt(cipher: &str, identity: &Path) -> Result<String> {
+    let identity = identity.to_string_lossy();
+    run_age(&["--decrypt", "--identity", &identity], cipher)
+}
+
+fn run_age(args: &[&str], input: &str) -> Result<String> {
+    let mut child = Command::new("age")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed running age, is it installed?")?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())
+        .context("Failed writing to age.")?;
+    let output = child
+        .wait_with_output()
+        .context("Failed waiting for age.")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "age failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).context("age output wasn't utf8.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_encrypted;
+
+    #[test]
+    fn test_is_encrypted() {
+        assert!(is_encrypted(
+            "-----BEGIN AGE ENCRYPTED FILE-----\nYWdl\n-----END AGE ENCRYPTED FILE-----\n"
+        ));
+        assert!(!is_encrypted("buy milk"));
+    }
+}
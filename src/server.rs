@@ -0,0 +1,608 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use anyhow::{Context, Result, anyhow};
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path as UrlPath, Query as Params, Request, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::{get, patch, post},
+};
+use chrono::{Days, Local, NaiveDate, Utc};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio_rustls::{TlsAcceptor, rustls};
+
+use crate::{
+    error::FhError,
+    export,
+    notes::{DayNotes, NewNote, Note, Status},
+    query::{self, Query},
+    slack::{self, Action, SlashCommand},
+    store::NoteStore,
+};
+
+/// What a bearer token may do, a write token can also read.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Write,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiToken {
+    pub token: String,
+    pub scope: Scope,
+}
+
+/// The `[server]` table of the config.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub address: String,
+    pub tokens: Vec<ApiToken>,
+    /// PEM certificate chain, TLS is used when both this and `tls_key` are set.
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// Base url `fh share` links are made under, defaults to the served address.
+    pub public_url: Option<String>,
+    /// How many days `/feed.atom` covers.
+    pub feed_days: u32,
+    /// Signing secret of the Slack app whose `/fh` slash command posts to `/slack/command`.
+    pub slack_signing_secret: Option<String>,
+}
+impl ServerConfig {
+    /// `public_url` without a trailing slash, or the served address.
+    pub fn base_url(&self) -> String {
+        match &self.public_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None if self.tls_cert.is_some() => format!("https://{}", self.address),
+            None => format!("http://{}", self.address),
+        }
+    }
+}
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            address: String::from("127.0.0.1:8420"),
+            tokens: vec![],
+            tls_cert: None,
+            tls_key: None,
+            public_url: None,
+            feed_days: 30,
+            slack_signing_secret: None,
+        }
+    }
+}
+
+struct AppState {
+    store: NoteStore,
+    tokens: Vec<ApiToken>,
+    include_private: bool,
+    feed_days: u32,
+    /// Url the server is reached at, without a trailing slash.
+    base_url: String,
+    slack_signing_secret: Option<String>,
+}
+
+/// 32 random bytes as hex.
+pub fn random_token() -> String {
+    let bytes: [u8; 32] = rand::rng().random();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A random token with the config snippet that enables it.
+pub fn gen_token(scope: Scope) -> String {
+    let token = random_token();
+    let scope = match scope {
+        Scope::Read => "read",
+        Scope::Write => "write",
+    };
+    format!(
+        "[[server.tokens]]\ntoken = \"{}\"\nscope = \"{}\"",
+        token, scope
+    )
+}
+
+/// Serve the notes over HTTP until interrupted, with TLS when a certificate is configured.
+pub async fn serve(store: NoteStore, config: &ServerConfig, include_private: bool) -> Result<()> {
+    if config.tokens.is_empty() {
+        return Err(anyhow!(
+            "No server tokens configured, add one made by `fh serve --gen-token`."
+        ));
+    }
+    let state = Arc::new(AppState {
+        store,
+        tokens: config.tokens.clone(),
+        include_private,
+        feed_days: config.feed_days,
+        base_url: config.base_url(),
+        slack_signing_secret: config.slack_signing_secret.clone(),
+    });
+    let app = router(state);
+    let listener = TcpListener::bind(&config.address)
+        .await
+        .context(format!("Failed binding {}.", config.address))?;
+    match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => {
+            println!("Serving on https://{}", config.address);
+            serve_tls(listener, app, tls_acceptor(cert, key)?).await
+        }
+        (None, None) => {
+            println!("Serving on http://{}", config.address);
+            axum::serve(listener, app)
+                .await
+                .context("Server stopped with an error.")
+        }
+        _ => Err(anyhow!("Set both tls_cert and tls_key to serve TLS.")),
+    }
+}
+
+/// Url a share made by `fh share` is served at.
+pub fn share_url(config: &ServerConfig, token: &str) -> String {
+    format!("{}/share/{}", config.base_url(), token)
+}
+
+fn router(state: Arc<AppState>) -> Router {
+    let read = Router::new()
+        .route("/days", get(get_days))
+        .route("/notes", get(get_notes))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_read));
+    let write = Router::new()
+        .route("/notes", post(post_note))
+        .route("/notes/{id}/status", patch(patch_status))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_write));
+    let feed = Router::new()
+        .route("/feed.atom", get(get_feed))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_feed_read,
+        ));
+    // Shares are reached by their unguessable token, no bearer token needed.
+    // Slack signs its requests instead of sending one.
+    let public = Router::new()
+        .route("/share/{token}", get(get_share))
+        .route("/slack/command", post(post_slack_command));
+    read.merge(write)
+        .merge(feed)
+        .merge(public)
+        .with_state(state)
+}
+
+async fn serve_tls(listener: TcpListener, app: Router, acceptor: TlsAcceptor) -> Result<()> {
+    loop {
+        let (stream, peer) = listener
+            .accept()
+            .await
+            .context("Failed accepting connection.")?;
+        let acceptor = acceptor.clone();
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::warn!("TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+            };
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::warn!("Connection with {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+fn tls_acceptor(cert: &Path, key: &Path) -> Result<TlsAcceptor> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .context(format!("Failed opening {}.", path.display()))
+    };
+    let certs = rustls_pemfile::certs(&mut open(cert)?)
+        .collect::<Result<Vec<_>, _>>()
+        .context(format!("Failed reading certificates {}.", cert.display()))?;
+    let key = rustls_pemfile::private_key(&mut open(key)?)
+        .context(format!("Failed reading key {}.", key.display()))?
+        .ok_or(anyhow!("No private key in {}.", key.display()))?;
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Invalid TLS certificate or key.")?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Scope of the bearer token in `headers`, compared in constant time.
+fn token_scope(tokens: &[ApiToken], headers: &HeaderMap) -> Option<Scope> {
+    let given = headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?
+        .trim();
+    given_scope(tokens, given)
+}
+
+fn given_scope(tokens: &[ApiToken], given: &str) -> Option<Scope> {
+    tokens
+        .iter()
+        .filter(|t| constant_time_eq(t.token.as_bytes(), given.as_bytes()))
+        .map(|t| t.scope)
+        .max()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn authorize(state: &AppState, headers: &HeaderMap, needed: Scope) -> Result<(), StatusCode> {
+    match token_scope(&state.tokens, headers) {
+        None => Err(StatusCode::UNAUTHORIZED),
+        Some(scope) if scope < needed => Err(StatusCode::FORBIDDEN),
+        Some(_) => Ok(()),
+    }
+}
+
+async fn require_read(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    authorize(&state, req.headers(), Scope::Read)?;
+    Ok(next.run(req).await)
+}
+
+#[derive(Deserialize)]
+struct FeedParams {
+    token: Option<String>,
+}
+
+/// Like [`require_read`], also taking the token from a `?token=` parameter as feed readers
+/// rarely send custom headers.
+async fn require_feed_read(
+    State(state): State<Arc<AppState>>,
+    Params(params): Params<FeedParams>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let from_param = params
+        .token
+        .and_then(|t| given_scope(&state.tokens, &t))
+        .is_some();
+    if !from_param {
+        authorize(&state, req.headers(), Scope::Read)?;
+    }
+    Ok(next.run(req).await)
+}
+
+async fn require_write(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    authorize(&state, req.headers(), Scope::Write)?;
+    Ok(next.run(req).await)
+}
+
+/// Errors from the store, shown to the client as not found for a missing note or day, a server
+/// error for a failing database and a bad request otherwise.
+struct ApiError(anyhow::Error);
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(value: E) -> Self {
+        ApiError(value.into())
+    }
+}
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        match self.0.chain().find_map(|c| c.downcast_ref::<FhError>()) {
+            Some(FhError::DayNotFound(_) | FhError::NoteNotFound { .. }) => StatusCode::NOT_FOUND,
+            Some(FhError::Storage { .. } | FhError::Migrate { .. }) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(json!({ "error": format!("{:#}", self.0) }));
+        (self.status(), body).into_response()
+    }
+}
+
+fn note_json(note: &Note) -> Value {
+    json!(Note {
+        body: String::from(note.display_body()),
+        ..note.clone()
+    })
+}
+
+impl AppState {
+    async fn hidden_ids(&self) -> Result<HashSet<u32>> {
+        if self.include_private {
+            return Ok(HashSet::new());
+        }
+        Ok(self.store.private_note_ids().await?)
+    }
+}
+
+#[derive(Deserialize)]
+struct DaysParams {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+/// Days between `from` and `to`, both default to today.
+async fn get_days(
+    State(state): State<Arc<AppState>>,
+    Params(params): Params<DaysParams>,
+) -> Result<Json<Value>, ApiError> {
+    let today = Local::now().date_naive();
+    let parse = |d: Option<String>| match d {
+        Some(d) => query::parse_date(&d, today),
+        None => Ok(today),
+    };
+    let (from, to) = (parse(params.from)?, parse(params.to)?);
+    let hidden = state.hidden_ids().await?;
+    let days = state.store.get_day_notes_in_range(from, to).await?;
+    let days = days
+        .iter()
+        .map(|d| day_json(d, &hidden))
+        .collect::<Vec<_>>();
+    Ok(Json(Value::Array(days)))
+}
+
+fn day_json(day: &DayNotes, hidden: &HashSet<u32>) -> Value {
+    json!({
+        "date": day.date,
+        "day_text": day.day_text,
+        "notes": day
+            .notes
+            .iter()
+            .filter(|n| !hidden.contains(&n.id))
+            .map(note_json)
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// A shared snapshot, not found once expired.
+async fn get_share(
+    State(state): State<Arc<AppState>>,
+    UrlPath(token): UrlPath<String>,
+) -> Result<Response, ApiError> {
+    Ok(match state.store.get_share(&token, Utc::now()).await? {
+        Some(html) => Html(html).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    })
+}
+
+/// Atom feed of the last `feed_days` days, one entry per day with its text and completed notes.
+async fn get_feed(State(state): State<Arc<AppState>>) -> Result<Response, ApiError> {
+    let today = Local::now().date_naive();
+    let start_day = today
+        .checked_sub_days(Days::new(state.feed_days.saturating_sub(1) as u64))
+        .expect("Don't account for leap");
+    let hidden = state.hidden_ids().await?;
+    let mut days = state.store.get_day_notes_in_range(start_day, today).await?;
+    for day in days.iter_mut() {
+        day.retain_notes(|n| n.status == Status::Done && !hidden.contains(&n.id));
+    }
+    days.reverse();
+    let feed = export::days_atom(&days, &state.base_url);
+    Ok(([(header::CONTENT_TYPE, "application/atom+xml")], feed).into_response())
+}
+
+#[derive(Deserialize)]
+struct NotesParams {
+    q: String,
+}
+
+/// Notes matching a query in the `fh query` syntax.
+async fn get_notes(
+    State(state): State<Arc<AppState>>,
+    Params(params): Params<NotesParams>,
+) -> Result<Json<Value>, ApiError> {
+    let query = Query::parse(&params.q, Local::now().date_naive())?;
+    let hidden = state.hidden_ids().await?;
+    let notes = state
+        .store
+        .query_notes(&query)
+        .await?
+        .iter()
+        .filter(|(_, n)| !hidden.contains(&n.id))
+        .map(|(date, note)| {
+            let mut out = note_json(note);
+            out["date"] = json!(date);
+            out
+        })
+        .collect::<Vec<_>>();
+    Ok(Json(Value::Array(notes)))
+}
+
+#[derive(Deserialize)]
+struct NewNoteBody {
+    body: String,
+    date: Option<NaiveDate>,
+}
+
+async fn post_note(
+    State(state): State<Arc<AppState>>,
+    Json(new): Json<NewNoteBody>,
+) -> Result<(StatusCode, Json<Value>), ApiError> {
+    let date = new.date.unwrap_or(Local::now().date_naive());
+    let note = state
+        .store
+        .insert_note_on(date, NewNote::new(new.body))
+        .await?;
+    state.store.index_note_body(&note).await?;
+    Ok((StatusCode::CREATED, Json(note_json(&note))))
+}
+
+#[derive(Deserialize)]
+struct StatusBody {
+    status: String,
+}
+
+async fn patch_status(
+    State(state): State<Arc<AppState>>,
+    UrlPath(id): UrlPath<u32>,
+    Json(body): Json<StatusBody>,
+) -> Result<Json<Value>, ApiError> {
+    let status = Status::from_str(&body.status)?;
+    let note = state.store.set_status(id, status).await?;
+    Ok(Json(note_json(&note)))
+}
+
+/// Slack's `/fh` slash command, `/fh show` replies with today's notes and any other text is
+/// added as a note for today. Requests not signed with `slack_signing_secret` are refused.
+async fn post_slack_command(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    let Some(secret) = &state.slack_signing_secret else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+    };
+    let signed = slack::verify(
+        secret,
+        header("X-Slack-Request-Timestamp"),
+        &body,
+        header("X-Slack-Signature"),
+        Utc::now().timestamp(),
+    );
+    if !signed {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+    let command: SlashCommand = serde_urlencoded::from_bytes(&body)?;
+    let today = Local::now().date_naive();
+    let text = match command.action() {
+        None => String::from("Usage: /fh show, or /fh <note> to add a note for today."),
+        Some(Action::Show) => {
+            let hidden = state.hidden_ids().await?;
+            let mut day = state.store.get_days_notes(today).await?;
+            day.retain_notes(|n| !hidden.contains(&n.id));
+            let mut lines = vec![format!("*{}*", day.date_label())];
+            lines.extend(day.notes.iter().map(Note::pretty));
+            if day.notes.is_empty() {
+                lines.push(String::from("No Notes."));
+            }
+            lines.join("\n")
+        }
+        Some(Action::Add(body)) => {
+            let note = state
+                .store
+                .insert_note_on(today, NewNote::new(body))
+                .await?;
+            state.store.index_note_body(&note).await?;
+            format!("Added {}", note.display_body())
+        }
+    };
+    Ok(Json(slack::ephemeral(&text)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+
+    use super::{ApiError, ApiToken, Scope, ServerConfig, gen_token, share_url, token_scope};
+    use crate::error::FhError;
+
+    #[test]
+    fn test_token_scope() {
+        let tokens = vec![
+            ApiToken {
+                token: String::from("reader"),
+                scope: Scope::Read,
+            },
+            ApiToken {
+                token: String::from("writer"),
+                scope: Scope::Write,
+            },
+        ];
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        assert_eq!(
+            token_scope(&tokens, &headers("Bearer reader")),
+            Some(Scope::Read)
+        );
+        assert_eq!(
+            token_scope(&tokens, &headers("Bearer writer")),
+            Some(Scope::Write)
+        );
+        assert_eq!(token_scope(&tokens, &headers("Bearer write")), None);
+        assert_eq!(token_scope(&tokens, &headers("writer")), None);
+        assert_eq!(token_scope(&tokens, &HeaderMap::new()), None);
+        assert!(Scope::Write > Scope::Read);
+    }
+    #[test]
+    fn test_api_error_status() {
+        let table = vec![
+            (
+                ApiError::from(FhError::NoteNotFound { id: 1 }),
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                ApiError::from(FhError::Storage {
+                    context: String::from("Failed fetching note 1."),
+                    source: sqlx::Error::PoolClosed,
+                }),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+            (
+                ApiError::from(FhError::parse("- [?]", "Invalid status.")),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                ApiError::from(anyhow!("Empty body.")),
+                StatusCode::BAD_REQUEST,
+            ),
+        ];
+        for (error, expected) in table {
+            assert_eq!(error.status(), expected, "{:#}", error.0);
+        }
+    }
+    #[test]
+    fn test_gen_token() {
+        let snippet = gen_token(Scope::Write);
+        let config: toml::Value = toml::from_str(&snippet).unwrap();
+        let token = &config["server"]["tokens"][0];
+        assert_eq!(token["scope"].as_str(), Some("write"));
+        assert_eq!(token["token"].as_str().unwrap().len(), 64);
+    }
+    #[test]
+    fn test_share_url() {
+        let mut config = ServerConfig::default();
+        assert_eq!(share_url(&config, "ab"), "http://127.0.0.1:8420/share/ab");
+        config.public_url = Some(String::from("https://fh.example.com/"));
+        assert_eq!(share_url(&config, "ab"), "https://fh.example.com/share/ab");
+    }
+}
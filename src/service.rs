@@ -0,0 +1,263 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::config::Config;
+
+/// A background job `install-service` sets up.
+#[derive(Debug, PartialEq)]
+pub struct Job {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub args: Vec<&'static str>,
+    /// Seconds between runs for jobs started on a timer, `None` keeps the job running.
+    pub every: Option<u32>,
+}
+
+/// Jobs for the current config, the server and replication only when configured.
+pub fn jobs(config: &Config) -> Vec<Job> {
+    let mut jobs = vec![
+        Job {
+            name: "daemon",
+            description: "fh daemon answering new and show",
+            args: vec!["daemon"],
+            every: None,
+        },
+        Job {
+            name: "notify",
+            description: "fh notifications for due notes",
+            args: vec!["notify"],
+            every: Some(3600),
+        },
+    ];
+    if !config.server.tokens.is_empty() {
+        jobs.push(Job {
+            name: "serve",
+            description: "fh HTTP server",
+            args: vec!["serve"],
+            every: None,
+        });
+    }
+    if config.replica.is_some() {
+        jobs.push(Job {
+            name: "replicate",
+            description: "fh database replication",
+            args: vec!["replicate"],
+            every: None,
+        });
+    }
+    jobs
+}
+
+/// A file to write, with the unit or label to enable once written.
+#[derive(Debug, PartialEq)]
+pub struct ServiceFile {
+    pub path: PathBuf,
+    pub contents: String,
+    pub enable: String,
+}
+
+/// systemd units for `jobs` in `dir`, timers for the jobs run on a schedule.
+/// System units run as `user` with `home` so they find the config.
+pub fn systemd_units(
+    jobs: &[Job],
+    exe: &Path,
+    dir: &Path,
+    system: Option<(&str, &str)>,
+) -> Vec<ServiceFile> {
+    let mut files = vec![];
+    for job in jobs {
+        let unit = format!("fh-{}", job.name);
+        let mut service = format!(
+            "[Unit]\nDescription={}\n\n[Service]\nExecStart={} {}\n",
+            job.description,
+            exe.display(),
+            job.args.join(" ")
+        );
+        if let Some((user, home)) = system {
+            service.push_str(&format!("User={}\nEnvironment=HOME={}\n", user, home));
+        }
+        let wanted_by = if system.is_some() {
+            "multi-user.target"
+        } else {
+            "default.target"
+        };
+        match job.every {
+            None => {
+                service.push_str(&format!(
+                    "Restart=on-failure\n\n[Install]\nWantedBy={}\n",
+                    wanted_by
+                ));
+                files.push(ServiceFile {
+                    path: dir.join(format!("{}.service", unit)),
+                    contents: service,
+                    enable: format!("{}.service", unit),
+                });
+            }
+            Some(seconds) => {
+                service.push_str("Type=oneshot\n");
+                files.push(ServiceFile {
+                    path: dir.join(format!("{}.service", unit)),
+                    contents: service,
+                    enable: format!("{}.timer", unit),
+                });
+                files.push(ServiceFile {
+                    path: dir.join(format!("{}.timer", unit)),
+                    contents: format!(
+                        "[Unit]\nDescription=Run {}\n\n[Timer]\nOnBootSec=1min\nOnUnitActiveSec={}s\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n",
+                        job.description, seconds
+                    ),
+                    enable: String::new(),
+                });
+            }
+        }
+    }
+    files
+}
+
+/// launchd property lists for `jobs` in `dir`.
+pub fn launchd_plists(jobs: &[Job], exe: &Path, dir: &Path) -> Vec<ServiceFile> {
+    jobs.iter()
+        .map(|job| {
+            let label = format!("fh.{}", job.name);
+            let args = std::iter::once(exe.display().to_string())
+                .chain(job.args.iter().map(|a| a.to_string()))
+                .map(|a| format!("        <string>{}</string>\n", a))
+                .collect::<String>();
+            let schedule = match job.every {
+                None => String::from("    <key>KeepAlive</key>\n    <true/>\n"),
+                Some(seconds) => format!(
+                    "    <key>StartInterval</key>\n    <integer>{}</integer>\n",
+                    seconds
+                ),
+            };
+            let path = dir.join(format!("{}.plist", label));
+            ServiceFile {
+                contents: format!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n<plist version=\"1.0\">\n<dict>\n    <key>Label</key>\n    <string>{}</string>\n    <key>ProgramArguments</key>\n    <array>\n{}    </array>\n    <key>RunAtLoad</key>\n    <true/>\n{}</dict>\n</plist>\n",
+                    label, args, schedule
+                ),
+                enable: path.display().to_string(),
+                path,
+            }
+        })
+        .collect()
+}
+
+/// Write the service files for the current platform and enable them, printing each path.
+/// User services go in the user's service directory, others are system wide and need root.
+pub fn install(config: &Config, user: bool, dry_run: bool) -> Result<()> {
+    let exe = std::env::current_exe().context("Failed finding the fh binary.")?;
+    let home = std::env::var("HOME").context("HOME isn't set.")?;
+    let jobs = jobs(config);
+    let macos = cfg!(target_os = "macos");
+    let files = match (macos, user) {
+        (true, true) => launchd_plists(&jobs, &exe, &Path::new(&home).join("Library/LaunchAgents")),
+        (true, false) => launchd_plists(&jobs, &exe, Path::new("/Library/LaunchDaemons")),
+        (false, true) => systemd_units(
+            &jobs,
+            &exe,
+            &Path::new(&home).join(".config/systemd/user"),
+            None,
+        ),
+        (false, false) => {
+            let name = std::env::var("USER").context("USER isn't set.")?;
+            systemd_units(
+                &jobs,
+                &exe,
+                Path::new("/etc/systemd/system"),
+                Some((&name, &home)),
+            )
+        }
+    };
+    for file in &files {
+        if dry_run {
+            println!("# {}\n{}", file.path.display(), file.contents);
+            continue;
+        }
+        if let Some(dir) = file.path.parent() {
+            std::fs::create_dir_all(dir).context(format!("Failed creating {}.", dir.display()))?;
+        }
+        std::fs::write(&file.path, &file.contents)
+            .context(format!("Failed writing {}.", file.path.display()))?;
+        println!("Wrote {}", file.path.display());
+    }
+    if dry_run {
+        return Ok(());
+    }
+    let enable = files
+        .iter()
+        .filter(|f| !f.enable.is_empty())
+        .map(|f| f.enable.as_str());
+    if macos {
+        for plist in enable {
+            run("launchctl", &["load", "-w", plist])?;
+        }
+    } else {
+        let scope = if user { vec!["--user"] } else { vec![] };
+        run(
+            "systemctl",
+            &[scope.as_slice(), &["daemon-reload"]].concat(),
+        )?;
+        for unit in enable {
+            run(
+                "systemctl",
+                &[scope.as_slice(), &["enable", "--now", unit]].concat(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .context(format!("Failed running {}.", program))?;
+    if !status.success() {
+        return Err(anyhow!("{} {} failed: {}", program, args.join(" "), status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{jobs, launchd_plists, systemd_units};
+    use crate::config::Config;
+
+    #[test]
+    fn test_systemd_units() {
+        let mut config = Config::default();
+        assert_eq!(jobs(&config).len(), 2);
+        config.replica = Some(String::from("s3://notes/fh"));
+        let jobs = jobs(&config);
+        let exe = Path::new("/usr/bin/fh");
+        let files = systemd_units(&jobs, exe, Path::new("/units"), None);
+        let names = files
+            .iter()
+            .map(|f| f.path.display().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "/units/fh-daemon.service",
+                "/units/fh-notify.service",
+                "/units/fh-notify.timer",
+                "/units/fh-replicate.service"
+            ]
+        );
+        assert!(files[0].contents.contains("ExecStart=/usr/bin/fh daemon"));
+        assert!(files[0].contents.contains("WantedBy=default.target"));
+        assert_eq!(files[1].enable, "fh-notify.timer");
+        let system = systemd_units(&jobs, exe, Path::new("/units"), Some(("sam", "/home/sam")));
+        assert!(system[0].contents.contains("Environment=HOME=/home/sam"));
+        let plists = launchd_plists(&jobs, exe, Path::new("/agents"));
+        assert!(plists[1].contents.contains("<integer>3600</integer>"));
+        assert_eq!(plists[0].enable, "/agents/fh.daemon.plist");
+    }
+}
@@ -0,0 +1,97 @@
+use ring::hmac;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// Oldest request timestamp accepted, in seconds, so a captured request can't be replayed later.
+const MAX_AGE_SECS: i64 = 5 * 60;
+
+/// The form Slack posts for a slash command, only the fields fh reads.
+#[derive(Deserialize, Debug)]
+pub struct SlashCommand {
+    pub text: String,
+}
+
+/// What `/fh <text>` asks for.
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    /// `/fh show`, today's notes.
+    Show,
+    /// Any other text is a note for today.
+    Add(String),
+}
+impl SlashCommand {
+    pub fn action(&self) -> Option<Action> {
+        match self.text.trim() {
+            "" => None,
+            "show" => Some(Action::Show),
+            text => Some(Action::Add(text.to_string())),
+        }
+    }
+}
+
+/// Whether `signature`, the `X-Slack-Signature` header, signs `body` sent at `timestamp`, the
+/// `X-Slack-Request-Timestamp` header, with the app's signing secret.
+pub fn verify(secret: &str, timestamp: &str, body: &[u8], signature: &str, now: i64) -> bool {
+    let Ok(sent) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    if (now - sent).abs() > MAX_AGE_SECS {
+        return false;
+    }
+    let Some(tag) = signature.strip_prefix("v0=").and_then(from_hex) else {
+        return false;
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut message = format!("v0:{}:", timestamp).into_bytes();
+    message.extend_from_slice(body);
+    hmac::verify(&key, &message, &tag).is_ok()
+}
+
+/// A reply only the user who ran the command sees.
+pub fn ephemeral(text: &str) -> Value {
+    json!({ "response_type": "ephemeral", "text": text })
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::hmac;
+
+    use super::{Action, SlashCommand, verify};
+
+    #[test]
+    fn test_verify() {
+        let secret = "8f742231b10e8888abcd99yyyzzz85a5";
+        let body = b"command=%2Ffh&text=buy+printer+ink";
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+        let mut message = b"v0:1531420618:".to_vec();
+        message.extend_from_slice(body);
+        let signature = format!(
+            "v0={}",
+            hmac::sign(&key, &message)
+                .as_ref()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        );
+        assert!(verify(secret, "1531420618", body, &signature, 1531420700));
+        assert!(!verify(secret, "1531420618", body, &signature, 1531429999));
+        assert!(!verify("other", "1531420618", body, &signature, 1531420700));
+        assert!(!verify(secret, "1531420619", body, &signature, 1531420700));
+        assert!(!verify(secret, "1531420618", body, "v0=zz", 1531420700));
+        let command: SlashCommand = serde_urlencoded::from_bytes(body).unwrap();
+        assert_eq!(
+            command.action(),
+            Some(Action::Add(String::from("buy printer ink")))
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde_json::{Value, json};
+
+use crate::{
+    notes::Note,
+    store::{DayCounts, NoteStore},
+};
+
+/// Numbers `fh status` can print, read with count queries only so it's cheap enough for a prompt.
+#[derive(Debug, Default, PartialEq)]
+pub struct StatusLine {
+    pub counts: DayCounts,
+    pub streak: u32,
+    pub overdue: i64,
+}
+impl StatusLine {
+    pub async fn load(store: &NoteStore, today: NaiveDate) -> Result<StatusLine> {
+        Ok(StatusLine {
+            counts: store.day_counts(today).await?,
+            streak: store.streak(today).await?,
+            overdue: store.overdue_count(today).await?,
+        })
+    }
+    /// Fill `{open}`, `{done}`, `{total}`, `{streak}` and `{overdue}` in `format`, other text
+    /// is kept as is.
+    pub fn render(&self, format: &str) -> String {
+        format
+            .replace("{open}", &self.counts.open.to_string())
+            .replace("{done}", &self.counts.done.to_string())
+            .replace("{total}", &self.counts.total.to_string())
+            .replace("{streak}", &self.streak.to_string())
+            .replace("{overdue}", &self.overdue.to_string())
+    }
+    /// Waybar custom module JSON, today's `notes` go in the tooltip and the class is `overdue`
+    /// while any note is overdue, otherwise `ok`.
+    pub fn waybar(&self, format: &str, notes: &[Note]) -> Value {
+        let tooltip = notes
+            .iter()
+            .map(|n| format!("[{}] {}", n.status.marker(), n.display_body()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        json!({
+            "text": self.render(format),
+            "tooltip": tooltip,
+            "class": if self.overdue > 0 { "overdue" } else { "ok" },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusLine;
+    use crate::{
+        notes::{Note, Status},
+        store::DayCounts,
+    };
+
+    fn status() -> StatusLine {
+        StatusLine {
+            counts: DayCounts {
+                open: 2,
+                done: 3,
+                total: 6,
+            },
+            streak: 4,
+            overdue: 1,
+        }
+    }
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            status().render("{open}/{total} {streak}d {done} {overdue} {other}"),
+            "2/6 4d 3 1 {other}"
+        );
+    }
+    #[test]
+    fn test_waybar() {
+        let notes = [Note {
+            id: 1,
+            body: String::from("ship"),
+            status: Status::Done,
+            section: None,
+            due: None,
+            focus_rank: None,
+            ..Default::default()
+        }];
+        let out = status().waybar("{open}", &notes);
+        assert_eq!(out["text"], "2");
+        assert_eq!(out["tooltip"], "[x] ship");
+        assert_eq!(out["class"], "overdue");
+    }
+}
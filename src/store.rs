@@ -1,15 +1,53 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
 
-use crate::notes::{DayNotes, NewNote, Note, ParsedDayNotes, ParsedNote};
-use anyhow::{Context, Result};
-use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
-use sqlx::{SqlitePool, migrate, prelude::FromRow};
-pub async fn setup_db(fname: &str) -> NoteStore {
-    let pool = SqlitePool::connect(fname).await.unwrap();
-    migrate!().run(&pool).await.unwrap();
-    return NoteStore { pool };
+use crate::error::{FhError, Result, StorageContext};
+use crate::{
+    filter::NoteFilter,
+    hooks::HooksConfig,
+    notes::{
+        DayNotes, NewNote, Note, ParsedDayNotes, ParsedNote, Status, context_token, goal_slug,
+        has_private_token, mention_tokens, meta_tokens, project_token, tag_tokens,
+    },
+    push::PushConfig,
+    query::{Query, parse_duration},
+    secret,
+};
+use chrono::{DateTime, Days, NaiveDate, TimeDelta, Utc, Weekday};
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    Column, QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool, ValueRef, migrate,
+    prelude::FromRow,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow},
+};
+/// Template name used for weekdays without a template of their own.
+pub const DEFAULT_TEMPLATE: &str = "default";
+/// Workspace of days and notes when none is picked, and of every day from before workspaces.
+pub const DEFAULT_WORKSPACE: &str = "default";
+pub async fn setup_db(fname: &str) -> Result<NoteStore> {
+    let pool = SqlitePool::connect(fname)
+        .await
+        .storage(format!("Failed connecting to {}.", fname))?;
+    migrate!()
+        .run(&pool)
+        .await
+        .storage(format!("Failed migrating {}.", fname))?;
+    Ok(NoteStore {
+        pool,
+        cache: Arc::default(),
+        contexts: Arc::new([]),
+        workspace: String::from(DEFAULT_WORKSPACE),
+        hooks: Arc::default(),
+        push: Arc::default(),
+    })
 }
 #[derive(FromRow)]
+#[allow(dead_code)]
 pub struct DateRow {
     id: u32,
     date: NaiveDate,
@@ -20,267 +58,4073 @@ pub struct DateRow {
 pub struct NoteRow {
     pub id: u32,
     pub body: String,
-    pub completed: bool,
+    pub status: Status,
     pub created_at: DateTime<Utc>,
-    updated_at: Option<DateTime<Utc>>,
-    deleted_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub section: Option<String>,
+    pub due: Option<NaiveDate>,
+    pub focus_rank: Option<u32>,
 }
 #[derive(FromRow, Clone, Default)]
 pub struct NoteRowDate {
     pub id: u32,
     pub body: String,
-    pub completed: bool,
+    pub status: Status,
     pub created_at: DateTime<Utc>,
-    updated_at: Option<DateTime<Utc>>,
-    deleted_at: Option<DateTime<Utc>>,
-    date: NaiveDate,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub section: Option<String>,
+    pub due: Option<NaiveDate>,
+    pub focus_rank: Option<u32>,
+    pub date: NaiveDate,
+}
+
+/// Lowercase a note body and collapse its whitespace for duplicate comparison.
+pub fn normalize_body(body: &str) -> String {
+    body.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// A text replacement made in a note body, or in the day text when `note_id` is `None`.
+pub struct Replacement {
+    pub date: NaiveDate,
+    pub note_id: Option<u32>,
+    pub before: String,
+    pub after: String,
+}
+
+/// A note created, updated or deleted after an export's high-water mark.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangedNote {
+    pub date: NaiveDate,
+    pub note: Note,
+    /// Latest change to the note as `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub changed_at: String,
+    pub deleted: bool,
+}
+
+/// A note as exchanged by `fh sync`, identified by its uid rather than the per-database id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncNote {
+    pub uid: String,
+    pub date: NaiveDate,
+    pub body: String,
+    pub status: Status,
+    pub section: Option<String>,
+    pub due: Option<NaiveDate>,
+    /// Latest change to the note as `YYYY-MM-DD HH:MM:SS` in UTC.
+    pub changed_at: String,
+    pub deleted: bool,
+}
+
+/// A day held open by an `fh edit` session.
+#[derive(Debug, PartialEq)]
+pub struct EditLock {
+    pub pid: u32,
+    pub locked_at: DateTime<Utc>,
+}
+
+/// Mood and energy from 1 to 5 and free tags recorded for a day.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayMetrics {
+    pub mood: Option<u8>,
+    pub energy: Option<u8>,
+    pub tags: Vec<String>,
+}
+impl DayMetrics {
+    pub fn is_empty(&self) -> bool {
+        self.mood.is_none() && self.energy.is_none() && self.tags.is_empty()
+    }
+}
+
+/// Note counts for one day, cancelled notes are in the total only.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayCounts {
+    pub open: i64,
+    pub done: i64,
+    pub total: i64,
+}
+
+/// Note totals for a range of days, carried over notes are still open before its last day.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RangeTotals {
+    pub created: i64,
+    pub completed: i64,
+    pub carried_over: i64,
+}
+
+/// A quarterly or yearly goal, notes link to it with `goal:<slug>`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    pub slug: String,
+    pub title: String,
+    /// `2025Q3` for a quarter, `2025` for a year.
+    pub period: String,
+}
+
+/// A file attached to a note, kept in the attachments directory under its content hash.
+#[derive(Debug, PartialEq)]
+pub struct Attachment {
+    pub note_id: u32,
+    pub name: String,
+    /// Hex sha256 of the file's content.
+    pub hash: String,
+}
+
+/// A goal's linked notes and the time logged on them with `time:<duration>` tokens.
+#[derive(Debug, PartialEq)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub open: i64,
+    pub done: i64,
+    pub time: TimeDelta,
+}
+
+/// Select for [`NoteRowDate`] used by the dynamically built queries.
+const NOTE_ROW_DATE_SELECT: &str = r#"SELECT n.id, n.body, n.status, n.created_at, n.updated_at, n.deleted_at, n.section, n.due, n.focus_rank, d.date
+    FROM note as n INNER JOIN day as d ON n.day_key = d.id"#;
+
+/// Returning clause for [`NoteRow`] of the dynamically built updates.
+const NOTE_ROW_RETURNING: &str =
+    " RETURNING id, body, status, created_at, updated_at, deleted_at, section, due, focus_rank";
+
+/// Render a dynamically typed sqlite value, NULL is rendered as an empty string.
+fn sql_value_to_string(row: &SqliteRow, i: usize) -> String {
+    match row.try_get_raw(i) {
+        Ok(raw) if raw.is_null() => return String::new(),
+        Err(_) => return String::new(),
+        _ => (),
+    }
+    if let Ok(v) = row.try_get::<i64, _>(i) {
+        return v.to_string();
+    }
+    if let Ok(v) = row.try_get::<f64, _>(i) {
+        return v.to_string();
+    }
+    if let Ok(v) = row.try_get::<String, _>(i) {
+        return v;
+    }
+    String::from("<blob>")
+}
+
+/// Refuse SQL for [`NoteStore::read_only_query`] holding more than one statement, or a pragma
+/// or attach that could lift the read only connection's restrictions.
+fn check_read_only_sql(sql: &str) -> Result<()> {
+    let mut statements = 0;
+    let mut words = vec![];
+    let mut current = String::new();
+    let mut in_statement = false;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                for q in chars.by_ref() {
+                    if q == close {
+                        break;
+                    }
+                }
+                in_statement = true;
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for q in chars.by_ref() {
+                    if q == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for q in chars.by_ref() {
+                    if last == '*' && q == '/' {
+                        break;
+                    }
+                    last = q;
+                }
+            }
+            ';' => {
+                if in_statement {
+                    statements += 1;
+                }
+                in_statement = false;
+            }
+            c if c.is_whitespace() => (),
+            _ => in_statement = true,
+        }
+        if c.is_alphanumeric() || c == '_' {
+            current.push(c);
+        } else if !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+    }
+    words.push(current);
+    if in_statement {
+        statements += 1;
+    }
+    if statements > 1 {
+        return Err(FhError::Invalid(String::from(
+            "Only a single SQL statement can be run.",
+        )));
+    }
+    if let Some(word) = words.iter().find(|w| {
+        ["pragma", "attach", "detach"]
+            .iter()
+            .any(|k| w.eq_ignore_ascii_case(k))
+    }) {
+        return Err(FhError::Invalid(format!(
+            "{} statements can't be run.",
+            word.to_uppercase()
+        )));
+    }
+    Ok(())
+}
+
+/// Tables of rows belonging to a note by `note_id`.
+const NOTE_TABLES: [&str; 7] = [
+    "note_tag",
+    "note_meta",
+    "mention",
+    "notified",
+    "remote_link",
+    "attachment",
+    "reminder",
+];
+
+/// Tables moved by `archive_before` for the workspace `?2`, in an order inserts satisfy the
+/// foreign keys. Every table of [`NOTE_TABLES`] follows these.
+const ARCHIVE_MOVES: [(&str, &str); 2] = [
+    ("day", "date < ?1 AND workspace = ?2"),
+    (
+        "note",
+        "day_key IN (SELECT id FROM main.day WHERE date < ?1 AND workspace = ?2)",
+    ),
+];
+/// Condition of `archive_before` for rows of [`NOTE_TABLES`].
+const ARCHIVE_NOTE_ROWS: &str = "note_id IN (SELECT n.id FROM main.note as n INNER JOIN main.day as d ON n.day_key = d.id WHERE d.date < ?1 AND d.workspace = ?2)";
+
+/// Most days [`NoteStore::get_days_notes`] keeps cached.
+const DAY_CACHE_SIZE: usize = 64;
+/// How long a cached day is used, so changes by other processes still show up.
+const DAY_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Least recently used cache of days by date, most recent first.
+#[derive(Default)]
+struct DayCache {
+    days: VecDeque<(NaiveDate, Instant, DayNotes)>,
+}
+impl DayCache {
+    fn get(&mut self, date: NaiveDate) -> Option<DayNotes> {
+        let i = self.days.iter().position(|(d, _, _)| *d == date)?;
+        let entry = self.days.remove(i)?;
+        if entry.1.elapsed() > DAY_CACHE_TTL {
+            return None;
+        }
+        let day = entry.2.clone();
+        self.days.push_front(entry);
+        Some(day)
+    }
+    fn insert(&mut self, day: DayNotes) {
+        self.days.retain(|(d, _, _)| *d != day.date);
+        self.days.push_front((day.date, Instant::now(), day));
+        self.days.truncate(DAY_CACHE_SIZE);
+    }
+}
+
+/// Clears the day cache when dropped. Methods writing notes or days hold one so the cache is
+/// cleared once their write is done.
+struct Writing<'a>(&'a Mutex<DayCache>);
+impl Drop for Writing<'_> {
+    fn drop(&mut self) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .days
+            .clear();
+    }
 }
 
+#[derive(Clone)]
 pub struct NoteStore {
     pub pool: SqlitePool,
+    cache: Arc<Mutex<DayCache>>,
+    /// `@name` tokens read as a note's context rather than a mention.
+    contexts: Arc<[String]>,
+    /// Workspace every day and note is read from and written to.
+    workspace: String,
+    /// Run for notes created or completed through the store, whichever interface wrote them.
+    hooks: Arc<HooksConfig>,
+    /// Pushes the completion of tagged notes, whichever interface completed them.
+    push: Arc<PushConfig>,
 }
 impl NoteStore {
+    /// A migrated database in memory, gone when the store is dropped.
+    /// Connections are never closed for being idle, which would lose the database.
+    pub async fn open_in_memory() -> Result<NoteStore> {
+        let pool = SqlitePoolOptions::new()
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .storage("Failed opening in-memory database.")?;
+        migrate!()
+            .run(&pool)
+            .await
+            .storage("Failed migrating in-memory database.")?;
+        Ok(NoteStore {
+            pool,
+            cache: Arc::default(),
+            contexts: Arc::new([]),
+            workspace: String::from(DEFAULT_WORKSPACE),
+            hooks: Arc::default(),
+            push: Arc::default(),
+        })
+    }
+    /// Open the database at `path`, creating and migrating it when needed.
+    pub async fn open(path: &Path) -> Result<NoteStore> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = SqlitePool::connect_with(options)
+            .await
+            .storage(format!("Failed opening database {}.", path.display()))?;
+        migrate!()
+            .run(&pool)
+            .await
+            .storage(format!("Failed migrating database {}.", path.display()))?;
+        Ok(NoteStore {
+            pool,
+            cache: Arc::default(),
+            contexts: Arc::new([]),
+            workspace: String::from(DEFAULT_WORKSPACE),
+            hooks: Arc::default(),
+            push: Arc::default(),
+        })
+    }
+    /// Read `@name` tokens naming one of `contexts` as the note's context when indexing.
+    pub fn with_contexts(mut self, contexts: &[String]) -> NoteStore {
+        self.contexts = contexts.iter().map(|c| c.to_lowercase()).collect();
+        self
+    }
+    /// Read and write the days and notes of `workspace`, other workspaces are never seen.
+    /// The store gets a cache of its own, as cached days are only keyed by date.
+    pub fn with_workspace(mut self, workspace: &str) -> NoteStore {
+        self.workspace = workspace.to_string();
+        self.cache = Arc::default();
+        self
+    }
+    /// Run `hooks` once a note is created or completed.
+    pub fn with_hooks(mut self, hooks: HooksConfig) -> NoteStore {
+        self.hooks = Arc::new(hooks);
+        self
+    }
+    /// Push completed notes through `push` when it's enabled.
+    pub fn with_push(mut self, push: PushConfig) -> NoteStore {
+        self.push = Arc::new(push);
+        self
+    }
+    /// Workspace the store reads and writes.
+    pub fn workspace(&self) -> &str {
+        &self.workspace
+    }
+    /// Move days before `before` with their notes into the database at `path`, returning how many
+    /// days moved. The archive is remembered so it can be read with [`NoteStore::archives`].
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn archive_before(&self, before: NaiveDate, path: &Path) -> Result<u64> {
+        let _writing = self.writing();
+        // Create the archive with the same schema, then copy across an attached connection.
+        NoteStore::open(path).await?.pool.close().await;
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .storage("Failed acquiring connection.")?;
+        sqlx::query("ATTACH DATABASE ?1 AS archive;")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(&mut *conn)
+            .await
+            .storage(format!("Failed attaching archive {}.", path.display()))?;
+        let moved = async {
+            let mut tx = sqlx::Connection::begin(&mut *conn)
+                .await
+                .storage("Failed to start transaction.")?;
+            sqlx::query(
+                "INSERT OR IGNORE INTO archive.project SELECT * FROM main.project WHERE id IN
+                (SELECT n.project_key FROM main.note as n INNER JOIN main.day as d ON n.day_key = d.id WHERE d.date < ?1 AND d.workspace = ?2);",
+            )
+            .bind(before)
+            .bind(self.workspace())
+            .execute(&mut *tx)
+            .await
+            .storage("Failed copying projects to archive.")?;
+            let moves = ARCHIVE_MOVES
+                .into_iter()
+                .chain(NOTE_TABLES.map(|table| (table, ARCHIVE_NOTE_ROWS)))
+                .collect::<Vec<_>>();
+            for (table, condition) in &moves {
+                sqlx::query(&format!(
+                    "INSERT INTO archive.{0} SELECT * FROM main.{0} WHERE {1};",
+                    table, condition
+                ))
+                .bind(before)
+                .bind(self.workspace())
+                .execute(&mut *tx)
+                .await
+                .storage(format!("Failed copying {} rows to archive.", table))?;
+            }
+            sqlx::query!(
+                "INSERT OR REPLACE INTO main.sync_tombstone (workspace, uid, date, body, status, section, due, deleted_at)
+                SELECT n.workspace, n.uid, d.date, n.body, n.status, n.section, n.due,
+                COALESCE(datetime(n.deleted_at), datetime('now'))
+                FROM main.note as n INNER JOIN main.day as d ON n.day_key = d.id
+                WHERE n.uid IS NOT NULL AND d.date < ?1 AND d.workspace = ?2;",
+                before,
+                self.workspace,
+            )
+            .execute(&mut *tx)
+            .await
+            .storage("Failed keeping sync tombstones.")?;
+            let mut moved = 0;
+            for (table, condition) in moves.iter().rev() {
+                moved = sqlx::query(&format!("DELETE FROM main.{} WHERE {};", table, condition))
+                    .bind(before)
+                    .bind(self.workspace())
+                    .execute(&mut *tx)
+                    .await
+                    .storage(format!("Failed deleting archived {} rows.", table))?
+                    .rows_affected();
+            }
+            tx.commit()
+                .await
+                .storage(format!("Failed archiving days before {}.", before))?;
+            Ok::<_, FhError>(moved)
+        }
+        .await;
+        sqlx::query("DETACH DATABASE archive;")
+            .execute(&mut *conn)
+            .await
+            .storage("Failed detaching archive.")?;
+        let moved = moved?;
+        let archive_path = path.to_string_lossy().into_owned();
+        sqlx::query!(
+            "INSERT INTO archive (path, before) VALUES (?1, ?2) ON CONFLICT (path) DO UPDATE SET before = MAX(before, ?2);",
+            archive_path,
+            before,
+        )
+        .execute(&self.pool)
+        .await
+        .storage("Failed recording archive.")?;
+        Ok(moved)
+    }
+    /// Paths of the databases days have been archived into.
+    pub async fn archives(&self) -> Result<Vec<PathBuf>> {
+        sqlx::query_scalar!("SELECT path FROM archive ORDER BY before;")
+            .fetch_all(&self.pool)
+            .await
+            .storage("Failed fetching archives.")
+            .map(|paths| paths.into_iter().map(PathBuf::from).collect())
+    }
     pub async fn soft_delte_note_by_id(&self, id: u32) -> Result<()> {
+        let _writing = self.writing();
         sqlx::query!(
-            r#"UPDATE note SET deleted_at = (datetime('now')) WHERE id =?;"#,
-            id
+            r#"UPDATE note SET deleted_at = (datetime('now')) WHERE id = ?1 AND workspace = ?2;"#,
+            id,
+            self.workspace
         )
         .execute(&self.pool)
         .await
-        .context("Failed to soft delete note.")
+        .storage("Failed to soft delete note.")
         .map(|_| ())
     }
-    pub async fn fetch_day(&self, d: NaiveDate) -> Result<Option<DateRow>> {
-        sqlx::query_as!(
-            DateRow,
-            r#"SELECT id "id: u32", date, task_count "task_count: u32", day_text FROM day WHERE date = ?1;"#,
-            d
+    /// Set where a note sits in its day, notes are shown in ascending position.
+    pub async fn update_note_position(&self, id: u32, position: u32) -> Result<()> {
+        let _writing = self.writing();
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .storage("Failed acquiring connection.")?;
+        self._update_note_position(&mut conn, id, position).await
+    }
+    async fn _update_note_position(
+        &self,
+        conn: &mut SqliteConnection,
+        id: u32,
+        position: u32,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE note SET position = ?1 WHERE id = ?2 AND workspace = ?3 AND position IS NOT ?1;",
+            position,
+            id,
+            self.workspace
         )
-        .fetch_optional(&self.pool)
+        .execute(conn)
         .await
-        .context("Failed fetchig day.")
+        .storage(format!("Failed updating position of note {}", id))
+        .map(|_| ())
     }
-    pub async fn update_note(&self, n: &Note) -> Result<Note> {
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn get_note(&self, id: u32) -> Result<Note> {
         sqlx::query_as!(
             NoteRow,
-            r#"UPDATE  note SET body = ?1, completed = ?2, updated_at = (datetime('now')) WHERE id = ?3
-            RETURNING id "id: u32",
+            r#"SELECT id "id: u32",
             body,
-            completed "completed: bool",
+            status "status: Status",
             created_at "created_at: DateTime<Utc>",
             updated_at "updated_at: DateTime<Utc>",
-            deleted_at "deleted_at: DateTime<Utc>"
-            "#,
+            deleted_at "deleted_at: DateTime<Utc>",
+            section,
+            due,
+            focus_rank "focus_rank?: u32"
+            FROM note WHERE id = ?1 AND deleted_at IS NULL AND workspace = ?2;"#,
+            id,
+            self.workspace
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .storage(format!("Failed fetching note {}.", id))?
+        .map(Note::from)
+        .ok_or(FhError::NoteNotFound { id })
+    }
+    /// The note `id` with the day it's on.
+    pub async fn get_dated_note(&self, id: u32) -> Result<(NaiveDate, Note)> {
+        let row = sqlx::query_as!(
+            NoteRowDate,
+            r#"SELECT
+            n.id "id: u32",
             n.body,
-            n.completed,
-            n.id,
-        ).fetch_one(&self.pool).await.context(format!("Failed updating note {}", n.id)).map(|r| Note::from(r))
+            n.status "status: Status",
+            n.created_at "created_at: DateTime<Utc>",
+            n.updated_at "updated_at: DateTime<Utc>",
+            n.deleted_at "deleted_at: DateTime<Utc>",
+            n.section,
+            n.due,
+            n.focus_rank "focus_rank?: u32",
+            d.date
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE n.id = ?1 AND n.deleted_at IS NULL AND d.workspace = ?2;"#,
+            id,
+            self.workspace
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .storage(format!("Failed fetching note {}.", id))?
+        .ok_or(FhError::NoteNotFound { id })?;
+        Ok((row.date, Note::from(row)))
     }
-    pub async fn insert_day(
+    /// Find an open note from `since` onwards whose body matches `body` ignoring case and whitespace.
+    pub async fn find_open_duplicate(
         &self,
-        d: NaiveDate,
-        task_count: Option<u32>,
-        text: impl AsRef<str>,
-    ) -> Result<DateRow> {
-        let task_count = task_count.unwrap_or(0) as i64;
-        let text = text.as_ref();
-        sqlx::query_as!(
-            DateRow,
-            r#"INSERT INTO day (date, task_count, day_text) VALUES (?1, ?2, ?3) RETURNING id "id: u32", date, task_count "task_count:u32", day_text;"#,
-            d,
-            task_count,
-            text
-        ).fetch_one(&self.pool).await.context("Failed inserting day.")
+        body: &str,
+        since: NaiveDate,
+    ) -> Result<Option<(NaiveDate, Note)>> {
+        let normalized = normalize_body(body);
+        let candidates = sqlx::query_as!(
+            NoteRowDate,
+            r#"SELECT
+            n.id "id: u32",
+            n.body,
+            n.status "status: Status",
+            n.created_at "created_at: DateTime<Utc>",
+            n.updated_at "updated_at: DateTime<Utc>",
+            n.deleted_at "deleted_at: DateTime<Utc>",
+            n.section,
+            n.due,
+            n.focus_rank "focus_rank?: u32",
+            d.date
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE d.date >= ?1 AND d.workspace = ?2 AND n.deleted_at IS NULL AND n.status IN ('todo', 'doing', 'blocked')
+            ORDER BY d.date DESC;"#,
+            since,
+            self.workspace,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed searching for duplicate notes.")?;
+        Ok(candidates
+            .into_iter()
+            .find(|row| normalize_body(&row.body) == normalized)
+            .map(|row| (row.date, Note::from(row))))
     }
-    pub async fn insert_note(&self, n: NewNote) -> Result<Note> {
-        let utc_naive = n.created_at.date_naive();
-        let day_key = match sqlx::query_scalar!(r#"SELECT id FROM day WHERE date=?1;"#, utc_naive)
-            .fetch_optional(&self.pool)
-            .await
-            .context("Failed fetching day during note insertion.")?
-        {
-            Some(id) => id as u32,
-            None => {
-                let day = self.insert_day(utc_naive, None, "").await?;
-                day.id as u32
-            }
-        };
-        let note = self
-            ._insert_note(&n.body, n.created_at, n.completed, day_key)
-            .await
-            .map(|id| n.to_note(id));
-        note
+    /// Open notes from days before `today` or with a due date before `today`, once a day that
+    /// isn't skipped has passed since.
+    pub async fn overdue_notes(&self, today: NaiveDate) -> Result<Vec<(NaiveDate, Note)>> {
+        let rows = sqlx::query_as!(
+            NoteRowDate,
+            r#"SELECT
+            n.id "id: u32",
+            n.body,
+            n.status "status: Status",
+            n.created_at "created_at: DateTime<Utc>",
+            n.updated_at "updated_at: DateTime<Utc>",
+            n.deleted_at "deleted_at: DateTime<Utc>",
+            n.section,
+            n.due,
+            n.focus_rank "focus_rank?: u32",
+            d.date
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE d.workspace = ?2 AND n.deleted_at IS NULL AND n.status IN ('todo', 'doing', 'blocked')
+            AND (d.date < ?1 OR n.due < ?1)
+            AND julianday(?1) - julianday(CASE WHEN n.due < ?1 THEN n.due ELSE d.date END) > (
+                SELECT COUNT(*) FROM skipped_day as s
+                WHERE s.workspace = ?2 AND s.date > (CASE WHEN n.due < ?1 THEN n.due ELSE d.date END) AND s.date <= ?1
+            )
+            ORDER BY d.date, n.position;"#,
+            today,
+            self.workspace,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching overdue notes.")?;
+        Ok(rows.into_iter().map(|r| (r.date, Note::from(r))).collect())
     }
-    async fn _insert_note(
+    pub async fn day_counts(&self, date: NaiveDate) -> Result<DayCounts> {
+        let row = sqlx::query!(
+            r#"SELECT
+            COALESCE(SUM(n.status IN ('todo', 'doing', 'blocked')), 0) "open!: i64",
+            COALESCE(SUM(n.status = 'done'), 0) "done!: i64",
+            COUNT(n.id) "total!: i64"
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE n.deleted_at IS NULL AND d.date = ?1 AND d.workspace = ?2;"#,
+            date,
+            self.workspace
+        )
+        .fetch_one(&self.pool)
+        .await
+        .storage(format!("Failed counting notes for {}.", date))?;
+        Ok(DayCounts {
+            open: row.open,
+            done: row.done,
+            total: row.total,
+        })
+    }
+    /// Totals of the notes on the days from `start_day` to `end_day` inclusive.
+    pub async fn range_totals(
         &self,
-        body: impl AsRef<str>,
-        created_at: DateTime<Utc>,
-        completed: bool,
-        day_key: u32,
-    ) -> Result<u32> {
-        let body = body.as_ref();
+        start_day: NaiveDate,
+        end_day: NaiveDate,
+    ) -> Result<RangeTotals> {
+        let row = sqlx::query!(
+            r#"SELECT
+            COUNT(n.id) "created!: i64",
+            COALESCE(SUM(n.status = 'done'), 0) "completed!: i64",
+            COALESCE(SUM(n.status IN ('todo', 'doing', 'blocked') AND d.date < ?2), 0) "carried_over!: i64"
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE n.deleted_at IS NULL AND d.date BETWEEN ?1 AND ?2 AND d.workspace = ?3;"#,
+            start_day,
+            end_day,
+            self.workspace
+        )
+        .fetch_one(&self.pool)
+        .await
+        .storage(format!(
+            "Failed totalling notes between {} and {}.",
+            start_day, end_day
+        ))?;
+        Ok(RangeTotals {
+            created: row.created,
+            completed: row.completed,
+            carried_over: row.carried_over,
+        })
+    }
+    /// Count of notes [`NoteStore::overdue_notes`] would list.
+    pub async fn overdue_count(&self, today: NaiveDate) -> Result<i64> {
         sqlx::query_scalar!(
-            r#"INSERT INTO note (body, created_at, completed, day_key) VALUES (?1, ?2, ?3, ?4) RETURNING id "id: u32";"#,
-            body,
-            created_at,
-            completed,
-            day_key,
+            r#"SELECT COUNT(*) "count: i64"
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE d.workspace = ?2 AND n.deleted_at IS NULL AND n.status IN ('todo', 'doing', 'blocked')
+            AND (d.date < ?1 OR n.due < ?1)
+            AND julianday(?1) - julianday(CASE WHEN n.due < ?1 THEN n.due ELSE d.date END) > (
+                SELECT COUNT(*) FROM skipped_day as s
+                WHERE s.workspace = ?2 AND s.date > (CASE WHEN n.due < ?1 THEN n.due ELSE d.date END) AND s.date <= ?1
+            );"#,
+            today,
+            self.workspace
         )
         .fetch_one(&self.pool)
         .await
-        .context("Failed adding note.")
+        .storage("Failed counting overdue notes.")
+    }
+    /// Days in a row up to `today` with a completed note. Today only adds to the streak,
+    /// so a streak isn't broken before anything is done, and skipped days don't break it.
+    pub async fn streak(&self, today: NaiveDate) -> Result<u32> {
+        let dates = sqlx::query_scalar!(
+            r#"SELECT DISTINCT d.date "date: NaiveDate"
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE n.deleted_at IS NULL AND n.status = 'done' AND d.date <= ?1 AND d.workspace = ?2
+            ORDER BY d.date DESC;"#,
+            today,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching completed days.")?;
+        let first = dates.last().copied().unwrap_or(today);
+        let skipped = self.skipped_days(first, today).await?;
+        let mut expected = today - Days::new(1);
+        let mut streak = 0;
+        for date in dates {
+            if date == today {
+                streak += 1;
+                continue;
+            }
+            while date < expected && skipped.contains_key(&expected) {
+                expected = expected - Days::new(1);
+            }
+            if date != expected {
+                break;
+            }
+            streak += 1;
+            expected = date - Days::new(1);
+        }
+        Ok(streak)
+    }
+    /// Days from `start_day` to `end_day` marked skipped, with the reason given.
+    pub async fn skipped_days(
+        &self,
+        start_day: NaiveDate,
+        end_day: NaiveDate,
+    ) -> Result<BTreeMap<NaiveDate, Option<String>>> {
+        sqlx::query!(
+            r#"SELECT date "date: NaiveDate", reason FROM skipped_day
+            WHERE date BETWEEN ?1 AND ?2 AND workspace = ?3;"#,
+            start_day,
+            end_day,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!(
+            "Failed fetching skipped days from {} to {}.",
+            start_day, end_day
+        ))
+        .map(|rows| rows.into_iter().map(|r| (r.date, r.reason)).collect())
     }
-    pub async fn persist_parsed_day_note(&self, note: ParsedDayNotes) -> Result<DayNotes> {
+    /// Mark the days from `start_day` to `end_day` as intentionally empty, replacing the reason
+    /// of days already skipped. Returns how many days were marked.
+    pub async fn skip_days(
+        &self,
+        start_day: NaiveDate,
+        end_day: NaiveDate,
+        reason: Option<&str>,
+    ) -> Result<u64> {
+        if end_day < start_day {
+            return Err(FhError::Invalid(format!(
+                "Can't skip from {} to the earlier {}.",
+                start_day, end_day
+            )));
+        }
         let mut tx = self
             .pool
             .begin()
             .await
-            .context("Failed to start transaction.")?;
-        let day_key = sqlx::query_scalar!(
-            r#"INSERT INTO day (date, task_count, day_text)
-            VALUES (?1, ?2, ?3)
-            ON CONFLICT (date)
-            DO UPDATE SET date=?1, task_count=?2, day_text=?3 RETURNING id;"#,
-            note.date,
-            note.note_count,
-            note.day_text,
-        )
-        .fetch_one(&mut *tx)
-        .await
-        .context("Failied upserting day note.")?;
-        let mut notes = vec![];
-        for n in note.notes {
-            let note = match n {
-                ParsedNote::NewNote(n) => self
-                    ._insert_note(&n.body, n.created_at, n.completed, day_key as u32)
-                    .await
-                    .map(|id| n.to_note(id))?,
-                ParsedNote::Note(n) => {
-                    self.update_note(&n).await?;
-                    n
-                }
-            };
-            notes.push(note);
+            .storage("Failed to start transaction.")?;
+        let mut skipped = 0;
+        for date in start_day.iter_days().take_while(|d| *d <= end_day) {
+            skipped += sqlx::query!(
+                "INSERT INTO skipped_day (date, reason, workspace) VALUES (?1, ?2, ?3)
+                ON CONFLICT (workspace, date) DO UPDATE SET reason = excluded.reason;",
+                date,
+                reason,
+                self.workspace
+            )
+            .execute(&mut *tx)
+            .await
+            .storage(format!("Failed skipping {}.", date))?
+            .rows_affected();
         }
         tx.commit().await?;
-        let note_count = notes.len() as u32;
-        Ok(DayNotes {
-            notes,
-            date: note.date,
-            day_text: note.day_text,
-            note_count,
-        })
+        Ok(skipped)
     }
-
-    pub async fn update_day_text(&self, date: NaiveDate, day_text: impl AsRef<str>) -> Result<()> {
-        let day_text = day_text.as_ref();
+    /// Soft delete every note of `date` and clear its text, returning how many notes were deleted.
+    pub async fn delete_day(&self, date: NaiveDate) -> Result<u64> {
+        let _writing = self.writing();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        let deleted = sqlx::query!(
+            r#"UPDATE note SET deleted_at = (datetime('now'))
+            WHERE deleted_at IS NULL AND day_key = (SELECT id FROM day WHERE date = ?1 AND workspace = ?2);"#,
+            date,
+            self.workspace
+        )
+        .execute(&mut *tx)
+        .await
+        .storage(format!("Failed deleting the notes of {}.", date))?
+        .rows_affected();
         sqlx::query!(
-            "UPDATE day SET day_text = ?1 WHERE date = ?2;",
-            day_text,
+            "UPDATE day SET day_text = '' WHERE date = ?1 AND workspace = ?2;",
             date,
+            self.workspace
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
-        .map(|_| ())
-        .context("Failed while updating day text.")
+        .storage(format!("Failed clearing day text of {}.", date))?;
+        tx.commit().await?;
+        Ok(deleted)
     }
-    /// Get day notes in inclusive range.
-    pub async fn get_day_notes_in_range(
-        &self,
-        start_day: NaiveDate,
-        end_day: NaiveDate,
-    ) -> Result<Vec<DayNotes>> {
-        let mut jobbies = sqlx::query_as!(
-            NoteRowDate,
+    /// Open notes due on or before `until` that haven't been notified for their due date yet.
+    pub async fn unnotified_due(&self, until: NaiveDate) -> Result<Vec<Note>> {
+        let rows = sqlx::query_as!(
+            NoteRow,
             r#"SELECT
             n.id "id: u32",
             n.body,
-            n.completed "completed: bool",
+            n.status "status: Status",
             n.created_at "created_at: DateTime<Utc>",
             n.updated_at "updated_at: DateTime<Utc>",
             n.deleted_at "deleted_at: DateTime<Utc>",
-            d.date
-            FROM note as n INNER JOIN day as d ON n.day_key = d.id WHERE d.date BETWEEN ?1 AND ?2 and n.deleted_at IS NULL
-            ORDER BY n.created_at;"#,
-            start_day,
-            end_day
+            n.section,
+            n.due,
+            n.focus_rank "focus_rank?: u32"
+            FROM note as n LEFT JOIN notified as nd ON nd.note_id = n.id
+            WHERE n.deleted_at IS NULL AND n.status IN ('todo', 'doing', 'blocked')
+            AND n.due <= ?1 AND (nd.due IS NULL OR nd.due != n.due) AND n.workspace = ?2
+            ORDER BY n.due, n.id;"#,
+            until,
+            self.workspace,
         )
         .fetch_all(&self.pool)
         .await
-        .context(format!("Failed fetching day notes between days {}:{}.", start_day, end_day))?;
-        log::info!(
-            "Fetched rows {} when querying days between {} and {}",
-            jobbies.len(),
-            start_day,
-            end_day
-        );
-        let day_delta = (end_day - start_day).num_days() + 1;
-        let mut notes: HashMap<NaiveDate, Vec<NoteRowDate>> =
-            HashMap::with_capacity(day_delta as usize);
-        for row in jobbies {
-            let day = row.date;
-            notes.entry(day).or_default().push(row);
+        .storage("Failed fetching notes to notify.")?;
+        Ok(rows.into_iter().map(Note::from).collect())
+    }
+    pub async fn mark_notified(&self, id: u32, due: NaiveDate) -> Result<()> {
+        self.get_note(id).await?;
+        sqlx::query!(
+            "INSERT INTO notified (note_id, due) VALUES (?1, ?2)
+            ON CONFLICT (note_id) DO UPDATE SET due = excluded.due;",
+            id,
+            due
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed marking note {} notified.", id))?;
+        Ok(())
+    }
+    /// Remind about note `id` at `remind_at`, replacing an earlier reminder, `None` clears it.
+    pub async fn set_reminder(&self, id: u32, remind_at: Option<DateTime<Utc>>) -> Result<()> {
+        self.get_note(id).await?;
+        let query = match remind_at {
+            Some(at) => {
+                sqlx::query!(
+                    "INSERT INTO reminder (note_id, remind_at) VALUES (?1, ?2)
+                    ON CONFLICT (note_id) DO UPDATE SET remind_at = excluded.remind_at;",
+                    id,
+                    at
+                )
+                .execute(&self.pool)
+                .await
+            }
+            None => {
+                sqlx::query!("DELETE FROM reminder WHERE note_id = ?1;", id)
+                    .execute(&self.pool)
+                    .await
+            }
+        };
+        query
+            .storage(format!("Failed setting the reminder of note {}.", id))
+            .map(|_| ())
+    }
+    /// Open notes with a reminder, soonest first. With `due` only reminders at or before `now`,
+    /// otherwise only those after it.
+    pub async fn reminders(
+        &self,
+        now: DateTime<Utc>,
+        due: bool,
+    ) -> Result<Vec<(DateTime<Utc>, Note)>> {
+        let rows = sqlx::query!(
+            r#"SELECT
+            r.remind_at "remind_at: DateTime<Utc>",
+            n.id "id: u32",
+            n.body,
+            n.status "status: Status",
+            n.created_at "created_at: DateTime<Utc>",
+            n.updated_at "updated_at: DateTime<Utc>",
+            n.deleted_at "deleted_at: DateTime<Utc>",
+            n.section,
+            n.due,
+            n.focus_rank "focus_rank?: u32"
+            FROM reminder as r INNER JOIN note as n ON r.note_id = n.id
+            WHERE n.deleted_at IS NULL AND n.status IN ('todo', 'doing', 'blocked') AND n.workspace = ?3
+            AND (r.remind_at <= ?1) = ?2
+            ORDER BY r.remind_at, n.id;"#,
+            now,
+            due,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching reminders.")?;
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                let note = NoteRow {
+                    id: r.id,
+                    body: r.body,
+                    status: r.status,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    deleted_at: r.deleted_at,
+                    section: r.section,
+                    due: r.due,
+                    focus_rank: r.focus_rank,
+                };
+                (r.remind_at, Note::from(note))
+            })
+            .collect())
+    }
+    /// Move a note onto the day `date`, creating the day if needed.
+    pub async fn move_note(&self, id: u32, date: NaiveDate) -> Result<()> {
+        let _writing = self.writing();
+        let day_key = self.day_key(date).await?;
+        sqlx::query!(
+            r#"UPDATE note SET day_key = ?1, updated_at = (datetime('now')) WHERE id = ?2 AND workspace = ?3;"#,
+            day_key,
+            id,
+            self.workspace
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed moving note {} to {}.", id, date))
+        .map(|_| ())
+    }
+    /// Fold note `dup_id` into `keep_id`, appending its body, taking its project and every row
+    /// belonging to it, and soft deleting it. Where both notes have a row, the kept note's stays.
+    pub async fn merge_notes(&self, keep_id: u32, dup_id: u32) -> Result<Note> {
+        let _writing = self.writing();
+        if keep_id == dup_id {
+            return Err(FhError::Invalid(format!(
+                "Can't merge note {} into itself.",
+                keep_id
+            )));
+        }
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        let mut bodies = Vec::with_capacity(2);
+        for id in [keep_id, dup_id] {
+            let body = sqlx::query_scalar!(
+                r#"SELECT body FROM note WHERE id = ?1 AND deleted_at IS NULL AND workspace = ?2;"#,
+                id,
+                self.workspace
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .storage(format!("Failed fetching note {}.", id))?
+            .ok_or(FhError::NoteNotFound { id })?;
+            bodies.push(body);
+        }
+        let body = bodies.join("; ");
+        let merged = sqlx::query_as!(
+            NoteRow,
+            r#"UPDATE note SET body = ?1, updated_at = (datetime('now')),
+            project_key = COALESCE(project_key, (SELECT project_key FROM note WHERE id = ?3 AND workspace = ?4))
+            WHERE id = ?2 AND workspace = ?4
+            RETURNING id "id: u32",
+            body,
+            status "status: Status",
+            created_at "created_at: DateTime<Utc>",
+            updated_at "updated_at: DateTime<Utc>",
+            deleted_at "deleted_at: DateTime<Utc>",
+            section,
+            due,
+            focus_rank "focus_rank?: u32"
+            "#,
+            body,
+            keep_id,
+            dup_id,
+            self.workspace,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .storage(format!("Failed updating note {}.", keep_id))?;
+        for table in NOTE_TABLES {
+            sqlx::query(&format!(
+                "UPDATE OR IGNORE {} SET note_id = ?1 WHERE note_id = ?2;",
+                table
+            ))
+            .bind(keep_id)
+            .bind(dup_id)
+            .execute(&mut *tx)
+            .await
+            .storage(format!("Failed moving {} rows of note {}.", table, dup_id))?;
+        }
+        sqlx::query!(
+            r#"UPDATE note SET deleted_at = (datetime('now')) WHERE id = ?1 AND workspace = ?2;"#,
+            dup_id,
+            self.workspace
+        )
+        .execute(&mut *tx)
+        .await
+        .storage(format!("Failed deleting note {}.", dup_id))?;
+        tx.commit().await?;
+        Ok(Note::from(merged))
+    }
+    pub async fn fetch_day(&self, d: NaiveDate) -> Result<Option<DateRow>> {
+        sqlx::query_as!(
+            DateRow,
+            r#"SELECT id "id: u32", date, task_count "task_count: u32", day_text FROM day WHERE date = ?1 AND workspace = ?2;"#,
+            d,
+            self.workspace
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .storage("Failed fetchig day.")
+    }
+    /// Save a note's body, status and section, `updated_at` is only bumped when one changed.
+    #[tracing::instrument(level = "debug", skip_all, fields(id = n.id))]
+    pub async fn update_note(&self, n: &Note) -> Result<Note> {
+        let _writing = self.writing();
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .storage("Failed acquiring connection.")?;
+        let (note, completed) = self._update_note(&mut conn, n).await?;
+        drop(conn);
+        if completed {
+            self.note_completed(&note).await;
+        }
+        Ok(note)
+    }
+    /// Save a note, with whether it's newly done.
+    async fn _update_note(&self, conn: &mut SqliteConnection, n: &Note) -> Result<(Note, bool)> {
+        let was_done = match n.status {
+            Status::Done => sqlx::query_scalar!(
+                r#"SELECT status = 'done' "done: bool" FROM note WHERE id = ?1 AND workspace = ?2;"#,
+                n.id,
+                self.workspace
+            )
+            .fetch_optional(&mut *conn)
+            .await
+            .storage(format!("Failed fetching note {}.", n.id))?
+            .unwrap_or(false),
+            _ => false,
+        };
+        let status = n.status.as_str();
+        let note = sqlx::query_as!(
+            NoteRow,
+            r#"UPDATE  note SET body = ?1, status = ?2, section = ?4,
+            updated_at = CASE WHEN body IS ?1 AND status IS ?2 AND section IS ?4 THEN updated_at ELSE (datetime('now')) END
+            WHERE id = ?3 AND workspace = ?5
+            RETURNING id "id: u32",
+            body,
+            status "status: Status",
+            created_at "created_at: DateTime<Utc>",
+            updated_at "updated_at: DateTime<Utc>",
+            deleted_at "deleted_at: DateTime<Utc>",
+            section,
+            due,
+            focus_rank "focus_rank?: u32"
+            "#,
+            n.body,
+            status,
+            n.id,
+            n.section,
+            self.workspace,
+        ).fetch_one(conn).await.storage(format!("Failed updating note {}", n.id)).map(Note::from)?;
+        let completed = note.status == Status::Done && !was_done;
+        Ok((note, completed))
+    }
+    pub async fn insert_day(
+        &self,
+        d: NaiveDate,
+        task_count: Option<u32>,
+        text: impl AsRef<str>,
+    ) -> Result<DateRow> {
+        let _writing = self.writing();
+        let task_count = task_count.unwrap_or(0) as i64;
+        let text = text.as_ref();
+        sqlx::query_as!(
+            DateRow,
+            r#"INSERT INTO day (date, task_count, day_text, workspace) VALUES (?1, ?2, ?3, ?4) RETURNING id "id: u32", date, task_count "task_count:u32", day_text;"#,
+            d,
+            task_count,
+            text,
+            self.workspace
+        ).fetch_one(&self.pool).await.storage("Failed inserting day.")
+    }
+    /// Id of the day row for `date`, inserting the day if it doesn't exist.
+    pub async fn day_key(&self, date: NaiveDate) -> Result<u32> {
+        match sqlx::query_scalar!(
+            r#"SELECT id FROM day WHERE date = ?1 AND workspace = ?2;"#,
+            date,
+            self.workspace
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .storage("Failed fetching day key.")?
+        {
+            Some(id) => Ok(id as u32),
+            None => Ok(self.insert_day(date, None, "").await?.id),
+        }
+    }
+    /// Update a note read from the buffer, or insert it if it's new.
+    /// An encrypted note's placeholder body keeps the stored body.
+    pub async fn save_parsed_note(&self, n: ParsedNote) -> Result<Note> {
+        match n {
+            ParsedNote::NewNote(n) => self.insert_note(n).await,
+            ParsedNote::Note(mut n) => {
+                if n.body == secret::ENCRYPTED_BODY {
+                    n.body = self.get_note(n.id).await?.body;
+                }
+                self.update_note(&n).await
+            }
+        }
+    }
+    pub async fn insert_note(&self, n: NewNote) -> Result<Note> {
+        self.insert_note_on(n.created_at.date_naive(), n).await
+    }
+    /// Insert a note onto the day `date` rather than the day it was created.
+    #[tracing::instrument(level = "debug", skip(self, n))]
+    pub async fn insert_note_on(&self, date: NaiveDate, n: NewNote) -> Result<Note> {
+        let _writing = self.writing();
+        let day_key = self.day_key(date).await?;
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .storage("Failed acquiring connection.")?;
+        let note = self
+            ._insert_note(&mut conn, &n, day_key)
+            .await
+            .map(|id| Note {
+                date: Some(date),
+                ..n.to_note(id)
+            })?;
+        self.hooks.note_created(&note);
+        Ok(note)
+    }
+    async fn _insert_note(
+        &self,
+        conn: &mut SqliteConnection,
+        n: &NewNote,
+        day_key: u32,
+    ) -> Result<u32> {
+        let status = n.status.as_str();
+        sqlx::query_scalar!(
+            r#"INSERT INTO note (body, created_at, status, section, day_key, position, due, workspace)
+            VALUES (?1, ?2, ?3, ?4, ?5, (SELECT COALESCE(MAX(position) + 1, 0) FROM note WHERE day_key = ?5), ?6, ?7)
+            RETURNING id "id: u32";"#,
+            n.body,
+            n.created_at,
+            status,
+            n.section,
+            day_key,
+            n.due,
+            self.workspace,
+        )
+        .fetch_one(conn)
+        .await
+        .storage("Failed adding note.")
+    }
+    /// Insert every note onto the day `date` in one transaction, in order.
+    #[tracing::instrument(level = "debug", skip(self, notes))]
+    pub async fn insert_notes_on(&self, date: NaiveDate, notes: Vec<NewNote>) -> Result<Vec<Note>> {
+        let _writing = self.writing();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        let day_key = sqlx::query_scalar!(
+            r#"INSERT INTO day (date, task_count, day_text, workspace)
+            VALUES (?1, 0, '', ?2)
+            ON CONFLICT (workspace, date)
+            DO UPDATE SET date=?1 RETURNING id "id: u32";"#,
+            date,
+            self.workspace,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .storage("Failed upserting day.")?;
+        let mut out = Vec::with_capacity(notes.len());
+        for n in notes {
+            let id = self._insert_note(&mut tx, &n, day_key).await?;
+            out.push(Note {
+                date: Some(date),
+                ..n.to_note(id)
+            });
+        }
+        tx.commit().await?;
+        for note in &out {
+            self.hooks.note_created(note);
+        }
+        Ok(out)
+    }
+    /// Save the days read from the editor in one transaction, their notes in buffer order.
+    /// Unchanged notes aren't written, so their `updated_at` still wins syncs and exports.
+    /// Notes of a day missing from its buffer are soft deleted, except those in `keep`, which
+    /// go after the buffer's notes.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn persist_parsed_days(
+        &self,
+        days: Vec<ParsedDayNotes>,
+        keep: &[u32],
+    ) -> Result<Vec<DayNotes>> {
+        let writing = self.writing();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        let mut created = vec![];
+        let mut completed = vec![];
+        let mut dates = Vec::with_capacity(days.len());
+        for day in days {
+            let day_key = sqlx::query_scalar!(
+                r#"INSERT INTO day (date, task_count, day_text, workspace)
+                VALUES (?1, 0, ?2, ?3)
+                ON CONFLICT (workspace, date)
+                DO UPDATE SET day_text = ?2
+                RETURNING id "id: u32";"#,
+                day.date,
+                day.day_text,
+                self.workspace,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .storage(format!("Failed upserting day {}.", day.date))?;
+            let before = sqlx::query_as!(
+                NoteRow,
+                r#"SELECT id "id: u32",
+                body,
+                status "status: Status",
+                created_at "created_at: DateTime<Utc>",
+                updated_at "updated_at: DateTime<Utc>",
+                deleted_at "deleted_at: DateTime<Utc>",
+                section,
+                due,
+                focus_rank "focus_rank?: u32"
+                FROM note WHERE day_key = ?1 AND deleted_at IS NULL;"#,
+                day_key
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .storage(format!("Failed fetching notes of {}.", day.date))?;
+            let mut seen = Vec::with_capacity(day.note_count as usize);
+            let parsed_notes = day
+                .notes
+                .into_iter()
+                .chain(day.sections.into_iter().flat_map(|s| s.notes));
+            for parsed in parsed_notes {
+                let id = match parsed {
+                    ParsedNote::NewNote(n) => {
+                        let id = self._insert_note(&mut tx, &n, day_key).await?;
+                        let note = Note {
+                            date: Some(day.date),
+                            ..n.to_note(id)
+                        };
+                        self._index_note_body(&mut tx, &note).await?;
+                        created.push(note);
+                        id
+                    }
+                    ParsedNote::Note(mut n) => {
+                        // An encrypted note's placeholder body keeps the stored body.
+                        if n.body == secret::ENCRYPTED_BODY {
+                            n.body = sqlx::query_scalar!(
+                                "SELECT body FROM note WHERE id = ?1 AND workspace = ?2;",
+                                n.id,
+                                self.workspace
+                            )
+                            .fetch_optional(&mut *tx)
+                            .await
+                            .storage(format!("Failed fetching note {}.", n.id))?
+                            .ok_or(FhError::NoteNotFound { id: n.id })?;
+                        }
+                        let unchanged = before.iter().any(|old| {
+                            old.id == n.id
+                                && old.body == n.body
+                                && old.status == n.status
+                                && old.section == n.section
+                        });
+                        if !unchanged {
+                            let (note, done) = self._update_note(&mut tx, &n).await?;
+                            self._index_note_body(&mut tx, &note).await?;
+                            if done {
+                                completed.push(note);
+                            }
+                        }
+                        n.id
+                    }
+                };
+                self._update_note_position(&mut tx, id, seen.len() as u32)
+                    .await?;
+                seen.push(id);
+            }
+            let removed = before
+                .iter()
+                .filter(|old| !seen.contains(&old.id))
+                .collect::<Vec<_>>();
+            let mut position = seen.len() as u32;
+            for old in &removed {
+                if keep.contains(&old.id) {
+                    self._update_note_position(&mut tx, old.id, position)
+                        .await?;
+                    position += 1;
+                    continue;
+                }
+                sqlx::query!(
+                    r#"UPDATE note SET deleted_at = (datetime('now')) WHERE id = ?1 AND workspace = ?2;"#,
+                    old.id,
+                    self.workspace
+                )
+                .execute(&mut *tx)
+                .await
+                .storage(format!("Failed deleting note {}.", old.id))?;
+                tracing::info!("Deleted note {} removed from {}.", old.id, day.date);
+            }
+            dates.push(day.date);
+        }
+        tx.commit().await?;
+        drop(writing);
+        for note in &created {
+            self.hooks.note_created(note);
+        }
+        for note in &completed {
+            self.note_completed(note).await;
+        }
+        let mut saved = Vec::with_capacity(dates.len());
+        for date in dates {
+            saved.push(self.get_days_notes(date).await?);
+        }
+        Ok(saved)
+    }
+
+    /// Duplicate the notes of day `from` onto day `to`, returning how many were copied.
+    /// With `open_only` done and cancelled notes are skipped, with `reset_status` copies start as todo.
+    pub async fn copy_notes(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        open_only: bool,
+        reset_status: bool,
+    ) -> Result<u64> {
+        let _writing = self.writing();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        let day_key = sqlx::query_scalar!(
+            r#"INSERT INTO day (date, task_count, day_text, workspace)
+            VALUES (?1, 0, '', ?2)
+            ON CONFLICT (workspace, date)
+            DO UPDATE SET date=?1 RETURNING id;"#,
+            to,
+            self.workspace,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .storage("Failed upserting target day.")?;
+        let copied = sqlx::query!(
+            r#"INSERT INTO note (body, status, section, day_key, position, workspace)
+            SELECT
+            n.body,
+            CASE WHEN ?3 THEN 'todo' ELSE n.status END,
+            n.section,
+            ?4,
+            n.position + (SELECT COALESCE(MAX(position) + 1, 0) FROM note WHERE day_key = ?4),
+            d.workspace
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE d.date = ?1 AND d.workspace = ?5 AND n.deleted_at IS NULL AND (NOT ?2 OR n.status IN ('todo', 'doing', 'blocked'))
+            ORDER BY n.position, n.created_at;"#,
+            from,
+            open_only,
+            reset_status,
+            day_key,
+            self.workspace,
+        )
+        .execute(&mut *tx)
+        .await
+        .storage(format!("Failed copying notes from {} to {}.", from, to))?
+        .rows_affected();
+        tx.commit().await?;
+        Ok(copied)
+    }
+
+    /// Move every note and the text of day `from` onto day `to`, after the notes and text `to`
+    /// already has. Returns how many notes moved.
+    pub async fn move_day(&self, from: NaiveDate, to: NaiveDate) -> Result<u64> {
+        let _writing = self.writing();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        let day_key = sqlx::query_scalar!(
+            r#"INSERT INTO day (date, task_count, day_text, workspace)
+            VALUES (?1, 0, '', ?2)
+            ON CONFLICT (workspace, date)
+            DO UPDATE SET date=?1 RETURNING id;"#,
+            to,
+            self.workspace,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .storage("Failed upserting target day.")?;
+        let moved = sqlx::query!(
+            r#"UPDATE note SET
+            day_key = ?2,
+            position = position + (SELECT COALESCE(MAX(position) + 1, 0) FROM note WHERE day_key = ?2),
+            updated_at = (datetime('now'))
+            WHERE day_key = (SELECT id FROM day WHERE date = ?1 AND workspace = ?3);"#,
+            from,
+            day_key,
+            self.workspace,
+        )
+        .execute(&mut *tx)
+        .await
+        .storage(format!("Failed moving notes from {} to {}.", from, to))?
+        .rows_affected();
+        sqlx::query!(
+            r#"UPDATE day SET day_text = CASE
+            WHEN day_text = '' THEN (SELECT day_text FROM day WHERE date = ?1 AND workspace = ?3)
+            ELSE day_text || char(10) || (SELECT day_text FROM day WHERE date = ?1 AND workspace = ?3)
+            END
+            WHERE id = ?2 AND (SELECT day_text FROM day WHERE date = ?1 AND workspace = ?3) != '';"#,
+            from,
+            day_key,
+            self.workspace,
+        )
+        .execute(&mut *tx)
+        .await
+        .storage(format!("Failed moving day text from {} to {}.", from, to))?;
+        sqlx::query!(
+            "UPDATE day SET day_text = '' WHERE date = ?1 AND workspace = ?2;",
+            from,
+            self.workspace
+        )
+        .execute(&mut *tx)
+        .await
+        .storage(format!("Failed clearing day text of {}.", from))?;
+        tx.commit().await?;
+        Ok(moved)
+    }
+
+    /// Replace `old` with `new` in note bodies and day text between the optional inclusive bounds.
+    /// With `dry_run` the changes are computed but rolled back.
+    pub async fn replace_text(
+        &self,
+        old: &str,
+        new: &str,
+        start_day: Option<NaiveDate>,
+        end_day: Option<NaiveDate>,
+        dry_run: bool,
+    ) -> Result<Vec<Replacement>> {
+        let _writing = self.writing();
+        if old.is_empty() {
+            return Err(FhError::Invalid(String::from(
+                "Text to replace can't be empty.",
+            )));
+        }
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        let mut out = vec![];
+        let notes = sqlx::query!(
+            r#"SELECT n.id "id: u32", n.body, d.date
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE (?1 IS NULL OR d.date >= ?1) AND (?2 IS NULL OR d.date <= ?2) AND d.workspace = ?4
+            AND n.deleted_at IS NULL AND instr(n.body, ?3) > 0
+            ORDER BY d.date, n.position;"#,
+            start_day,
+            end_day,
+            old,
+            self.workspace,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .storage("Failed finding notes to replace text in.")?;
+        for row in notes {
+            let after = row.body.replace(old, new);
+            sqlx::query!(
+                "UPDATE note SET body = ?1, updated_at = (datetime('now')) WHERE id = ?2 AND workspace = ?3;",
+                after,
+                row.id,
+                self.workspace
+            )
+            .execute(&mut *tx)
+            .await
+            .storage(format!("Failed replacing text in note {}.", row.id))?;
+            out.push(Replacement {
+                date: row.date,
+                note_id: Some(row.id),
+                before: row.body,
+                after,
+            });
+        }
+        let days = sqlx::query!(
+            r#"SELECT date, day_text FROM day
+            WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date <= ?2) AND workspace = ?4
+            AND instr(day_text, ?3) > 0
+            ORDER BY date;"#,
+            start_day,
+            end_day,
+            old,
+            self.workspace,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .storage("Failed finding day text to replace text in.")?;
+        for row in days {
+            let after = row.day_text.replace(old, new);
+            sqlx::query!(
+                "UPDATE day SET day_text = ?1 WHERE date = ?2 AND workspace = ?3;",
+                after,
+                row.date,
+                self.workspace
+            )
+            .execute(&mut *tx)
+            .await
+            .storage(format!("Failed replacing text on {}.", row.date))?;
+            out.push(Replacement {
+                date: row.date,
+                note_id: None,
+                before: row.day_text,
+                after,
+            });
+        }
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+        Ok(out)
+    }
+
+    /// Notes matching `filter` with the date they belong to.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn filtered_notes(&self, filter: &NoteFilter) -> Result<Vec<(NaiveDate, Note)>> {
+        let mut qb = QueryBuilder::new(NOTE_ROW_DATE_SELECT);
+        filter.push_conditions(&mut qb);
+        self.fetch_note_rows(qb, None).await
+    }
+    /// Notes matching `filter` across all days, oldest first, at most `limit` of them.
+    pub async fn list_notes(
+        &self,
+        filter: &NoteFilter,
+        limit: Option<u32>,
+    ) -> Result<Vec<(NaiveDate, Note)>> {
+        let mut qb = QueryBuilder::new(NOTE_ROW_DATE_SELECT);
+        filter.push_conditions(&mut qb);
+        self.fetch_note_rows(qb, limit).await
+    }
+    /// Mark every note matching `filter` done, returning those that weren't done before.
+    pub async fn bulk_complete(&self, filter: &NoteFilter) -> Result<Vec<Note>> {
+        let _writing = self.writing();
+        let mut qb = QueryBuilder::new(
+            "UPDATE note SET status = 'done', updated_at = (datetime('now')) WHERE status != 'done' AND id IN ",
+        );
+        filter.push_ids(&mut qb, self.workspace());
+        qb.push(" AND workspace = ")
+            .push_bind(self.workspace.clone());
+        qb.push(NOTE_ROW_RETURNING);
+        let notes = qb
+            .build_query_as::<NoteRow>()
+            .fetch_all(&self.pool)
+            .await
+            .storage("Failed completing filtered notes.")?
+            .into_iter()
+            .map(Note::from)
+            .collect::<Vec<_>>();
+        for note in &notes {
+            self.note_completed(note).await;
+        }
+        Ok(notes)
+    }
+    /// Soft delete every note matching `filter`, returning the deleted notes.
+    pub async fn bulk_delete(&self, filter: &NoteFilter) -> Result<Vec<Note>> {
+        let _writing = self.writing();
+        let mut qb =
+            QueryBuilder::new("UPDATE note SET deleted_at = (datetime('now')) WHERE id IN ");
+        filter.push_ids(&mut qb, self.workspace());
+        qb.push(" AND workspace = ")
+            .push_bind(self.workspace.clone());
+        qb.push(NOTE_ROW_RETURNING);
+        qb.build_query_as::<NoteRow>()
+            .fetch_all(&self.pool)
+            .await
+            .storage("Failed deleting filtered notes.")
+            .map(|rows| rows.into_iter().map(Note::from).collect())
+    }
+    /// Tag every note matching `filter`, returning how many were newly tagged.
+    pub async fn bulk_tag(&self, filter: &NoteFilter, tag: &str) -> Result<u64> {
+        let mut qb = QueryBuilder::new("INSERT OR IGNORE INTO note_tag (note_id, tag) SELECT id, ");
+        qb.push_bind(String::from(tag))
+            .push(" FROM note WHERE id IN ");
+        filter.push_ids(&mut qb, self.workspace());
+        qb.push(" AND workspace = ")
+            .push_bind(self.workspace.clone());
+        qb.build()
+            .execute(&self.pool)
+            .await
+            .storage("Failed tagging filtered notes.")
+            .map(|r| r.rows_affected())
+    }
+    pub async fn set_status(&self, id: u32, status: Status) -> Result<Note> {
+        let mut note = self.get_note(id).await?;
+        note.status = status;
+        self.update_note(&note).await
+    }
+    /// Focus the given notes in order, replacing the focus of the days they're on.
+    /// At most `limit` notes can be focused on a day.
+    pub async fn set_focus(&self, ids: &[u32], limit: usize) -> Result<()> {
+        let _writing = self.writing();
+        let mut days: Vec<(u32, Vec<u32>)> = vec![];
+        for id in ids {
+            let day_key = sqlx::query_scalar!(
+                r#"SELECT day_key "day_key: u32" FROM note WHERE id = ?1 AND deleted_at IS NULL AND workspace = ?2;"#,
+                id,
+                self.workspace
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .storage(format!("Failed fetching note {}.", id))?
+            .ok_or(FhError::NoteNotFound { id: *id })?;
+            match days.iter_mut().find(|(key, _)| *key == day_key) {
+                Some((_, day_ids)) => day_ids.push(*id),
+                None => days.push((day_key, vec![*id])),
+            }
+        }
+        if let Some((_, day_ids)) = days.iter().find(|(_, day_ids)| day_ids.len() > limit) {
+            return Err(FhError::Invalid(format!(
+                "Can only focus {} notes a day, got {:?}.",
+                limit, day_ids
+            )));
+        }
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        for (day_key, day_ids) in days {
+            sqlx::query!(
+                "UPDATE note SET focus_rank = NULL WHERE day_key = ?;",
+                day_key
+            )
+            .execute(&mut *tx)
+            .await
+            .storage("Failed clearing focus.")?;
+            for (rank, id) in day_ids.iter().enumerate() {
+                let rank = rank as u32 + 1;
+                sqlx::query!(
+                    "UPDATE note SET focus_rank = ?1 WHERE id = ?2 AND workspace = ?3;",
+                    rank,
+                    id,
+                    self.workspace
+                )
+                .execute(&mut *tx)
+                .await
+                .storage(format!("Failed focusing note {}.", id))?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+    /// Set or clear the due date of a note.
+    pub async fn set_due(&self, id: u32, due: Option<NaiveDate>) -> Result<()> {
+        let _writing = self.writing();
+        sqlx::query!(
+            "UPDATE note SET due = ?1, updated_at = (datetime('now')) WHERE id = ?2 AND workspace = ?3;",
+            due,
+            id,
+            self.workspace
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed setting due date of note {}.", id))
+        .map(|_| ())
+    }
+    /// Notes matching a structured query with the date they belong to.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn query_notes(&self, query: &Query) -> Result<Vec<(NaiveDate, Note)>> {
+        let mut qb = QueryBuilder::new(NOTE_ROW_DATE_SELECT);
+        query.push_conditions(&mut qb);
+        self.fetch_note_rows(qb, None).await
+    }
+    /// Save a query under `name`, replacing any query already saved with that name.
+    pub async fn save_query(&self, name: &str, query: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO saved_query (name, query) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET query = ?2;",
+            name,
+            query,
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .storage(format!("Failed saving query {}.", name))
+    }
+    pub async fn get_saved_query(&self, name: &str) -> Result<Option<String>> {
+        sqlx::query_scalar!("SELECT query FROM saved_query WHERE name = ?;", name)
+            .fetch_optional(&self.pool)
+            .await
+            .storage(format!("Failed fetching saved query {}.", name))
+    }
+    /// Notes, including deleted ones, changed after `since`, oldest change first.
+    /// Timestamps are normalised with `datetime` as inserts and updates store different formats.
+    pub async fn notes_changed_since(&self, since: Option<&str>) -> Result<Vec<ChangedNote>> {
+        let since = since.unwrap_or_default();
+        let rows = sqlx::query!(
+            r#"SELECT
+            n.id "id: u32",
+            n.body,
+            n.status "status: Status",
+            n.section,
+            n.due,
+            n.focus_rank "focus_rank?: u32",
+            n.created_at "created_at: DateTime<Utc>",
+            n.updated_at "updated_at: DateTime<Utc>",
+            n.deleted_at "deleted_at: DateTime<Utc>",
+            d.date,
+            MAX(datetime(COALESCE(n.updated_at, n.created_at)), COALESCE(datetime(n.deleted_at), '')) "changed_at!: String",
+            n.deleted_at IS NOT NULL "deleted!: bool"
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE MAX(datetime(COALESCE(n.updated_at, n.created_at)), COALESCE(datetime(n.deleted_at), '')) > ?1
+            AND d.workspace = ?2
+            ORDER BY 8, n.id;"#,
+            since,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching notes changed since {}.", since))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| ChangedNote {
+                date: r.date,
+                note: Note {
+                    id: r.id,
+                    body: r.body,
+                    status: r.status,
+                    section: r.section,
+                    due: r.due,
+                    focus_rank: r.focus_rank,
+                    created_at: Some(r.created_at),
+                    updated_at: r.updated_at,
+                    deleted_at: r.deleted_at,
+                    date: Some(r.date),
+                },
+                changed_at: r.changed_at,
+                deleted: r.deleted,
+            })
+            .collect())
+    }
+    /// Latest change emitted by the export `name`.
+    pub async fn export_mark(&self, name: &str) -> Result<Option<String>> {
+        sqlx::query_scalar!("SELECT changed_at FROM export_mark WHERE name = ?;", name)
+            .fetch_optional(&self.pool)
+            .await
+            .storage(format!("Failed fetching export mark {}.", name))
+    }
+    pub async fn set_export_mark(&self, name: &str, changed_at: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO export_mark (name, changed_at) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET changed_at = ?2;",
+            name,
+            changed_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .storage(format!("Failed saving export mark {}.", name))
+    }
+    /// Save a shared html snapshot under `token` until `expires_at`.
+    pub async fn create_share(
+        &self,
+        token: &str,
+        html: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO share (token, html, expires_at) VALUES (?1, ?2, ?3);",
+            token,
+            html,
+            expires_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .storage("Failed saving share.")
+    }
+    /// Html of the share `token`, `None` once it has expired.
+    pub async fn get_share(&self, token: &str, now: DateTime<Utc>) -> Result<Option<String>> {
+        sqlx::query_scalar!(
+            "SELECT html FROM share WHERE token = ?1 AND expires_at > ?2;",
+            token,
+            now,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .storage("Failed fetching share.")
+    }
+    /// The edit session holding `date`, if any.
+    pub async fn edit_lock(&self, date: NaiveDate) -> Result<Option<EditLock>> {
+        sqlx::query_as!(
+            EditLock,
+            r#"SELECT pid "pid: u32", locked_at "locked_at: DateTime<Utc>" FROM edit_lock WHERE date = ?1 AND workspace = ?2;"#,
+            date,
+            self.workspace
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .storage(format!("Failed fetching edit lock of {}.", date))
+    }
+    /// Hold `date` for the edit session of process `pid`, replacing any other lock.
+    pub async fn lock_day(&self, date: NaiveDate, pid: u32) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO edit_lock (date, pid, workspace) VALUES (?1, ?2, ?3)
+            ON CONFLICT (workspace, date) DO UPDATE SET pid = ?2, locked_at = (datetime('now'));",
+            date,
+            pid,
+            self.workspace
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed locking {}.", date))
+        .map(|_| ())
+    }
+    /// Release the lock on `date` if process `pid` still holds it.
+    pub async fn unlock_day(&self, date: NaiveDate, pid: u32) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM edit_lock WHERE date = ?1 AND pid = ?2 AND workspace = ?3;",
+            date,
+            pid,
+            self.workspace
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed unlocking {}.", date))
+        .map(|_| ())
+    }
+    /// Mood, energy and tags of `date`, empty when none were recorded.
+    pub async fn day_metrics(&self, date: NaiveDate) -> Result<DayMetrics> {
+        Ok(self
+            .day_metrics_in_range(date, date)
+            .await?
+            .pop()
+            .map(|(_, m)| m)
+            .unwrap_or_default())
+    }
+    /// Days from `start_day` to `end_day` with recorded metrics, in date order.
+    pub async fn day_metrics_in_range(
+        &self,
+        start_day: NaiveDate,
+        end_day: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, DayMetrics)>> {
+        let rows = sqlx::query!(
+            r#"SELECT date "date: NaiveDate", mood "mood: u8", energy "energy: u8", tags
+            FROM day_metric WHERE date BETWEEN ?1 AND ?2 AND workspace = ?3 ORDER BY date;"#,
+            start_day,
+            end_day,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!(
+            "Failed fetching metrics from {} to {}.",
+            start_day, end_day
+        ))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                let tags = r
+                    .tags
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect();
+                let metrics = DayMetrics {
+                    mood: r.mood,
+                    energy: r.energy,
+                    tags,
+                };
+                (r.date, metrics)
+            })
+            .collect())
+    }
+    /// Replace the metrics of `date`, empty metrics remove them.
+    pub async fn set_day_metrics(&self, date: NaiveDate, metrics: &DayMetrics) -> Result<()> {
+        for value in [metrics.mood, metrics.energy].into_iter().flatten() {
+            if !(1..=5).contains(&value) {
+                return Err(FhError::Invalid(format!(
+                    "Mood and energy go from 1 to 5, not {}.",
+                    value
+                )));
+            }
+        }
+        let query = if metrics.is_empty() {
+            sqlx::query!(
+                "DELETE FROM day_metric WHERE date = ?1 AND workspace = ?2;",
+                date,
+                self.workspace
+            )
+            .execute(&self.pool)
+            .await
+        } else {
+            let tags = metrics.tags.join(",");
+            sqlx::query!(
+                "INSERT INTO day_metric (date, mood, energy, tags, workspace) VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (workspace, date) DO UPDATE SET mood = ?2, energy = ?3, tags = ?4;",
+                date,
+                metrics.mood,
+                metrics.energy,
+                tags,
+                self.workspace
+            )
+            .execute(&self.pool)
+            .await
+        };
+        query
+            .storage(format!("Failed saving metrics of {}.", date))
+            .map(|_| ())
+    }
+    /// Frontmatter metadata of `date` by key.
+    pub async fn day_meta(&self, date: NaiveDate) -> Result<BTreeMap<String, String>> {
+        sqlx::query!(
+            "SELECT key, value FROM day_meta WHERE date = ?1 AND workspace = ?2;",
+            date,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching metadata of {}.", date))
+        .map(|rows| rows.into_iter().map(|r| (r.key, r.value)).collect())
+    }
+    /// Replace the frontmatter metadata of `date`.
+    pub async fn set_day_meta(
+        &self,
+        date: NaiveDate,
+        meta: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        sqlx::query!(
+            "DELETE FROM day_meta WHERE date = ?1 AND workspace = ?2;",
+            date,
+            self.workspace
+        )
+        .execute(&mut *tx)
+        .await
+        .storage(format!("Failed clearing metadata of {}.", date))?;
+        for (key, value) in meta {
+            sqlx::query!(
+                "INSERT INTO day_meta (date, key, value, workspace) VALUES (?1, ?2, ?3, ?4);",
+                date,
+                key,
+                value,
+                self.workspace
+            )
+            .execute(&mut *tx)
+            .await
+            .storage(format!("Failed setting {} on {}.", key, date))?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+    /// Id of this database, used by its sync peers.
+    pub async fn device_id(&self) -> Result<String> {
+        sqlx::query_scalar!("SELECT id FROM device;")
+            .fetch_one(&self.pool)
+            .await
+            .storage("Failed fetching device id.")
+    }
+    /// Give every note without one a sync uid.
+    pub async fn ensure_note_uids(&self) -> Result<()> {
+        sqlx::query!("UPDATE note SET uid = lower(hex(randomblob(16))) WHERE uid IS NULL;")
+            .execute(&self.pool)
+            .await
+            .storage("Failed adding note uids.")
+            .map(|_| ())
+    }
+    /// When this database last synced with the peer `device`.
+    pub async fn synced_at(&self, device: &str) -> Result<Option<String>> {
+        sqlx::query_scalar!("SELECT synced_at FROM sync_peer WHERE device = ?;", device)
+            .fetch_optional(&self.pool)
+            .await
+            .storage(format!("Failed fetching last sync with {}.", device))
+    }
+    pub async fn set_synced_at(&self, device: &str, synced_at: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO sync_peer (device, synced_at) VALUES (?1, ?2) ON CONFLICT (device) DO UPDATE SET synced_at = ?2;",
+            device,
+            synced_at,
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .storage(format!("Failed saving last sync with {}.", device))
+    }
+    /// Notes with a uid, including deleted and purged ones, changed since `since`.
+    /// Changes in the same second as `since` are included, timestamps only have seconds.
+    pub async fn sync_changes_since(&self, since: Option<&str>) -> Result<Vec<SyncNote>> {
+        let since = since.unwrap_or_default();
+        let rows = sqlx::query!(
+            r#"SELECT
+            n.uid "uid!: String",
+            n.body,
+            n.status "status: Status",
+            n.section,
+            n.due,
+            d.date,
+            MAX(datetime(COALESCE(n.updated_at, n.created_at)), COALESCE(datetime(n.deleted_at), '')) "changed_at!: String",
+            n.deleted_at IS NOT NULL "deleted!: bool"
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE n.uid IS NOT NULL AND d.workspace = ?2
+            AND MAX(datetime(COALESCE(n.updated_at, n.created_at)), COALESCE(datetime(n.deleted_at), '')) >= ?1
+            ORDER BY 7, n.id;"#,
+            since,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching notes changed since {}.", since))?;
+        let mut changes = rows
+            .into_iter()
+            .map(|r| SyncNote {
+                uid: r.uid,
+                date: r.date,
+                body: r.body,
+                status: r.status,
+                section: r.section,
+                due: r.due,
+                changed_at: r.changed_at,
+                deleted: r.deleted,
+            })
+            .collect::<Vec<_>>();
+        let tombstones = sqlx::query!(
+            r#"SELECT uid, date "date: NaiveDate", body, status "status: Status", section,
+            due "due: NaiveDate", deleted_at
+            FROM sync_tombstone WHERE workspace = ?2 AND deleted_at >= ?1;"#,
+            since,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching notes purged since {}.", since))?;
+        changes.extend(tombstones.into_iter().map(|r| SyncNote {
+            uid: r.uid,
+            date: r.date,
+            body: r.body,
+            status: r.status,
+            section: r.section,
+            due: r.due,
+            changed_at: r.deleted_at,
+            deleted: true,
+        }));
+        changes.sort_by(|a, b| a.changed_at.cmp(&b.changed_at));
+        Ok(changes)
+    }
+    /// Write a note received from a sync peer, keeping its change time so it isn't sent back.
+    #[tracing::instrument(level = "debug", skip_all, fields(uid = %note.uid))]
+    pub async fn apply_sync_note(&self, note: &SyncNote) -> Result<()> {
+        let _writing = self.writing();
+        if note.deleted {
+            let purged = sqlx::query_scalar!(
+                "SELECT uid FROM sync_tombstone WHERE uid = ?1 AND workspace = ?2;",
+                note.uid,
+                self.workspace
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .storage(format!("Failed fetching tombstone of {}.", note.uid))?;
+            // Purged here already, inserting it would only bring the deleted note back.
+            if purged.is_some() {
+                return Ok(());
+            }
+        }
+        let day_key = self.day_key(note.date).await?;
+        let status = note.status.as_str();
+        let deleted_at = note.deleted.then_some(note.changed_at.as_str());
+        let updated = sqlx::query!(
+            r#"UPDATE note SET body = ?2, status = ?3, section = ?4, due = ?5, day_key = ?6,
+            updated_at = ?7, deleted_at = ?8
+            WHERE uid = ?1 AND workspace = ?9;"#,
+            note.uid,
+            note.body,
+            status,
+            note.section,
+            note.due,
+            day_key,
+            note.changed_at,
+            deleted_at,
+            self.workspace,
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed updating synced note {}.", note.uid))?
+        .rows_affected();
+        if updated > 0 {
+            return Ok(());
+        }
+        sqlx::query!(
+            r#"INSERT INTO note (uid, body, status, section, due, day_key, created_at, deleted_at, position, workspace)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, (SELECT COALESCE(MAX(position) + 1, 0) FROM note WHERE day_key = ?6), ?9);"#,
+            note.uid,
+            note.body,
+            status,
+            note.section,
+            note.due,
+            day_key,
+            note.changed_at,
+            deleted_at,
+            self.workspace,
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed adding synced note {}.", note.uid))
+        .map(|_| ())
+    }
+    /// Run a single arbitrary SQL statement on a read only connection, returning column names and
+    /// rows rendered as text. Pragmas and attaching databases are refused.
+    pub async fn read_only_query(&self, sql: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+        check_read_only_sql(sql)?;
+        let options = (*self.pool.connect_options()).clone().read_only(true);
+        let mut conn: SqliteConnection = sqlx::Connection::connect_with(&options)
+            .await
+            .storage("Failed opening read only connection.")?;
+        // In-memory databases ignore the read only flag, the connection is never handed back.
+        sqlx::query("PRAGMA query_only = ON;")
+            .execute(&mut conn)
+            .await
+            .storage("Failed making connection read only.")?;
+        let rows = sqlx::query(sql)
+            .fetch_all(&mut conn)
+            .await
+            .storage("Failed running query, only reads are allowed.");
+        sqlx::Connection::close(conn)
+            .await
+            .storage("Failed closing read only connection.")?;
+        let rows = rows?;
+        let columns = match rows.first() {
+            Some(row) => row.columns().iter().map(|c| c.name().to_string()).collect(),
+            None => vec![],
+        };
+        let values = rows
+            .iter()
+            .map(|row| {
+                (0..row.len())
+                    .map(|i| sql_value_to_string(row, i))
+                    .collect()
+            })
+            .collect();
+        Ok((columns, values))
+    }
+    async fn fetch_note_rows(
+        &self,
+        mut qb: QueryBuilder<'static, Sqlite>,
+        limit: Option<u32>,
+    ) -> Result<Vec<(NaiveDate, Note)>> {
+        qb.push(" AND d.workspace = ")
+            .push_bind(self.workspace.clone());
+        qb.push(" ORDER BY d.date, n.position, n.created_at");
+        if let Some(limit) = limit {
+            qb.push(" LIMIT ").push_bind(limit);
+        }
+        qb.push(";");
+        let rows = qb
+            .build_query_as::<NoteRowDate>()
+            .fetch_all(&self.pool)
+            .await
+            .storage("Failed fetching filtered notes.")?;
+        Ok(rows.into_iter().map(|r| (r.date, Note::from(r))).collect())
+    }
+    /// Set a metadata key on a note, replacing any previous value.
+    pub async fn set_meta(&self, note_id: u32, key: &str, value: &str) -> Result<()> {
+        self.get_note(note_id).await?;
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .storage("Failed acquiring connection.")?;
+        Self::_set_meta(&mut conn, note_id, key, value).await
+    }
+    async fn _set_meta(
+        conn: &mut SqliteConnection,
+        note_id: u32,
+        key: &str,
+        value: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO note_meta (note_id, key, value) VALUES (?1, ?2, ?3) ON CONFLICT (note_id, key) DO UPDATE SET value = ?3;",
+            note_id,
+            key,
+            value,
+        )
+        .execute(conn)
+        .await
+        .map(|_| ())
+        .storage(format!("Failed setting {} on note {}.", key, note_id))
+    }
+    /// Store the tokens of a note's body, `key:value` metadata, `#tag` tags and `@name` mentions.
+    /// Encrypted bodies aren't indexed.
+    pub async fn index_note_body(&self, note: &Note) -> Result<()> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        self._index_note_body(&mut tx, note).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+    async fn _index_note_body(&self, conn: &mut SqliteConnection, note: &Note) -> Result<()> {
+        if secret::is_encrypted(&note.body) {
+            return Ok(());
+        }
+        for (key, value) in meta_tokens(&note.body) {
+            Self::_set_meta(conn, note.id, key, value).await?;
+        }
+        Self::_set_body_tags(conn, note.id, &tag_tokens(&note.body)).await?;
+        if let Some(project) = project_token(&note.body) {
+            self._set_project(conn, note.id, &project).await?;
+        }
+        if has_private_token(&note.body) {
+            self._set_private(conn, note.id, true).await?;
+        }
+        let context = context_token(&note.body, &self.contexts);
+        self._set_context(conn, note.id, context.as_deref()).await?;
+        let mentions = mention_tokens(&note.body)
+            .into_iter()
+            .filter(|name| !self.contexts.contains(name))
+            .collect::<Vec<_>>();
+        Self::_set_mentions(conn, note.id, &mentions).await
+    }
+    /// Replace the tags read from a note's body, tags added by `bulk tag` stay.
+    async fn _set_body_tags(
+        conn: &mut SqliteConnection,
+        note_id: u32,
+        tags: &[String],
+    ) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM note_tag WHERE note_id = ? AND from_body = 1;",
+            note_id
+        )
+        .execute(&mut *conn)
+        .await
+        .storage(format!("Failed clearing tags of note {}.", note_id))?;
+        for tag in tags {
+            sqlx::query!(
+                "INSERT OR IGNORE INTO note_tag (note_id, tag, from_body) VALUES (?1, ?2, 1);",
+                note_id,
+                tag
+            )
+            .execute(&mut *conn)
+            .await
+            .storage(format!("Failed tagging note {}.", note_id))?;
+        }
+        Ok(())
+    }
+    /// Assign a note to a project, creating the project if needed.
+    pub async fn set_project(&self, note_id: u32, name: &str) -> Result<()> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .storage("Failed acquiring connection.")?;
+        self._set_project(&mut conn, note_id, name).await
+    }
+    async fn _set_project(
+        &self,
+        conn: &mut SqliteConnection,
+        note_id: u32,
+        name: &str,
+    ) -> Result<()> {
+        let name = name.trim_start_matches('+').to_lowercase();
+        let project_key = sqlx::query_scalar!(
+            "INSERT INTO project (name) VALUES (?1) ON CONFLICT (name) DO UPDATE SET name = ?1 RETURNING id;",
+            name
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .storage(format!("Failed upserting project {}.", name))?;
+        sqlx::query!(
+            "UPDATE note SET project_key = ?1, updated_at = (datetime('now')) WHERE id = ?2 AND workspace = ?3 AND project_key IS NOT ?1;",
+            project_key,
+            note_id,
+            self.workspace
+        )
+        .execute(conn)
+        .await
+        .storage(format!("Failed assigning note {} to {}.", note_id, name))
+        .map(|_| ())
+    }
+    /// Project name of every note assigned to one.
+    pub async fn projects_by_note(&self) -> Result<HashMap<u32, String>> {
+        sqlx::query!(
+            r#"SELECT n.id "id: u32", p.name FROM note as n INNER JOIN project as p ON n.project_key = p.id;"#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching note projects.")
+        .map(|rows| rows.into_iter().map(|r| (r.id, r.name)).collect())
+    }
+    /// Every project with its open and completed note counts.
+    pub async fn project_counts(&self) -> Result<Vec<(String, i64, i64)>> {
+        sqlx::query!(
+            r#"SELECT p.name,
+            COALESCE(SUM(n.status IN ('todo', 'doing', 'blocked')), 0) "open: i64",
+            COALESCE(SUM(n.status = 'done'), 0) "done: i64"
+            FROM project as p LEFT JOIN note as n
+            ON n.project_key = p.id AND n.deleted_at IS NULL AND n.workspace = ?1
+            GROUP BY p.id ORDER BY p.name;"#,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed counting project notes.")
+        .map(|rows| rows.into_iter().map(|r| (r.name, r.open, r.done)).collect())
+    }
+    /// Add a goal for `period`, its slug is the title lowercased with dashes.
+    pub async fn add_goal(&self, title: &str, period: &str) -> Result<Goal> {
+        let slug = goal_slug(title);
+        if slug.is_empty() {
+            return Err(FhError::Invalid(format!(
+                "No slug in goal title {}.",
+                title
+            )));
+        }
+        sqlx::query!(
+            "INSERT INTO goal (slug, title, period) VALUES (?1, ?2, ?3);",
+            slug,
+            title,
+            period
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!(
+            "Failed adding goal {}, is goal:{} taken?",
+            title, slug
+        ))?;
+        Ok(Goal {
+            slug,
+            title: title.to_string(),
+            period: period.to_string(),
+        })
+    }
+    /// Goals for `period`, or all of them, in period order.
+    pub async fn goals(&self, period: Option<&str>) -> Result<Vec<Goal>> {
+        sqlx::query_as!(
+            Goal,
+            "SELECT slug, title, period FROM goal WHERE ?1 IS NULL OR period = ?1 ORDER BY period, id;",
+            period
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching goals.")
+    }
+    /// Open and completed notes linked to each goal for `period`, or all goals, with the time
+    /// logged on them. Logged times that aren't a duration like `90m` or `2h` are skipped.
+    pub async fn goal_progress(&self, period: Option<&str>) -> Result<Vec<GoalProgress>> {
+        let goals = self.goals(period).await?;
+        let rows = sqlx::query!(
+            r#"SELECT lower(g.value) "slug!: String", n.status "status: Status", t.value "time?: String"
+            FROM note_meta as g INNER JOIN note as n ON g.note_id = n.id
+            LEFT JOIN note_meta as t ON t.note_id = n.id AND t.key = 'time'
+            WHERE g.key = 'goal' AND n.deleted_at IS NULL AND n.workspace = ?1;"#,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching goal notes.")?;
+        Ok(goals
+            .into_iter()
+            .map(|goal| {
+                let mut progress = GoalProgress {
+                    goal,
+                    open: 0,
+                    done: 0,
+                    time: TimeDelta::zero(),
+                };
+                for row in rows.iter().filter(|r| r.slug == progress.goal.slug) {
+                    match row.status {
+                        Status::Done => progress.done += 1,
+                        Status::Cancelled => {}
+                        _ => progress.open += 1,
+                    }
+                    if let Some(time) = row.time.as_deref().and_then(|t| parse_duration(t).ok()) {
+                        progress.time += time;
+                    }
+                }
+                progress
+            })
+            .collect())
+    }
+    /// Replace the people mentioned by a note.
+    pub async fn set_mentions(&self, note_id: u32, names: &[String]) -> Result<()> {
+        self.get_note(note_id).await?;
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        Self::_set_mentions(&mut tx, note_id, names).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+    async fn _set_mentions(
+        conn: &mut SqliteConnection,
+        note_id: u32,
+        names: &[String],
+    ) -> Result<()> {
+        sqlx::query!("DELETE FROM mention WHERE note_id = ?;", note_id)
+            .execute(&mut *conn)
+            .await
+            .storage(format!("Failed clearing mentions of note {}.", note_id))?;
+        for name in names {
+            sqlx::query!(
+                "INSERT OR IGNORE INTO mention (note_id, name) VALUES (?1, ?2);",
+                note_id,
+                name
+            )
+            .execute(&mut *conn)
+            .await
+            .storage(format!("Failed adding mention to note {}.", note_id))?;
+        }
+        Ok(())
+    }
+    /// Mark a note private, or public again.
+    pub async fn set_private(&self, id: u32, private: bool) -> Result<()> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .storage("Failed acquiring connection.")?;
+        self._set_private(&mut conn, id, private).await
+    }
+    async fn _set_private(
+        &self,
+        conn: &mut SqliteConnection,
+        id: u32,
+        private: bool,
+    ) -> Result<()> {
+        let result = sqlx::query!(
+            "UPDATE note SET private = ?1,
+            updated_at = CASE WHEN private IS ?1 THEN updated_at ELSE (datetime('now')) END
+            WHERE id = ?2 AND workspace = ?3;",
+            private,
+            id,
+            self.workspace
+        )
+        .execute(conn)
+        .await
+        .storage(format!("Failed setting private on note {}.", id))?;
+        if result.rows_affected() == 0 {
+            return Err(FhError::NoteNotFound { id });
+        }
+        Ok(())
+    }
+    /// Keep a note when the trash is purged, or let it be purged again.
+    pub async fn set_keep_forever(&self, id: u32, keep: bool) -> Result<()> {
+        let result = sqlx::query!(
+            "UPDATE note SET keep_forever = ?1 WHERE id = ?2 AND workspace = ?3;",
+            keep,
+            id,
+            self.workspace
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed keeping note {}.", id))?;
+        if result.rows_affected() == 0 {
+            return Err(FhError::NoteNotFound { id });
+        }
+        Ok(())
+    }
+    /// Deleted notes with their date and when they were deleted, most recently deleted first.
+    pub async fn deleted_notes(&self) -> Result<Vec<(NaiveDate, Note)>> {
+        let mut notes = self
+            .fetch_note_rows(
+                QueryBuilder::new(format!(
+                    "{} WHERE n.deleted_at IS NOT NULL",
+                    NOTE_ROW_DATE_SELECT
+                )),
+                None,
+            )
+            .await?;
+        notes.sort_by_key(|(_, n)| std::cmp::Reverse(n.deleted_at));
+        Ok(notes)
+    }
+    /// Hard delete notes soft deleted before `before` along with their tags, metadata and
+    /// mentions, notes marked keep forever stay. Returns how many notes were purged.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<u64> {
+        let _writing = self.writing();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .storage("Failed to start transaction.")?;
+        let condition =
+            "deleted_at IS NOT NULL AND keep_forever = 0 AND datetime(deleted_at) < datetime(?1)
+            AND workspace = ?2";
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO sync_tombstone (workspace, uid, date, body, status, section, due, deleted_at)
+            SELECT n.workspace, n.uid, d.date, n.body, n.status, n.section, n.due, datetime(n.deleted_at)
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE n.uid IS NOT NULL AND n.id IN (SELECT id FROM note WHERE {});",
+            condition
+        ))
+        .bind(before)
+        .bind(self.workspace())
+        .execute(&mut *tx)
+        .await
+        .storage("Failed keeping sync tombstones.")?;
+        for table in NOTE_TABLES {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE note_id IN (SELECT id FROM note WHERE {});",
+                table, condition
+            ))
+            .bind(before)
+            .bind(self.workspace())
+            .execute(&mut *tx)
+            .await
+            .storage(format!("Failed purging {} rows.", table))?;
+        }
+        let purged = sqlx::query(&format!("DELETE FROM note WHERE {};", condition))
+            .bind(before)
+            .bind(self.workspace())
+            .execute(&mut *tx)
+            .await
+            .storage("Failed purging deleted notes.")?
+            .rows_affected();
+        tx.commit()
+            .await
+            .storage(format!("Failed purging notes deleted before {}.", before))?;
+        Ok(purged)
+    }
+    /// Ids of every private note.
+    pub async fn private_note_ids(&self) -> Result<HashSet<u32>> {
+        sqlx::query_scalar!(
+            r#"SELECT id "id: u32" FROM note WHERE private = 1 AND workspace = ?1;"#,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching private notes.")
+        .map(|ids| ids.into_iter().collect())
+    }
+    /// Set where a note can be done, `None` clears it.
+    pub async fn set_context(&self, note_id: u32, context: Option<&str>) -> Result<()> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .storage("Failed acquiring connection.")?;
+        self._set_context(&mut conn, note_id, context).await
+    }
+    async fn _set_context(
+        &self,
+        conn: &mut SqliteConnection,
+        note_id: u32,
+        context: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE note SET context = ?1 WHERE id = ?2 AND workspace = ?3 AND context IS NOT ?1;",
+            context,
+            note_id,
+            self.workspace
+        )
+        .execute(conn)
+        .await
+        .storage(format!("Failed setting the context of note {}.", note_id))
+        .map(|_| ())
+    }
+    /// Ids of the open notes in `context`.
+    pub async fn context_note_ids(&self, context: &str) -> Result<HashSet<u32>> {
+        let context = context.trim_start_matches('@').to_lowercase();
+        sqlx::query_scalar!(
+            r#"SELECT id "id: u32" FROM note
+            WHERE context = ?1 AND status IN ('todo', 'doing', 'blocked') AND deleted_at IS NULL
+            AND workspace = ?2;"#,
+            context,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching notes at {}.", context))
+        .map(|ids| ids.into_iter().collect())
+    }
+    /// Ids of the notes mentioning `name`.
+    pub async fn mentioned_note_ids(&self, name: &str) -> Result<Vec<u32>> {
+        let name = name.trim_start_matches('@').to_lowercase();
+        sqlx::query_scalar!(
+            r#"SELECT m.note_id "note_id: u32" FROM mention as m
+            INNER JOIN note as n ON m.note_id = n.id
+            WHERE m.name = ?1 AND n.workspace = ?2 ORDER BY m.note_id;"#,
+            name,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching mentions of {}.", name))
+    }
+    /// Everyone mentioned in a note that hasn't been deleted, with how many notes mention them.
+    pub async fn mention_counts(&self) -> Result<Vec<(String, i64)>> {
+        sqlx::query!(
+            r#"SELECT m.name, COUNT(*) "count!: i64" FROM mention as m
+            INNER JOIN note as n ON m.note_id = n.id
+            WHERE n.deleted_at IS NULL AND n.workspace = ?1
+            GROUP BY m.name ORDER BY COUNT(*) DESC, m.name;"#,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed counting mentions.")
+        .map(|rows| rows.into_iter().map(|r| (r.name, r.count)).collect())
+    }
+    pub async fn get_meta(&self, note_id: u32) -> Result<Vec<(String, String)>> {
+        sqlx::query!(
+            "SELECT g.key, g.value FROM note_meta as g INNER JOIN note as n ON g.note_id = n.id
+            WHERE g.note_id = ?1 AND n.workspace = ?2 ORDER BY g.key;",
+            note_id,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching metadata of note {}.", note_id))
+        .map(|rows| rows.into_iter().map(|r| (r.key, r.value)).collect())
+    }
+    /// Tags of every note that has any.
+    pub async fn tags_by_note(&self) -> Result<HashMap<u32, Vec<String>>> {
+        let rows = sqlx::query!(
+            r#"SELECT t.note_id "note_id: u32", t.tag FROM note_tag as t
+            INNER JOIN note as n ON t.note_id = n.id
+            WHERE n.workspace = ? ORDER BY t.tag;"#,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching tags.")?;
+        let mut out: HashMap<u32, Vec<String>> = HashMap::new();
+        for row in rows {
+            out.entry(row.note_id).or_default().push(row.tag);
+        }
+        Ok(out)
+    }
+    /// Ids of the notes tagged `tag`, by `bulk tag` or a `#tag` word in the body.
+    pub async fn tagged_note_ids(&self, tag: &str) -> Result<HashSet<u32>> {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        sqlx::query_scalar!(
+            r#"SELECT t.note_id "note_id: u32" FROM note_tag as t
+            INNER JOIN note as n ON t.note_id = n.id
+            WHERE t.tag = ?1 AND n.workspace = ?2;"#,
+            tag,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching notes tagged {}.", tag))
+        .map(|ids| ids.into_iter().collect())
+    }
+    /// Every tag on a note that hasn't been deleted, with how many notes carry it.
+    pub async fn tag_counts(&self) -> Result<Vec<(String, i64)>> {
+        sqlx::query!(
+            r#"SELECT t.tag, COUNT(*) "count!: i64" FROM note_tag as t
+            INNER JOIN note as n ON t.note_id = n.id
+            WHERE n.deleted_at IS NULL AND n.workspace = ?1
+            GROUP BY t.tag ORDER BY COUNT(*) DESC, t.tag;"#,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed counting tags.")
+        .map(|rows| rows.into_iter().map(|r| (r.tag, r.count)).collect())
+    }
+    /// Record `name`, stored under `hash`, as attached to the note. Attaching the same content
+    /// again renames it.
+    pub async fn add_attachment(&self, note_id: u32, name: &str, hash: &str) -> Result<Attachment> {
+        self.get_note(note_id).await?;
+        sqlx::query!(
+            "INSERT INTO attachment (note_id, name, hash) VALUES (?1, ?2, ?3) ON CONFLICT (note_id, hash) DO UPDATE SET name = ?2;",
+            note_id,
+            name,
+            hash
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed attaching {} to note {}.", name, note_id))?;
+        Ok(Attachment {
+            note_id,
+            name: name.to_string(),
+            hash: hash.to_string(),
+        })
+    }
+    /// Attachments of every note that has any, oldest first.
+    pub async fn attachments_by_note(&self) -> Result<HashMap<u32, Vec<Attachment>>> {
+        let rows = sqlx::query!(
+            r#"SELECT a.note_id "note_id: u32", a.name, a.hash FROM attachment as a
+            INNER JOIN note as n ON a.note_id = n.id
+            WHERE n.workspace = ? ORDER BY a.created_at, a.rowid;"#,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage("Failed fetching attachments.")?;
+        let mut out: HashMap<u32, Vec<Attachment>> = HashMap::new();
+        for row in rows {
+            out.entry(row.note_id).or_default().push(Attachment {
+                note_id: row.note_id,
+                name: row.name,
+                hash: row.hash,
+            });
+        }
+        Ok(out)
+    }
+    /// Link a note to the item `remote_id` of `source`, e.g. `jira` and `PROJ-123`.
+    pub async fn link_note(&self, note_id: u32, source: &str, remote_id: &str) -> Result<()> {
+        self.get_note(note_id).await?;
+        sqlx::query!(
+            "INSERT INTO remote_link (note_id, source, remote_id) VALUES (?1, ?2, ?3);",
+            note_id,
+            source,
+            remote_id
+        )
+        .execute(&self.pool)
+        .await
+        .storage(format!("Failed linking note {} to {}.", note_id, remote_id))
+        .map(|_| ())
+    }
+    /// The note linked to `remote_id` of `source`, if any.
+    pub async fn linked_note(&self, source: &str, remote_id: &str) -> Result<Option<u32>> {
+        sqlx::query_scalar!(
+            r#"SELECT l.note_id "note_id: u32" FROM remote_link as l
+            INNER JOIN note as n ON l.note_id = n.id
+            WHERE l.source = ?1 AND l.remote_id = ?2 AND n.workspace = ?3;"#,
+            source,
+            remote_id,
+            self.workspace
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .storage(format!("Failed fetching the note linked to {}.", remote_id))
+    }
+    /// Item id of every note linked to `source`, by note id.
+    pub async fn remote_links(&self, source: &str) -> Result<HashMap<u32, String>> {
+        sqlx::query!(
+            r#"SELECT l.note_id "note_id: u32", l.remote_id FROM remote_link as l
+            INNER JOIN note as n ON l.note_id = n.id
+            WHERE l.source = ?1 AND n.workspace = ?2;"#,
+            source,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching links to {}.", source))
+        .map(|rows| rows.into_iter().map(|r| (r.note_id, r.remote_id)).collect())
+    }
+    /// Open notes linked to an item of `source`, with the item's id.
+    pub async fn open_linked_notes(&self, source: &str) -> Result<Vec<(u32, String)>> {
+        sqlx::query!(
+            r#"SELECT l.note_id "note_id: u32", l.remote_id FROM remote_link as l
+            INNER JOIN note as n ON l.note_id = n.id
+            WHERE l.source = ?1 AND n.status IN ('todo', 'doing', 'blocked') AND n.deleted_at IS NULL
+            AND n.workspace = ?2
+            ORDER BY l.note_id;"#,
+            source,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching notes linked to {}.", source))
+        .map(|rows| rows.into_iter().map(|r| (r.note_id, r.remote_id)).collect())
+    }
+    /// Value of the metadata `key` for every note that has it.
+    pub async fn meta_values(&self, key: &str) -> Result<HashMap<u32, String>> {
+        sqlx::query!(
+            r#"SELECT g.note_id "note_id: u32", g.value FROM note_meta as g
+            INNER JOIN note as n ON g.note_id = n.id
+            WHERE g.key = ?1 AND n.workspace = ?2;"#,
+            key,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching metadata {}.", key))
+        .map(|rows| rows.into_iter().map(|r| (r.note_id, r.value)).collect())
+    }
+    pub async fn get_tags(&self, note_id: u32) -> Result<Vec<String>> {
+        sqlx::query_scalar!(
+            "SELECT t.tag FROM note_tag as t INNER JOIN note as n ON t.note_id = n.id
+            WHERE t.note_id = ?1 AND n.workspace = ?2 ORDER BY t.tag;",
+            note_id,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!("Failed fetching tags of note {}.", note_id))
+    }
+
+    pub async fn update_day_text(&self, date: NaiveDate, day_text: impl AsRef<str>) -> Result<()> {
+        let _writing = self.writing();
+        let day_text = day_text.as_ref();
+        sqlx::query!(
+            "UPDATE day SET day_text = ?1 WHERE date = ?2 AND workspace = ?3;",
+            day_text,
+            date,
+            self.workspace,
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .storage("Failed while updating day text.")
+    }
+    /// Get day notes in inclusive range.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn get_day_notes_in_range(
+        &self,
+        start_day: NaiveDate,
+        end_day: NaiveDate,
+    ) -> Result<Vec<DayNotes>> {
+        let jobbies = sqlx::query_as!(
+            NoteRowDate,
+            r#"SELECT
+            n.id "id: u32",
+            n.body,
+            n.status "status: Status",
+            n.created_at "created_at: DateTime<Utc>",
+            n.updated_at "updated_at: DateTime<Utc>",
+            n.deleted_at "deleted_at: DateTime<Utc>",
+            n.section,
+            n.due,
+            n.focus_rank "focus_rank?: u32",
+            d.date
+            FROM note as n INNER JOIN day as d ON n.day_key = d.id
+            WHERE d.date BETWEEN ?1 AND ?2 AND d.workspace = ?3 and n.deleted_at IS NULL
+            ORDER BY n.position, n.created_at;"#,
+            start_day,
+            end_day,
+            self.workspace
+        )
+        .fetch_all(&self.pool)
+        .await
+        .storage(format!(
+            "Failed fetching day notes between days {}:{}.",
+            start_day, end_day
+        ))?;
+        tracing::info!(
+            "Fetched rows {} when querying days between {} and {}",
+            jobbies.len(),
+            start_day,
+            end_day
+        );
+        let day_delta = (end_day - start_day).num_days() + 1;
+        let mut notes: HashMap<NaiveDate, Vec<NoteRowDate>> =
+            HashMap::with_capacity(day_delta as usize);
+        for row in jobbies {
+            let day = row.date;
+            notes.entry(day).or_default().push(row);
+        }
+        let mut out = vec![];
+        for delta in 0..day_delta {
+            let day = start_day
+                .checked_add_days(Days::new(delta as u64))
+                .expect("shouldn't be able to overflow.");
+            let day_notes = notes
+                .remove(&day)
+                .unwrap_or(vec![])
+                .into_iter()
+                .map(Note::from)
+                .collect::<Vec<_>>();
+            let text = sqlx::query_scalar!(
+                "SELECT day_text from day WHERE date = ?1 AND workspace = ?2;",
+                day,
+                self.workspace
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .storage("Failed fetching day summary text.")?;
+            let note_count = day_notes.len() as u32;
+            out.push(DayNotes {
+                notes: day_notes,
+                date: day,
+                note_count,
+                day_text: text.unwrap_or(String::new()),
+            });
+        }
+        Ok(out)
+    }
+    /// Call `f` with each day from `start_day` to `end_day` in order, empty days included.
+    /// Rows are streamed so only the current day is held in memory, however long the range.
+    #[tracing::instrument(level = "debug", skip(self, f))]
+    pub async fn for_each_day_in_range<E: From<FhError>>(
+        &self,
+        start_day: NaiveDate,
+        end_day: NaiveDate,
+        mut f: impl AsyncFnMut(DayNotes) -> std::result::Result<(), E>,
+    ) -> std::result::Result<(), E> {
+        let empty = |date| DayNotes {
+            notes: vec![],
+            note_count: 0,
+            date,
+            day_text: String::new(),
+        };
+        let mut rows = sqlx::query!(
+            r#"SELECT
+            d.date,
+            d.day_text,
+            n.id "id?: u32",
+            n.body "body?",
+            n.status "status?: Status",
+            n.section,
+            n.due,
+            n.focus_rank "focus_rank?: u32",
+            n.created_at "created_at?: DateTime<Utc>",
+            n.updated_at "updated_at?: DateTime<Utc>"
+            FROM day as d LEFT JOIN note as n ON n.day_key = d.id AND n.deleted_at IS NULL
+            WHERE d.date BETWEEN ?1 AND ?2 AND d.workspace = ?3
+            ORDER BY d.date, n.position, n.created_at;"#,
+            start_day,
+            end_day,
+            self.workspace
+        )
+        .fetch(&self.pool);
+        let mut next_day = Some(start_day);
+        let mut current: Option<DayNotes> = None;
+        while let Some(row) = rows.try_next().await.storage(format!(
+            "Failed streaming day notes between days {}:{}.",
+            start_day, end_day
+        ))? {
+            if current.as_ref().is_none_or(|d| d.date != row.date) {
+                if let Some(day) = current.take() {
+                    next_day = day.date.succ_opt();
+                    f(day).await?;
+                }
+                while let Some(day) = next_day.filter(|d| *d < row.date) {
+                    f(empty(day)).await?;
+                    next_day = day.succ_opt();
+                }
+                current = Some(DayNotes {
+                    day_text: row.day_text,
+                    ..empty(row.date)
+                });
+            }
+            if let (Some(day), Some(id), Some(body), Some(status)) =
+                (current.as_mut(), row.id, row.body, row.status)
+            {
+                day.notes.push(Note {
+                    id,
+                    body,
+                    status,
+                    section: row.section,
+                    due: row.due,
+                    focus_rank: row.focus_rank,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                    deleted_at: None,
+                    date: Some(row.date),
+                });
+                day.note_count += 1;
+            }
+        }
+        if let Some(day) = current.take() {
+            next_day = day.date.succ_opt();
+            f(day).await?;
+        }
+        while let Some(day) = next_day.filter(|d| *d <= end_day) {
+            f(empty(day)).await?;
+            next_day = day.succ_opt();
+        }
+        Ok(())
+    }
+    pub async fn get_template(&self, name: &str) -> Result<Option<String>> {
+        sqlx::query_scalar!("SELECT body FROM template WHERE name = ?;", name)
+            .fetch_optional(&self.pool)
+            .await
+            .storage(format!("Failed fetching template {}.", name))
+    }
+    /// Get the template for a weekday, falling back to the default template.
+    pub async fn get_template_for(&self, weekday: Weekday) -> Result<Option<String>> {
+        match self.get_template(&weekday.to_string()).await? {
+            Some(t) => Ok(Some(t)),
+            None => self.get_template(DEFAULT_TEMPLATE).await,
+        }
+    }
+    pub async fn upsert_template(&self, name: &str, body: impl AsRef<str>) -> Result<()> {
+        let body = body.as_ref();
+        sqlx::query!(
+            "INSERT INTO template (name, body) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET body = ?2;",
+            name,
+            body,
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .storage(format!("Failed saving template {}.", name))
+    }
+    #[tracing::instrument(level = "debug", skip(self))]
+    /// Notes of one day, served from the cache when read recently.
+    pub async fn get_days_notes(&self, day: NaiveDate) -> Result<DayNotes> {
+        if let Some(notes) = self.day_cache().get(day) {
+            tracing::debug!("Using cached notes for day {}", day);
+            return Ok(notes);
+        }
+        let notes = self.get_day_notes_in_range(day, day).await?;
+        tracing::debug!("Found {} notes for day {}", notes.len(), day);
+        if notes.is_empty() {
+            return Err(FhError::DayNotFound(day));
+        }
+        let notes = notes.into_iter().next().unwrap();
+        self.day_cache().insert(notes.clone());
+        Ok(notes)
+    }
+    fn day_cache(&self) -> MutexGuard<'_, DayCache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    /// Run the completion hook and push for a note newly done.
+    async fn note_completed(&self, note: &Note) {
+        self.hooks.note_completed(note);
+        self.push.note_completed(self, note).await;
+    }
+    /// Guard clearing the day cache once a write is done.
+    fn writing(&self) -> Writing<'_> {
+        Writing(&self.cache)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::notes::StatusMatch;
+    use sqlx::migrate;
+    use std::str::FromStr;
+
+    async fn setup_sqlitedb() -> NoteStore {
+        let s = setup_db("sqlite://:memory:").await.unwrap();
+        migrate!().run(&s.pool).await.unwrap();
+        s.insert_day(Utc::now().date_naive(), None, "")
+            .await
+            .unwrap();
+        s
+    }
+    #[tokio::test]
+    async fn test_get_day_notes() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        let notes = store.get_day_notes_in_range(day, day).await.unwrap();
+        assert_eq!(notes.len(), 1);
+    }
+    #[tokio::test]
+    async fn test_get_day_notes_none() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        let notes = store.get_day_notes_in_range(day, day).await.unwrap();
+        assert_eq!(notes[0].notes.len(), 0);
+    }
+    #[tokio::test]
+    async fn test_note_record() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        store.insert_note(NewNote::new("ship")).await.unwrap();
+        let notes = store.get_day_notes_in_range(day, day).await.unwrap();
+        let note = &notes[0].notes[0];
+        assert_eq!(note.date, Some(day));
+        assert!(note.created_at.is_some() && note.deleted_at.is_none());
+        let gone = store.insert_note(NewNote::new("gone")).await.unwrap();
+        store.soft_delte_note_by_id(gone.id).await.unwrap();
+        let changed = store.notes_changed_since(None).await.unwrap();
+        let gone = changed.iter().find(|c| c.note.id == gone.id).unwrap();
+        assert!(gone.note.deleted_at.is_some());
+        let json = serde_json::to_value(note).unwrap();
+        assert_eq!(json["status"], "todo");
+        assert_eq!(json["date"], day.to_string());
+        assert!(json["created_at"].is_string());
+    }
+    #[tokio::test]
+    async fn test_insert_built_note() {
+        let store = setup_sqlitedb().await;
+        let created_at = Utc::now() - Days::new(1);
+        let due = created_at.date_naive() + Days::new(7);
+        let note = NewNote::builder()
+            .body("ship")
+            .completed(true)
+            .created_at(created_at)
+            .section(Some("Work"))
+            .due(Some(due))
+            .build();
+        let id = store.insert_note(note).await.unwrap().id;
+        let stored = store.get_note(id).await.unwrap();
+        assert_eq!(stored.status, Status::Done);
+        assert_eq!(stored.section.as_deref(), Some("Work"));
+        assert_eq!(stored.due, Some(due));
+        let days = store
+            .get_day_notes_in_range(created_at.date_naive(), created_at.date_naive())
+            .await
+            .unwrap();
+        assert_eq!(days[0].notes[0].id, id);
+    }
+    #[tokio::test]
+    async fn test_note_not_found() {
+        let store = setup_sqlitedb().await;
+        assert!(matches!(
+            store.get_note(99).await,
+            Err(FhError::NoteNotFound { id: 99 })
+        ));
+    }
+    #[tokio::test]
+    async fn test_note_position_order() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        let first = store.insert_note(NewNote::new("first")).await.unwrap();
+        let second = store.insert_note(NewNote::new("second")).await.unwrap();
+        store.update_note_position(first.id, 1).await.unwrap();
+        store.update_note_position(second.id, 0).await.unwrap();
+        let notes = store.get_days_notes(day).await.unwrap();
+        assert_eq!(notes.notes[0].body, "second");
+        assert_eq!(notes.notes[1].body, "first");
+    }
+    #[tokio::test]
+    async fn test_find_open_duplicate() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        let note = store.insert_note(NewNote::new("Buy  Milk")).await.unwrap();
+        let (date, found) = store
+            .find_open_duplicate(" buy milk ", day)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(date, day);
+        assert_eq!(found.id, note.id);
+        let none = store.find_open_duplicate("buy bread", day).await.unwrap();
+        assert!(none.is_none());
+    }
+    #[tokio::test]
+    async fn test_replace_text() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        let note = store
+            .insert_note(NewNote::new("ship apollo"))
+            .await
+            .unwrap();
+        store
+            .update_day_text(day, "apollo went well\n")
+            .await
+            .unwrap();
+        let changes = store
+            .replace_text("apollo", "artemis", None, None, true)
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(store.get_note(note.id).await.unwrap().body, "ship apollo");
+        store
+            .replace_text("apollo", "artemis", Some(day), Some(day), false)
+            .await
+            .unwrap();
+        let notes = store.get_days_notes(day).await.unwrap();
+        assert_eq!(notes.notes[0].body, "ship artemis");
+        assert_eq!(notes.day_text, "artemis went well\n");
+    }
+    #[tokio::test]
+    async fn test_bulk() {
+        let store = setup_sqlitedb().await;
+        let milk = store.insert_note(NewNote::new("milk")).await.unwrap();
+        store.insert_note(NewNote::new("report")).await.unwrap();
+        let all = NoteFilter::default();
+        assert_eq!(store.filtered_notes(&all).await.unwrap().len(), 2);
+        store.bulk_tag(&all, "errands").await.unwrap();
+        assert_eq!(store.get_tags(milk.id).await.unwrap(), vec!["errands"]);
+        let filter = NoteFilter {
+            tag: Some(String::from("errands")),
+            status: Some(StatusMatch::Open),
+            ..Default::default()
+        };
+        let completed = store.bulk_complete(&filter).await.unwrap();
+        assert_eq!(completed.len(), 2);
+        assert!(completed.iter().all(|n| n.status == Status::Done));
+        assert_eq!(store.filtered_notes(&filter).await.unwrap().len(), 0);
+        let done = NoteFilter::from_str("status=done").unwrap();
+        let deleted = store.bulk_delete(&done).await.unwrap();
+        assert_eq!(deleted.len(), 2);
+        assert!(deleted.iter().all(|n| n.deleted_at.is_some()));
+        assert_eq!(store.filtered_notes(&all).await.unwrap().len(), 0);
+    }
+    #[tokio::test]
+    async fn test_body_tags() {
+        let store = setup_sqlitedb().await;
+        let mut note = store
+            .insert_note(NewNote::new("bins #home #notify"))
+            .await
+            .unwrap();
+        store.index_note_body(&note).await.unwrap();
+        store
+            .bulk_tag(&NoteFilter::default(), "errands")
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_tags(note.id).await.unwrap(),
+            vec!["errands", "home", "notify"]
+        );
+        note.body = String::from("bins #home");
+        let note = store.update_note(&note).await.unwrap();
+        store.index_note_body(&note).await.unwrap();
+        assert_eq!(
+            store.get_tags(note.id).await.unwrap(),
+            vec!["errands", "home"]
+        );
+        store.insert_note(NewNote::new("other")).await.unwrap();
+        assert_eq!(
+            store.tagged_note_ids("#Home").await.unwrap(),
+            HashSet::from([note.id])
+        );
+        assert_eq!(
+            store.tag_counts().await.unwrap(),
+            vec![(String::from("errands"), 1), (String::from("home"), 1)]
+        );
+    }
+    #[tokio::test]
+    async fn test_query_notes() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        let milk = store.insert_note(NewNote::new("buy milk")).await.unwrap();
+        store.insert_note(NewNote::new("report")).await.unwrap();
+        store.set_due(milk.id, Some(day)).await.unwrap();
+        let query = Query::parse("status:open due:<=today", day).unwrap();
+        let notes = store.query_notes(&query).await.unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].1.due, Some(day));
+        let query = Query::parse("MILK", day).unwrap();
+        assert_eq!(store.query_notes(&query).await.unwrap().len(), 1);
+        let query = Query::parse("day:<today", day).unwrap();
+        assert_eq!(store.query_notes(&query).await.unwrap().len(), 0);
+    }
+    #[tokio::test]
+    async fn test_saved_query() {
+        let store = setup_sqlitedb().await;
+        assert!(store.get_saved_query("focus").await.unwrap().is_none());
+        store.save_query("focus", "tag:focus").await.unwrap();
+        store
+            .save_query("focus", "tag:focus status:open")
+            .await
+            .unwrap();
+        let query = store.get_saved_query("focus").await.unwrap();
+        assert_eq!(query.as_deref(), Some("tag:focus status:open"));
+    }
+    #[tokio::test]
+    async fn test_read_only_query() {
+        let store = setup_sqlitedb().await;
+        store.insert_note(NewNote::new("milk")).await.unwrap();
+        let (columns, rows) = store
+            .read_only_query("SELECT body, status, due FROM note;")
+            .await
+            .unwrap();
+        assert_eq!(columns, vec!["body", "status", "due"]);
+        assert_eq!(rows, vec![vec!["milk", "todo", ""]]);
+        let write = store.read_only_query("DELETE FROM note;").await;
+        assert!(write.is_err());
+        let bypass = store
+            .read_only_query("PRAGMA query_only = OFF; DELETE FROM note")
+            .await;
+        assert!(bypass.is_err());
+        for sql in [
+            "SELECT 1; DELETE FROM note",
+            "pragma query_only = OFF",
+            "ATTACH DATABASE 'x.db' AS x",
+        ] {
+            assert!(store.read_only_query(sql).await.is_err(), "{}", sql);
         }
-        let mut out = vec![];
-        for delta in 0..day_delta {
-            let day = start_day
-                .checked_add_days(Days::new(delta as u64))
-                .expect("shouldn't be able to overflow.");
-            let day_notes = notes
-                .remove(&day)
-                .unwrap_or(vec![])
-                .into_iter()
-                .map(Note::from)
-                .collect::<Vec<_>>();
-            let text = sqlx::query_scalar!("SELECT day_text from day WHERE date = ?;", day)
-                .fetch_optional(&self.pool)
-                .await
-                .context("Failed fetching day summary text.")?;
-            let note_count = day_notes.len() as u32;
-            out.push(DayNotes {
-                notes: day_notes,
-                date: day,
-                note_count,
-                day_text: text.unwrap_or(String::new()),
-            });
+        let (_, rows) = store
+            .read_only_query("SELECT body FROM note WHERE body != 'a;b' -- one; two\n;")
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![vec!["milk"]]);
+        store
+            .insert_note(NewNote::new("still writable"))
+            .await
+            .unwrap();
+    }
+    #[tokio::test]
+    async fn test_list_notes() {
+        let store = setup_sqlitedb().await;
+        let yesterday = Utc::now()
+            .date_naive()
+            .checked_sub_days(Days::new(1))
+            .unwrap();
+        store
+            .insert_note_on(yesterday, NewNote::new("old"))
+            .await
+            .unwrap();
+        store.insert_note(NewNote::new("new")).await.unwrap();
+        let open = NoteFilter::from_str("status=open").unwrap();
+        let notes = store.list_notes(&open, Some(1)).await.unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].0, yesterday);
+        assert_eq!(notes[0].1.body, "old");
+        assert_eq!(store.list_notes(&open, None).await.unwrap().len(), 2);
+    }
+    #[tokio::test]
+    async fn test_overdue_notes() {
+        let store = setup_sqlitedb().await;
+        let today = Utc::now().date_naive();
+        let yesterday = today.checked_sub_days(Days::new(1)).unwrap();
+        store
+            .insert_note_on(yesterday, NewNote::new("carried"))
+            .await
+            .unwrap();
+        let due = store.insert_note(NewNote::new("due")).await.unwrap();
+        store.set_due(due.id, Some(yesterday)).await.unwrap();
+        store.insert_note(NewNote::new("fine")).await.unwrap();
+        let notes = store.overdue_notes(today).await.unwrap();
+        let bodies = notes
+            .iter()
+            .map(|(_, n)| n.body.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, vec!["carried", "due"]);
+    }
+    #[tokio::test]
+    async fn test_meta() {
+        let store = setup_sqlitedb().await;
+        let note = store
+            .insert_note(NewNote::new("estimate effort:3"))
+            .await
+            .unwrap();
+        store.index_note_body(&note).await.unwrap();
+        store.set_meta(note.id, "effort", "5").await.unwrap();
+        store.set_meta(note.id, "ticket", "ABC-1").await.unwrap();
+        let meta = store.get_meta(note.id).await.unwrap();
+        assert_eq!(
+            meta,
+            vec![
+                (String::from("effort"), String::from("5")),
+                (String::from("ticket"), String::from("ABC-1"))
+            ]
+        );
+    }
+    #[tokio::test]
+    async fn test_mentions() {
+        let store = setup_sqlitedb().await;
+        let note = store
+            .insert_note(NewNote::new("ask @alice and @bob"))
+            .await
+            .unwrap();
+        store.index_note_body(&note).await.unwrap();
+        let other = store.insert_note(NewNote::new("@alice 1:1")).await.unwrap();
+        store.index_note_body(&other).await.unwrap();
+        assert_eq!(
+            store.mentioned_note_ids("@Alice").await.unwrap(),
+            vec![note.id, other.id]
+        );
+        let counts = store.mention_counts().await.unwrap();
+        assert_eq!(
+            counts,
+            vec![(String::from("alice"), 2), (String::from("bob"), 1)]
+        );
+        store.set_mentions(note.id, &[]).await.unwrap();
+        assert!(store.mentioned_note_ids("bob").await.unwrap().is_empty());
+    }
+    #[tokio::test]
+    async fn test_contexts() {
+        let store = setup_sqlitedb()
+            .await
+            .with_contexts(&[String::from("Office"), String::from("home")]);
+        let mut ids = vec![];
+        for (body, status) in [
+            ("print slides @office with @alice", Status::Todo),
+            ("water plants @home", Status::Todo),
+            ("book room @office", Status::Done),
+        ] {
+            let mut new = NewNote::new(body);
+            new.status = status;
+            let note = store.insert_note(new).await.unwrap();
+            store.index_note_body(&note).await.unwrap();
+            ids.push(note.id);
         }
-        Ok(out)
+        assert_eq!(
+            store.context_note_ids("@office").await.unwrap(),
+            HashSet::from([ids[0]])
+        );
+        let counts = store.mention_counts().await.unwrap();
+        assert_eq!(counts, vec![(String::from("alice"), 1)]);
     }
-    pub async fn get_days_notes(&self, day: NaiveDate) -> Result<DayNotes> {
-        let notes = self.get_day_notes_in_range(day, day).await?;
-        log::debug!("Found {} notes for day {}", notes.len(), day);
-        if notes.is_empty() {
-            return Err(anyhow::anyhow!("No notes found for day {}", day));
+    #[tokio::test]
+    async fn test_projects() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        let dns = store
+            .insert_note(NewNote::new("fix dns +infra"))
+            .await
+            .unwrap();
+        store.index_note_body(&dns).await.unwrap();
+        let mut done = NewNote::new("rotate keys");
+        done.status = Status::Done;
+        let done = store.insert_note(done).await.unwrap();
+        store.set_project(done.id, "+Infra").await.unwrap();
+        store.insert_note(NewNote::new("groceries")).await.unwrap();
+        let counts = store.project_counts().await.unwrap();
+        assert_eq!(counts, vec![(String::from("infra"), 1, 1)]);
+        let query = Query::parse("project:infra", day).unwrap();
+        assert_eq!(store.query_notes(&query).await.unwrap().len(), 2);
+    }
+    #[tokio::test]
+    async fn test_day_metrics() {
+        let store = setup_sqlitedb().await;
+        let day = NaiveDate::from_ymd_opt(2025, 10, 12).unwrap();
+        assert!(store.day_metrics(day).await.unwrap().is_empty());
+        let metrics = DayMetrics {
+            mood: Some(4),
+            energy: None,
+            tags: vec![String::from("rainy"), String::from("tired")],
+        };
+        store.set_day_metrics(day, &metrics).await.unwrap();
+        store
+            .set_day_metrics(
+                day + Days::new(2),
+                &DayMetrics {
+                    mood: Some(2),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(store.day_metrics(day).await.unwrap(), metrics);
+        let range = store
+            .day_metrics_in_range(day, day + Days::new(7))
+            .await
+            .unwrap();
+        assert_eq!(range.len(), 2);
+        assert_eq!(range[1].1.mood, Some(2));
+        let bad = DayMetrics {
+            energy: Some(6),
+            ..Default::default()
+        };
+        assert!(store.set_day_metrics(day, &bad).await.is_err());
+        store
+            .set_day_metrics(day, &DayMetrics::default())
+            .await
+            .unwrap();
+        assert!(store.day_metrics(day).await.unwrap().is_empty());
+    }
+    #[tokio::test]
+    async fn test_attachments() {
+        let store = setup_sqlitedb().await;
+        let note = store.insert_note(NewNote::new("design")).await.unwrap();
+        store.add_attachment(note.id, "a.png", "aa").await.unwrap();
+        store.add_attachment(note.id, "b.png", "bb").await.unwrap();
+        store.add_attachment(note.id, "c.png", "aa").await.unwrap();
+        assert!(store.add_attachment(99, "a.png", "aa").await.is_err());
+        let attachments = store.attachments_by_note().await.unwrap();
+        let names = attachments[&note.id]
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["c.png", "b.png"]);
+    }
+    #[tokio::test]
+    async fn test_goal_progress() {
+        let store = setup_sqlitedb().await;
+        let goal = store.add_goal("Ship v2!", "2025Q3").await.unwrap();
+        assert_eq!(goal.slug, "ship-v2");
+        store.add_goal("Run a marathon", "2025").await.unwrap();
+        assert!(store.add_goal("ship V2", "2025Q4").await.is_err());
+        for (body, status) in [
+            ("cut release goal:ship-v2 time:2h", Status::Done),
+            ("write changelog goal:Ship-v2 time:30m", Status::Todo),
+            ("drop ie11 goal:ship-v2 time:soon", Status::Cancelled),
+            ("unrelated time:1h", Status::Done),
+        ] {
+            let mut new = NewNote::new(body);
+            new.status = status;
+            let note = store.insert_note(new).await.unwrap();
+            store.index_note_body(&note).await.unwrap();
         }
-        Ok(notes.into_iter().next().unwrap())
+        let progress = store.goal_progress(Some("2025Q3")).await.unwrap();
+        assert_eq!(
+            progress,
+            vec![GoalProgress {
+                goal,
+                open: 1,
+                done: 1,
+                time: TimeDelta::minutes(150),
+            }]
+        );
+        let all = store.goal_progress(None).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!((all[0].goal.period.as_str(), all[0].open), ("2025", 0));
     }
-}
-
-pub mod test {
-    use super::*;
-    use chrono::{NaiveDate, Utc};
-    use sqlx::migrate;
-
-    async fn setup_sqlitedb() -> NoteStore {
-        let s = setup_db("sqlite://:memory:").await;
-        migrate!().run(&s.pool).await.unwrap();
-        s.insert_day(Utc::now().date_naive(), None, "")
+    #[tokio::test]
+    async fn test_private_notes() {
+        let store = setup_sqlitedb().await;
+        let salary = store
+            .insert_note(NewNote::new("salary talk !!private"))
             .await
             .unwrap();
-        s
+        store.index_note_body(&salary).await.unwrap();
+        let other = store.insert_note(NewNote::new("standup")).await.unwrap();
+        store.index_note_body(&other).await.unwrap();
+        assert_eq!(
+            store.private_note_ids().await.unwrap(),
+            HashSet::from([salary.id])
+        );
+        store.set_private(salary.id, false).await.unwrap();
+        store.set_private(other.id, true).await.unwrap();
+        assert_eq!(
+            store.private_note_ids().await.unwrap(),
+            HashSet::from([other.id])
+        );
+        assert!(store.set_private(9999, true).await.is_err());
     }
     #[tokio::test]
-    async fn test_get_day_notes() {
+    async fn test_cancelled_kept() {
         let store = setup_sqlitedb().await;
         let day = Utc::now().date_naive();
-        let notes = store.get_day_notes_in_range(day, day).await.unwrap();
-        assert_eq!(notes.len(), 1);
+        let note = store.insert_note(NewNote::new("rewrite it")).await.unwrap();
+        store.set_status(note.id, Status::Cancelled).await.unwrap();
+        let notes = store.get_days_notes(day).await.unwrap();
+        assert_eq!(notes.notes[0].status, Status::Cancelled);
+        let overdue = store
+            .overdue_notes(day.checked_add_days(Days::new(1)).unwrap())
+            .await
+            .unwrap();
+        assert!(overdue.is_empty());
     }
     #[tokio::test]
-    async fn test_get_day_notes_none() {
+    async fn test_focus() {
         let store = setup_sqlitedb().await;
         let day = Utc::now().date_naive();
-        let notes = store.get_day_notes_in_range(day, day).await.unwrap();
-        assert_eq!(notes.notes.len(), 0);
+        let mut ids = vec![];
+        for body in ["a", "b", "c", "d"] {
+            ids.push(store.insert_note(NewNote::new(body)).await.unwrap().id);
+        }
+        assert!(store.set_focus(&ids, 3).await.is_err());
+        store.set_focus(&[ids[2], ids[0]], 3).await.unwrap();
+        let notes = store.get_days_notes(day).await.unwrap();
+        let focused = notes.focused();
+        assert_eq!(focused.len(), 2);
+        assert_eq!(focused[0].body, "c");
+        store.set_focus(&[ids[3]], 3).await.unwrap();
+        let notes = store.get_days_notes(day).await.unwrap();
+        assert_eq!(notes.focused().len(), 1);
+    }
+    #[tokio::test]
+    async fn test_day_counts_and_streak() {
+        let store = setup_sqlitedb().await;
+        let today = Utc::now().date_naive();
+        let days_ago = |n: u64| today - Days::new(n);
+        for (day, status) in [
+            (days_ago(0), Status::Todo),
+            (days_ago(0), Status::Cancelled),
+            (days_ago(1), Status::Done),
+            (days_ago(2), Status::Done),
+            (days_ago(4), Status::Done),
+        ] {
+            let mut note = NewNote::new("chore");
+            note.status = status;
+            store.insert_note_on(day, note).await.unwrap();
+        }
+        let counts = store.day_counts(today).await.unwrap();
+        assert_eq!((counts.open, counts.done, counts.total), (1, 0, 2));
+        assert_eq!(store.streak(today).await.unwrap(), 2);
+        let mut done = NewNote::new("done today");
+        done.status = Status::Done;
+        store.insert_note_on(today, done).await.unwrap();
+        assert_eq!(store.streak(today).await.unwrap(), 3);
+        assert_eq!(store.streak(days_ago(3)).await.unwrap(), 1);
+        assert_eq!(store.streak(days_ago(5)).await.unwrap(), 0);
+        assert_eq!(store.overdue_count(today).await.unwrap(), 0);
+        let totals = store.range_totals(days_ago(4), today).await.unwrap();
+        assert_eq!(
+            (totals.created, totals.completed, totals.carried_over),
+            (6, 4, 0)
+        );
+        let totals = store
+            .range_totals(days_ago(4), today + Days::new(1))
+            .await
+            .unwrap();
+        assert_eq!(totals.carried_over, 1);
+    }
+    #[tokio::test]
+    async fn test_skipped_days() {
+        let store = setup_sqlitedb().await;
+        let today = Utc::now().date_naive();
+        let days_ago = |n: u64| today - Days::new(n);
+        for day in [days_ago(0), days_ago(4)] {
+            let mut note = NewNote::new("chore");
+            note.status = Status::Done;
+            store.insert_note_on(day, note).await.unwrap();
+        }
+        store
+            .insert_note_on(days_ago(4), NewNote::new("before leaving"))
+            .await
+            .unwrap();
+        assert_eq!(store.streak(today).await.unwrap(), 1);
+        assert_eq!(store.overdue_count(today).await.unwrap(), 1);
+        let skipped = store
+            .skip_days(days_ago(3), days_ago(1), Some("vacation"))
+            .await
+            .unwrap();
+        assert_eq!(skipped, 3);
+        assert_eq!(store.streak(today).await.unwrap(), 2);
+        assert_eq!(store.overdue_count(today).await.unwrap(), 1);
+        // Back on the first day after the vacation, so nothing is late yet.
+        assert_eq!(store.overdue_count(days_ago(3)).await.unwrap(), 0);
+        assert!(store.skip_days(today, days_ago(1), None).await.is_err());
+        let skipped = store.skipped_days(days_ago(4), today).await.unwrap();
+        assert_eq!(
+            skipped.get(&days_ago(2)),
+            Some(&Some(String::from("vacation")))
+        );
+        assert_eq!(store.delete_day(days_ago(4)).await.unwrap(), 2);
+        assert_eq!(store.overdue_count(today).await.unwrap(), 0);
+        assert_eq!(
+            store.get_days_notes(days_ago(4)).await.unwrap().note_count,
+            0
+        );
+    }
+    #[tokio::test]
+    async fn test_workspaces() {
+        let work = setup_sqlitedb().await;
+        let home = work.clone().with_workspace("home");
+        let today = Utc::now().date_naive();
+        work.insert_note(NewNote::new("standup")).await.unwrap();
+        let errand = home.insert_note(NewNote::new("groceries")).await.unwrap();
+        let bodies = |notes: DayNotes| notes.notes.into_iter().map(|n| n.body).collect::<Vec<_>>();
+        assert_eq!(
+            bodies(work.get_days_notes(today).await.unwrap()),
+            ["standup"]
+        );
+        assert_eq!(
+            bodies(home.get_days_notes(today).await.unwrap()),
+            ["groceries"]
+        );
+        assert!(work.get_note(errand.id).await.is_err());
+        assert!(work.set_private(errand.id, true).await.is_err());
+        let open = NoteFilter::from_str("status=open").unwrap();
+        assert_eq!(work.bulk_complete(&open).await.unwrap().len(), 1);
+        assert_eq!(home.list_notes(&open, None).await.unwrap().len(), 1);
+    }
+    #[tokio::test]
+    async fn test_workspace_day_tables() {
+        let work = setup_sqlitedb().await;
+        let home = work.clone().with_workspace("home");
+        let today = Utc::now().date_naive();
+        let metrics = DayMetrics {
+            mood: Some(4),
+            energy: None,
+            tags: vec![],
+        };
+        work.set_day_metrics(today, &metrics).await.unwrap();
+        let meta = BTreeMap::from([(String::from("location"), String::from("office"))]);
+        work.set_day_meta(today, &meta).await.unwrap();
+        work.skip_days(today, today, Some("offsite")).await.unwrap();
+        work.lock_day(today, 11).await.unwrap();
+        assert!(home.day_metrics(today).await.unwrap().is_empty());
+        assert!(home.day_meta(today).await.unwrap().is_empty());
+        assert!(home.skipped_days(today, today).await.unwrap().is_empty());
+        assert_eq!(home.edit_lock(today).await.unwrap(), None);
+        home.set_day_metrics(today, &DayMetrics::default())
+            .await
+            .unwrap();
+        home.set_day_meta(today, &BTreeMap::new()).await.unwrap();
+        home.unlock_day(today, 11).await.unwrap();
+        assert_eq!(work.day_metrics(today).await.unwrap(), metrics);
+        assert_eq!(work.day_meta(today).await.unwrap(), meta);
+        assert_eq!(work.skipped_days(today, today).await.unwrap().len(), 1);
+        assert!(work.edit_lock(today).await.unwrap().is_some());
+    }
+    #[tokio::test]
+    async fn test_workspace_note_tables() {
+        let work = setup_sqlitedb().await;
+        let home = work.clone().with_workspace("home");
+        let note = work
+            .insert_note(NewNote::new("review #pr @sam priority:high"))
+            .await
+            .unwrap();
+        work.index_note_body(&note).await.unwrap();
+        work.add_attachment(note.id, "diff.txt", "abc")
+            .await
+            .unwrap();
+        work.link_note(note.id, "jira", "PROJ-1").await.unwrap();
+        assert!(home.set_meta(note.id, "priority", "low").await.is_err());
+        assert!(home.set_mentions(note.id, &[]).await.is_err());
+        assert!(home.link_note(note.id, "jira", "PROJ-2").await.is_err());
+        assert!(home.add_attachment(note.id, "x", "def").await.is_err());
+        assert!(home.set_reminder(note.id, None).await.is_err());
+        assert!(
+            home.mark_notified(note.id, Utc::now().date_naive())
+                .await
+                .is_err()
+        );
+        assert!(home.merge_notes(note.id, note.id + 1).await.is_err());
+        assert!(home.get_meta(note.id).await.unwrap().is_empty());
+        assert!(home.get_tags(note.id).await.unwrap().is_empty());
+        assert!(home.tags_by_note().await.unwrap().is_empty());
+        assert!(home.attachments_by_note().await.unwrap().is_empty());
+        assert!(home.meta_values("priority").await.unwrap().is_empty());
+        assert!(home.mentioned_note_ids("sam").await.unwrap().is_empty());
+        assert_eq!(home.linked_note("jira", "PROJ-1").await.unwrap(), None);
+        assert!(home.remote_links("jira").await.unwrap().is_empty());
+        assert_eq!(work.get_meta(note.id).await.unwrap().len(), 1);
+        assert_eq!(work.get_tags(note.id).await.unwrap(), ["pr"]);
+        assert_eq!(work.mentioned_note_ids("sam").await.unwrap(), [note.id]);
+        assert_eq!(
+            work.linked_note("jira", "PROJ-1").await.unwrap(),
+            Some(note.id)
+        );
+    }
+    #[tokio::test]
+    async fn test_reminders() {
+        let store = setup_sqlitedb().await;
+        let now = Utc::now();
+        let call = store.insert_note(NewNote::new("call")).await.unwrap();
+        let bins = store.insert_note(NewNote::new("bins")).await.unwrap();
+        let done = store.insert_note(NewNote::new("done")).await.unwrap();
+        store
+            .set_reminder(call.id, Some(now + TimeDelta::hours(1)))
+            .await
+            .unwrap();
+        store
+            .set_reminder(bins.id, Some(now - TimeDelta::minutes(1)))
+            .await
+            .unwrap();
+        store.set_reminder(done.id, Some(now)).await.unwrap();
+        store.set_status(done.id, Status::Done).await.unwrap();
+        assert!(store.set_reminder(99, Some(now)).await.is_err());
+        let ids =
+            |r: Vec<(DateTime<Utc>, Note)>| r.into_iter().map(|(_, n)| n.id).collect::<Vec<_>>();
+        assert_eq!(ids(store.reminders(now, true).await.unwrap()), [bins.id]);
+        assert_eq!(ids(store.reminders(now, false).await.unwrap()), [call.id]);
+        store.set_reminder(bins.id, None).await.unwrap();
+        assert!(store.reminders(now, true).await.unwrap().is_empty());
+    }
+    #[tokio::test]
+    async fn test_day_cache() {
+        let store = setup_sqlitedb().await;
+        let today = Utc::now().date_naive();
+        assert_eq!(store.get_days_notes(today).await.unwrap().day_text, "");
+        // Writes outside the store aren't seen until the cached day expires.
+        sqlx::query("UPDATE day SET day_text = 'outside';")
+            .execute(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(store.get_days_notes(today).await.unwrap().day_text, "");
+        store.insert_note(NewNote::new("new")).await.unwrap();
+        let notes = store.get_days_notes(today).await.unwrap();
+        assert_eq!(notes.notes.len(), 1);
+        assert_eq!(notes.day_text, "outside");
+    }
+    #[tokio::test]
+    async fn test_for_each_day_in_range() {
+        let store = setup_sqlitedb().await;
+        let today = Utc::now().date_naive();
+        let start = today - Days::new(5);
+        let end = today + Days::new(1);
+        store
+            .insert_note_on(today - Days::new(3), NewNote::new("old"))
+            .await
+            .unwrap();
+        let gone = store.insert_note(NewNote::new("gone")).await.unwrap();
+        store.insert_note(NewNote::new("kept")).await.unwrap();
+        store.soft_delte_note_by_id(gone.id).await.unwrap();
+        store.update_day_text(today, "text\n").await.unwrap();
+        let mut streamed = vec![];
+        store
+            .for_each_day_in_range(start, end, async |day| -> Result<()> {
+                streamed.push(day);
+                Ok(())
+            })
+            .await
+            .unwrap();
+        let loaded = store.get_day_notes_in_range(start, end).await.unwrap();
+        assert_eq!(streamed.len(), 7);
+        for (streamed, loaded) in streamed.iter().zip(&loaded) {
+            assert_eq!(streamed.date, loaded.date);
+            assert_eq!(streamed.day_text, loaded.day_text);
+            let ids = |d: &DayNotes| d.notes.iter().map(|n| n.id).collect::<Vec<_>>();
+            assert_eq!(ids(streamed), ids(loaded), "{}", streamed.date);
+        }
+    }
+    #[tokio::test]
+    async fn test_unnotified_due() {
+        let store = setup_sqlitedb().await;
+        let today = Utc::now().date_naive();
+        let soon = store.insert_note(NewNote::new("pay rent")).await.unwrap();
+        let later = store
+            .insert_note(NewNote::new("renew passport"))
+            .await
+            .unwrap();
+        store.set_due(soon.id, Some(today)).await.unwrap();
+        store
+            .set_due(later.id, Some(today + Days::new(30)))
+            .await
+            .unwrap();
+        let due = store.unnotified_due(today).await.unwrap();
+        assert_eq!(due.iter().map(|n| n.id).collect::<Vec<_>>(), vec![soon.id]);
+        store.mark_notified(soon.id, today).await.unwrap();
+        assert!(store.unnotified_due(today).await.unwrap().is_empty());
+        store
+            .set_due(soon.id, Some(today - Days::new(1)))
+            .await
+            .unwrap();
+        assert_eq!(store.unnotified_due(today).await.unwrap().len(), 1);
+    }
+    #[tokio::test]
+    async fn test_merge_notes() {
+        let store = setup_sqlitedb().await;
+        let keep = store.insert_note(NewNote::new("call bob")).await.unwrap();
+        let dup = store.insert_note(NewNote::new("ring bob")).await.unwrap();
+        store.index_note_body(&dup).await.unwrap();
+        store
+            .set_mentions(dup.id, &[String::from("bob")])
+            .await
+            .unwrap();
+        store.set_meta(dup.id, "effort", "2").await.unwrap();
+        store.set_project(dup.id, "home").await.unwrap();
+        store.link_note(dup.id, "gtasks", "task-1").await.unwrap();
+        store
+            .add_attachment(dup.id, "bob.vcf", "abc")
+            .await
+            .unwrap();
+        let at = Utc::now() + Days::new(1);
+        store.set_reminder(dup.id, Some(at)).await.unwrap();
+        let merged = store.merge_notes(keep.id, dup.id).await.unwrap();
+        assert_eq!(merged.body, "call bob; ring bob");
+        assert!(store.get_note(dup.id).await.is_err());
+        assert_eq!(
+            store.mentioned_note_ids("bob").await.unwrap(),
+            vec![keep.id]
+        );
+        assert_eq!(
+            store.get_meta(keep.id).await.unwrap(),
+            vec![(String::from("effort"), String::from("2"))]
+        );
+        assert_eq!(store.projects_by_note().await.unwrap()[&keep.id], "home");
+        assert_eq!(
+            store.linked_note("gtasks", "task-1").await.unwrap(),
+            Some(keep.id)
+        );
+        assert!(
+            store
+                .attachments_by_note()
+                .await
+                .unwrap()
+                .contains_key(&keep.id)
+        );
+        let reminders = store.reminders(Utc::now(), false).await.unwrap();
+        assert_eq!(reminders[0].1.id, keep.id);
+        assert!(store.merge_notes(keep.id, keep.id).await.is_err());
+        assert!(matches!(
+            store.merge_notes(keep.id, dup.id).await,
+            Err(FhError::NoteNotFound { .. })
+        ));
+    }
+    #[tokio::test]
+    async fn test_notes_changed_since() {
+        let store = setup_sqlitedb().await;
+        let kept = store.insert_note(NewNote::new("kept")).await.unwrap();
+        let gone = store.insert_note(NewNote::new("gone")).await.unwrap();
+        store.soft_delte_note_by_id(gone.id).await.unwrap();
+        let changed = store.notes_changed_since(None).await.unwrap();
+        assert_eq!(changed.len(), 2);
+        assert!(changed.iter().any(|c| c.note.id == gone.id && c.deleted));
+        let mark = changed.iter().map(|c| c.changed_at.clone()).max().unwrap();
+        store.set_export_mark("export", &mark).await.unwrap();
+        let mark = store.export_mark("export").await.unwrap();
+        let changed = store.notes_changed_since(mark.as_deref()).await.unwrap();
+        assert!(changed.is_empty());
+        assert!(store.get_note(kept.id).await.is_ok());
+    }
+    #[tokio::test]
+    async fn test_purge_deleted() {
+        let store = setup_sqlitedb().await;
+        let kept = store.insert_note(NewNote::new("kept")).await.unwrap();
+        let gone = store
+            .insert_note(NewNote::new("gone @alice effort:2"))
+            .await
+            .unwrap();
+        let saved = store.insert_note(NewNote::new("saved")).await.unwrap();
+        store.index_note_body(&gone).await.unwrap();
+        for id in [gone.id, saved.id] {
+            store.soft_delte_note_by_id(id).await.unwrap();
+        }
+        store.set_keep_forever(saved.id, true).await.unwrap();
+        assert_eq!(store.deleted_notes().await.unwrap().len(), 2);
+        let earlier = Utc::now() - Days::new(1);
+        assert_eq!(store.purge_deleted(earlier).await.unwrap(), 0);
+        let later = Utc::now() + Days::new(1);
+        assert_eq!(store.purge_deleted(later).await.unwrap(), 1);
+        let deleted = store.deleted_notes().await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].1.id, saved.id);
+        assert!(store.get_note(kept.id).await.is_ok());
+        assert!(store.mention_counts().await.unwrap().is_empty());
+    }
+    #[tokio::test]
+    async fn test_edit_lock() {
+        let store = setup_sqlitedb().await;
+        let day = Utc::now().date_naive();
+        assert_eq!(store.edit_lock(day).await.unwrap(), None);
+        store.lock_day(day, 10).await.unwrap();
+        store.lock_day(day, 11).await.unwrap();
+        assert_eq!(store.edit_lock(day).await.unwrap().unwrap().pid, 11);
+        store.unlock_day(day, 10).await.unwrap();
+        assert!(store.edit_lock(day).await.unwrap().is_some());
+        store.unlock_day(day, 11).await.unwrap();
+        assert_eq!(store.edit_lock(day).await.unwrap(), None);
+    }
+    #[tokio::test]
+    async fn test_open_in_memory() {
+        let store = NoteStore::open_in_memory().await.unwrap();
+        let note = store.insert_note(NewNote::new("demo")).await.unwrap();
+        assert_eq!(store.get_note(note.id).await.unwrap().body, "demo");
+        let other = NoteStore::open_in_memory().await.unwrap();
+        assert!(other.get_note(note.id).await.is_err());
+    }
+    #[tokio::test]
+    async fn test_share() {
+        let store = setup_sqlitedb().await;
+        let now = Utc::now();
+        let later = now + chrono::Duration::hours(1);
+        store
+            .create_share("abc", "<h1>hi</h1>", later)
+            .await
+            .unwrap();
+        let html = store.get_share("abc", now).await.unwrap();
+        assert_eq!(html.as_deref(), Some("<h1>hi</h1>"));
+        assert!(store.get_share("abc", later).await.unwrap().is_none());
+        assert!(store.get_share("abd", now).await.unwrap().is_none());
+    }
+    #[tokio::test]
+    async fn test_archive_before() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = NoteStore::open(&dir.path().join("db.db")).await.unwrap();
+        let old_day = NaiveDate::from_ymd_opt(2022, 6, 1).unwrap();
+        let old = store
+            .insert_note_on(old_day, NewNote::new("old priority:high"))
+            .await
+            .unwrap();
+        store.index_note_body(&old).await.unwrap();
+        store
+            .add_attachment(old.id, "design.png", "abc123")
+            .await
+            .unwrap();
+        let remind_at = Utc::now() + TimeDelta::hours(1);
+        store.set_reminder(old.id, Some(remind_at)).await.unwrap();
+        store.insert_note(NewNote::new("new")).await.unwrap();
+        store.ensure_note_uids().await.unwrap();
+        let path = dir.path().join("archive-2022.db");
+        let before = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        assert_eq!(store.archive_before(before, &path).await.unwrap(), 1);
+        assert!(store.get_days_notes(old_day).await.unwrap().is_empty());
+        let changes = store.sync_changes_since(None).await.unwrap();
+        let gone = changes.iter().find(|n| n.date == old_day).unwrap();
+        assert!(gone.deleted);
+        assert_eq!(store.archives().await.unwrap(), vec![path.clone()]);
+        let archive = NoteStore::open(&path).await.unwrap();
+        let archived = archive.get_days_notes(old_day).await.unwrap();
+        assert_eq!(archived.notes[0].body, "old priority:high");
+        assert_eq!(
+            archive.meta_values("priority").await.unwrap()[&old.id],
+            "high"
+        );
+        let attachments = archive.attachments_by_note().await.unwrap();
+        assert_eq!(attachments[&old.id][0].hash, "abc123");
+        assert_eq!(archive.reminders(remind_at, true).await.unwrap().len(), 1);
+        assert!(store.attachments_by_note().await.unwrap().is_empty());
+        assert!(store.reminders(remind_at, true).await.unwrap().is_empty());
+        let today = store.get_days_notes(Utc::now().date_naive()).await.unwrap();
+        assert_eq!(today.notes.len(), 1);
+    }
+    #[tokio::test]
+    async fn test_copy_notes() {
+        let store = setup_sqlitedb().await;
+        let today = Utc::now().date_naive();
+        let tomorrow = today.checked_add_days(Days::new(1)).unwrap();
+        let mut done = NewNote::new("done");
+        done.status = Status::Done;
+        store.insert_note(done).await.unwrap();
+        store.insert_note(NewNote::new("open")).await.unwrap();
+        let copied = store
+            .copy_notes(today, tomorrow, true, false)
+            .await
+            .unwrap();
+        assert_eq!(copied, 1);
+        let notes = store.get_days_notes(tomorrow).await.unwrap();
+        assert_eq!(notes.notes.len(), 1);
+        assert_eq!(notes.notes[0].body, "open");
+        let copied = store
+            .copy_notes(today, tomorrow, false, true)
+            .await
+            .unwrap();
+        assert_eq!(copied, 2);
+        let notes = store.get_days_notes(tomorrow).await.unwrap();
+        assert!(notes.notes.iter().all(|n| n.status == Status::Todo));
+    }
+    #[tokio::test]
+    async fn test_insert_notes_on() {
+        let store = setup_sqlitedb().await;
+        let tomorrow = Utc::now()
+            .date_naive()
+            .checked_add_days(Days::new(1))
+            .unwrap();
+        store
+            .insert_note_on(tomorrow, NewNote::new("first"))
+            .await
+            .unwrap();
+        let notes = store
+            .insert_notes_on(
+                tomorrow,
+                vec![NewNote::new("second"), NewNote::new("third")],
+            )
+            .await
+            .unwrap();
+        assert_eq!(notes.len(), 2);
+        assert!(notes.iter().all(|n| n.date == Some(tomorrow)));
+        let day = store.get_days_notes(tomorrow).await.unwrap();
+        let bodies = day
+            .notes
+            .iter()
+            .map(|n| n.body.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, ["first", "second", "third"]);
+        assert_eq!(day.notes[2].id, notes[1].id);
+    }
+    #[tokio::test]
+    async fn test_move_day() {
+        let store = setup_sqlitedb().await;
+        let from = NaiveDate::from_ymd_opt(2025, 10, 16).unwrap();
+        let to = from.succ_opt().unwrap();
+        store.insert_note_on(from, NewNote::new("a")).await.unwrap();
+        store.insert_note_on(from, NewNote::new("b")).await.unwrap();
+        store.update_day_text(from, "moved text").await.unwrap();
+        store
+            .insert_note_on(to, NewNote::new("there"))
+            .await
+            .unwrap();
+        assert_eq!(store.move_day(from, to).await.unwrap(), 2);
+        let day = store.get_days_notes(to).await.unwrap();
+        let bodies = day
+            .notes
+            .iter()
+            .map(|n| n.body.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, ["there", "a", "b"]);
+        assert_eq!(day.day_text, "moved text");
+        let old = store.get_days_notes(from).await.unwrap();
+        assert!(old.notes.is_empty());
+        assert_eq!(old.day_text, "");
+    }
+    #[tokio::test]
+    async fn test_get_dated_note() {
+        let store = setup_sqlitedb().await;
+        let day = NaiveDate::from_ymd_opt(2025, 10, 16).unwrap();
+        let note = store
+            .insert_note_on(day, NewNote::new("milk"))
+            .await
+            .unwrap();
+        let (date, fetched) = store.get_dated_note(note.id).await.unwrap();
+        assert_eq!(date, day);
+        assert_eq!(fetched.body, "milk");
+        store.soft_delte_note_by_id(note.id).await.unwrap();
+        assert!(store.get_dated_note(note.id).await.is_err());
     }
 }
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::store::{NoteStore, SyncNote};
+
+/// A note changed on both sides since the last sync, the later change wins.
+#[derive(Debug, PartialEq)]
+pub struct Conflict {
+    pub local: SyncNote,
+    pub peer: SyncNote,
+    pub local_won: bool,
+}
+impl Conflict {
+    pub fn pretty(&self) -> String {
+        let (kept, lost, side) = if self.local_won {
+            (&self.local, &self.peer, "local")
+        } else {
+            (&self.peer, &self.local, "peer")
+        };
+        format!(
+            "{} kept {} \"{}\" over \"{}\"",
+            kept.date,
+            side,
+            describe(kept),
+            describe(lost)
+        )
+    }
+}
+
+fn describe(note: &SyncNote) -> String {
+    if note.deleted {
+        format!("{} (deleted)", note.body)
+    } else {
+        format!("{} ({})", note.body, note.status.as_str())
+    }
+}
+
+/// Changes to apply on each side of a sync.
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncPlan {
+    pub to_local: Vec<SyncNote>,
+    pub to_peer: Vec<SyncNote>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Decide which changes cross over, a note changed on both sides goes to the later change
+/// with ties kept local.
+pub fn plan(local: Vec<SyncNote>, peer: Vec<SyncNote>) -> SyncPlan {
+    let mut out = SyncPlan::default();
+    let mut peer = peer
+        .into_iter()
+        .map(|n| (n.uid.clone(), n))
+        .collect::<HashMap<_, _>>();
+    for local_note in local {
+        let Some(peer_note) = peer.remove(&local_note.uid) else {
+            out.to_peer.push(local_note);
+            continue;
+        };
+        if same_content(&local_note, &peer_note) {
+            continue;
+        }
+        let local_won = local_note.changed_at >= peer_note.changed_at;
+        if local_won {
+            out.to_peer.push(local_note.clone());
+        } else {
+            out.to_local.push(peer_note.clone());
+        }
+        out.conflicts.push(Conflict {
+            local: local_note,
+            peer: peer_note,
+            local_won,
+        });
+    }
+    let mut rest = peer.into_values().collect::<Vec<_>>();
+    rest.sort_by(|a, b| a.changed_at.cmp(&b.changed_at));
+    out.to_local.extend(rest);
+    out
+}
+
+fn same_content(a: &SyncNote, b: &SyncNote) -> bool {
+    a.date == b.date
+        && a.body == b.body
+        && a.status == b.status
+        && a.section == b.section
+        && a.due == b.due
+        && a.deleted == b.deleted
+}
+
+/// Exchange notes changed since the last sync between two databases, returning the plan carried out.
+pub async fn sync(local: &NoteStore, peer: &NoteStore) -> Result<SyncPlan> {
+    // Changes made while syncing are picked up next time.
+    let started = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    local.ensure_note_uids().await?;
+    peer.ensure_note_uids().await?;
+    let (local_id, peer_id) = (local.device_id().await?, peer.device_id().await?);
+    if local_id == peer_id {
+        return Err(anyhow::anyhow!("Can't sync a database with itself."));
+    }
+    let since = local.synced_at(&peer_id).await?;
+    let plan = plan(
+        local.sync_changes_since(since.as_deref()).await?,
+        peer.sync_changes_since(since.as_deref()).await?,
+    );
+    for note in &plan.to_local {
+        local.apply_sync_note(note).await?;
+    }
+    for note in &plan.to_peer {
+        peer.apply_sync_note(note).await?;
+    }
+    local.set_synced_at(&peer_id, &started).await?;
+    peer.set_synced_at(&local_id, &started).await?;
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, NaiveDate, Utc};
+
+    use super::{plan, sync};
+    use crate::{
+        notes::{NewNote, Status},
+        store::{NoteStore, SyncNote},
+    };
+
+    fn note(uid: &str, body: &str, changed_at: &str) -> SyncNote {
+        SyncNote {
+            uid: String::from(uid),
+            date: NaiveDate::from_ymd_opt(2025, 10, 13).unwrap(),
+            body: String::from(body),
+            status: Status::Todo,
+            section: None,
+            due: None,
+            changed_at: String::from(changed_at),
+            deleted: false,
+        }
+    }
+    #[test]
+    fn test_plan() {
+        let local = vec![
+            note("a", "only local", "2025-10-13 09:00:00"),
+            note("b", "local edit", "2025-10-13 12:00:00"),
+            note("c", "same", "2025-10-13 09:00:00"),
+        ];
+        let peer = vec![
+            note("b", "peer edit", "2025-10-13 11:00:00"),
+            note("c", "same", "2025-10-13 10:00:00"),
+            note("d", "only peer", "2025-10-13 09:00:00"),
+        ];
+        let plan = plan(local, peer);
+        let bodies = |notes: &[SyncNote]| notes.iter().map(|n| n.body.clone()).collect::<Vec<_>>();
+        assert_eq!(bodies(&plan.to_peer), vec!["only local", "local edit"]);
+        assert_eq!(bodies(&plan.to_local), vec!["only peer"]);
+        assert_eq!(plan.conflicts.len(), 1);
+        assert!(plan.conflicts[0].local_won);
+        assert!(
+            plan.conflicts[0].pretty().contains("kept local"),
+            "{}",
+            plan.conflicts[0].pretty()
+        );
+    }
+    #[tokio::test]
+    async fn test_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let laptop = NoteStore::open(&dir.path().join("laptop.db"))
+            .await
+            .unwrap();
+        let desktop = NoteStore::open(&dir.path().join("desktop.db"))
+            .await
+            .unwrap();
+        let today = Utc::now().date_naive();
+        let gone = laptop.insert_note(NewNote::new("gone")).await.unwrap();
+        desktop
+            .insert_note(NewNote::new("from desktop"))
+            .await
+            .unwrap();
+        let plan = sync(&laptop, &desktop).await.unwrap();
+        assert_eq!((plan.to_local.len(), plan.to_peer.len()), (1, 1));
+        assert_eq!(desktop.get_days_notes(today).await.unwrap().notes.len(), 2);
+        laptop.soft_delte_note_by_id(gone.id).await.unwrap();
+        sync(&laptop, &desktop).await.unwrap();
+        let notes = desktop.get_days_notes(today).await.unwrap();
+        assert_eq!(notes.notes.len(), 1);
+        assert_eq!(notes.notes[0].body, "from desktop");
+        assert!(sync(&laptop, &laptop).await.is_err());
+    }
+    #[tokio::test]
+    async fn test_sync_purged() {
+        let dir = tempfile::tempdir().unwrap();
+        let laptop = NoteStore::open(&dir.path().join("laptop.db"))
+            .await
+            .unwrap();
+        let desktop = NoteStore::open(&dir.path().join("desktop.db"))
+            .await
+            .unwrap();
+        let today = Utc::now().date_naive();
+        let gone = laptop.insert_note(NewNote::new("gone")).await.unwrap();
+        sync(&laptop, &desktop).await.unwrap();
+        laptop.soft_delte_note_by_id(gone.id).await.unwrap();
+        laptop
+            .purge_deleted(Utc::now() + Days::new(1))
+            .await
+            .unwrap();
+        let plan = sync(&laptop, &desktop).await.unwrap();
+        assert_eq!(plan.to_peer.len(), 1);
+        assert!(plan.to_peer[0].deleted);
+        assert!(desktop.get_days_notes(today).await.unwrap().is_empty());
+        // The deletion coming back doesn't bring the purged note back.
+        let changes = desktop.sync_changes_since(None).await.unwrap();
+        laptop.apply_sync_note(&changes[0]).await.unwrap();
+        assert!(laptop.get_days_notes(today).await.unwrap().is_empty());
+    }
+    #[tokio::test]
+    async fn test_sync_uid_in_other_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let laptop = NoteStore::open(&dir.path().join("laptop.db"))
+            .await
+            .unwrap();
+        let desktop = NoteStore::open(&dir.path().join("desktop.db"))
+            .await
+            .unwrap();
+        let today = Utc::now().date_naive();
+        desktop.insert_note(NewNote::new("shared")).await.unwrap();
+        sync(&laptop, &desktop).await.unwrap();
+        // The same note lands in a second workspace of the laptop.
+        let work = NoteStore::open(&dir.path().join("laptop.db"))
+            .await
+            .unwrap()
+            .with_workspace("work");
+        let changes = desktop.sync_changes_since(None).await.unwrap();
+        work.apply_sync_note(&changes[0]).await.unwrap();
+        assert_eq!(work.get_days_notes(today).await.unwrap().notes.len(), 1);
+        assert_eq!(laptop.get_days_notes(today).await.unwrap().notes.len(), 1);
+    }
+}
@@ -0,0 +1,116 @@
+//! Note templates for `new --template`, markdown files in `~/.fuckhead/templates`:
+//!
+//! ```markdown
+//! ## Meeting with {{who}}
+//! - [ ] Send {{who}} the notes from {{date}}
+//! - [ ] Book a follow up
+//! ```
+//!
+//! Every line is a note, a `## ` heading puts the notes below it in that section.
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+
+use crate::notes::NewNote;
+
+/// Directory in the config directory holding the `<name>.md` templates.
+pub const DIR_NAME: &str = "templates";
+
+/// Read the template `name` from `dir`.
+pub fn load(dir: &Path, name: &str) -> Result<String> {
+    let path = dir.join(format!("{}.md", name));
+    std::fs::read_to_string(&path).context(format!(
+        "Failed reading template {}, add it as {}.",
+        name,
+        path.display()
+    ))
+}
+
+/// `key=value` of `--var`.
+pub fn parse_var(s: &str) -> Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or(anyhow!("Variable {} should look like key=value.", s))?;
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
+/// The notes of `template` on `date`, `{{date}}` and `{{weekday}}` are always set and any
+/// other `{{name}}` must be in `vars`.
+pub fn expand(template: &str, vars: &[(String, String)], date: NaiveDate) -> Result<Vec<NewNote>> {
+    let mut values = HashMap::from([
+        (String::from("date"), date.to_string()),
+        (String::from("weekday"), date.format("%A").to_string()),
+    ]);
+    values.extend(vars.iter().cloned());
+    let mut notes = Vec::new();
+    let mut section = None;
+    for line in template.lines() {
+        let line = fill(line, &values)?;
+        let line = line.trim();
+        if let Some(heading) = line.strip_prefix("## ") {
+            section = Some(heading.trim().to_string());
+            continue;
+        }
+        let body = ["- [ ] ", "- ", "* "]
+            .iter()
+            .find_map(|m| line.strip_prefix(m))
+            .unwrap_or(line)
+            .trim();
+        if body.is_empty() || body.starts_with("<!--") {
+            continue;
+        }
+        notes.push(
+            NewNote::builder()
+                .body(body)
+                .section(section.clone())
+                .build(),
+        );
+    }
+    Ok(notes)
+}
+
+/// Replace the `{{name}}` placeholders in `line`.
+fn fill(line: &str, values: &HashMap<String, String>) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .ok_or(anyhow!("Unclosed {{{{ in template line {}.", line))?;
+        let name = rest[start + 2..start + end].trim();
+        let value =
+            values
+                .get(name)
+                .ok_or(anyhow!("Template needs {}, pass --var {}=...", name, name))?;
+        out.push_str(&rest[..start]);
+        out.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{expand, parse_var};
+
+    #[test]
+    fn test_expand() {
+        let template = "## Meeting with {{ who }}\n- [ ] Send {{who}} notes from {{date}}\n\n- Book follow up\n<!-- {{weekday}} -->\n";
+        let date = NaiveDate::from_ymd_opt(2025, 10, 16).unwrap();
+        let vars = [parse_var("who=Alice").unwrap()];
+        let notes = expand(template, &vars, date).unwrap();
+        let bodies = notes.iter().map(|n| n.body.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            bodies,
+            ["Send Alice notes from 2025-10-16", "Book follow up"]
+        );
+        assert_eq!(notes[1].section.as_deref(), Some("Meeting with Alice"));
+        assert!(expand(template, &[], date).is_err());
+        assert!(expand("{{date", &[], date).is_err());
+        assert!(parse_var("who").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::notes::{Note, Status};
+
+/// Weights of the urgency score, set under `[urgency]` in the config.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct UrgencyWeights {
+    /// Added in full once a note is `age_max_days` old, scaled down for younger notes.
+    pub age: f64,
+    pub age_max_days: i64,
+    /// Added in full once a note is due, scaled down over the two weeks before.
+    pub due: f64,
+    /// Added for a `priority:h`, `priority:m` or `priority:l` metadata value.
+    pub priority_high: f64,
+    pub priority_medium: f64,
+    pub priority_low: f64,
+    pub doing: f64,
+    pub blocked: f64,
+    pub focus: f64,
+    /// Added for each tag of the note with a weight.
+    pub tags: HashMap<String, f64>,
+}
+impl Default for UrgencyWeights {
+    fn default() -> Self {
+        UrgencyWeights {
+            age: 2.0,
+            age_max_days: 365,
+            due: 12.0,
+            priority_high: 6.0,
+            priority_medium: 3.9,
+            priority_low: 1.8,
+            doing: 4.0,
+            blocked: -5.0,
+            focus: 5.0,
+            tags: HashMap::new(),
+        }
+    }
+}
+
+/// Everything the urgency of a note depends on.
+pub struct UrgencyInput<'a> {
+    pub note: &'a Note,
+    /// Day the note is on.
+    pub date: NaiveDate,
+    pub tags: &'a [String],
+    pub priority: Option<&'a str>,
+}
+
+/// Score how urgently an open note needs doing, higher is more urgent.
+pub fn urgency(input: &UrgencyInput, today: NaiveDate, weights: &UrgencyWeights) -> f64 {
+    let note = input.note;
+    let age = (today - input.date).num_days().max(0) as f64;
+    let mut score = weights.age * (age / weights.age_max_days.max(1) as f64).min(1.0);
+    if let Some(due) = note.due {
+        let days_left = (due - today).num_days();
+        // Overdue counts fully, two weeks out counts a fifth.
+        let due_factor = if days_left <= 0 {
+            1.0
+        } else if days_left >= 14 {
+            0.2
+        } else {
+            1.0 - 0.8 * days_left as f64 / 14.0
+        };
+        score += weights.due * due_factor;
+    }
+    score += match input.priority {
+        Some("h" | "high" | "1") => weights.priority_high,
+        Some("m" | "medium" | "2") => weights.priority_medium,
+        Some("l" | "low" | "3") => weights.priority_low,
+        _ => 0.0,
+    };
+    score += match note.status {
+        Status::Doing => weights.doing,
+        Status::Blocked => weights.blocked,
+        _ => 0.0,
+    };
+    if note.focus_rank.is_some() {
+        score += weights.focus;
+    }
+    for tag in input.tags {
+        score += weights.tags.get(tag).copied().unwrap_or(0.0);
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, NaiveDate};
+
+    use super::{UrgencyInput, UrgencyWeights, urgency};
+    use crate::notes::{Note, Status};
+
+    fn note(status: Status, due: Option<NaiveDate>) -> Note {
+        Note {
+            id: 1,
+            body: String::from("test"),
+            status,
+            section: None,
+            due,
+            focus_rank: None,
+            ..Default::default()
+        }
+    }
+    #[test]
+    fn test_urgency_order() {
+        let today = NaiveDate::from_ymd_opt(2025, 10, 15).unwrap();
+        let mut weights = UrgencyWeights::default();
+        weights.tags.insert(String::from("work"), 1.0);
+        let tags = vec![String::from("work")];
+        let plain = note(Status::Todo, None);
+        let due_soon = note(Status::Todo, today.checked_add_days(Days::new(1)));
+        let blocked = note(Status::Blocked, None);
+        let score = |note: &Note, tags: &[String], priority| {
+            let input = UrgencyInput {
+                note,
+                date: today,
+                tags,
+                priority,
+            };
+            urgency(&input, today, &weights)
+        };
+        assert_eq!(score(&plain, &[], None), 0.0);
+        assert_eq!(score(&plain, &tags, None), 1.0);
+        assert!(score(&due_soon, &[], None) > score(&plain, &[], Some("h")));
+        assert!(score(&blocked, &[], None) < score(&plain, &[], None));
+        let old = UrgencyInput {
+            note: &plain,
+            date: today.checked_sub_days(Days::new(365)).unwrap(),
+            tags: &[],
+            priority: None,
+        };
+        assert_eq!(urgency(&old, today, &weights), weights.age);
+    }
+}
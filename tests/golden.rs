@@ -0,0 +1,141 @@
+//! Golden output of the renderers against a seeded database.
+//! Run with `UPDATE_GOLDEN=1` to rewrite the files in `test/golden` after an intended change.
+
+use std::{path::Path, process::Command};
+
+use sqlx::{SqlitePool, migrate, sqlite::SqliteConnectOptions};
+use tempfile::TempDir;
+
+/// Days, notes in every status, a section, a deleted note, a private note, tags and a project,
+/// all with fixed timestamps so the output doesn't depend on when the test runs.
+const SEED: &str = r#"
+INSERT INTO day (id, task_count, date, day_text) VALUES
+    (1, 0, '2025-10-06', 'Planning day.
+'),
+    (2, 0, '2025-10-08', ''),
+    (3, 0, '2025-10-10', 'Shipped the release.
+Quiet afternoon.
+'),
+    (4, 0, '2025-10-13', '');
+INSERT INTO note (id, body, status, created_at, updated_at, deleted_at, day_key, position, section, due, private) VALUES
+    (1, 'plan sprint +work', 'done', '2025-10-06 09:00:00', '2025-10-06 17:00:00', NULL, 1, 0, NULL, NULL, 0),
+    (2, 'email @alice about invoices', 'todo', '2025-10-06 09:05:00', NULL, NULL, 1, 1, NULL, '2025-10-09', 0),
+    (3, 'removed idea', 'todo', '2025-10-06 09:10:00', NULL, '2025-10-06 12:00:00', 1, 2, NULL, NULL, 0),
+    (4, 'fix <flaky> test', 'doing', '2025-10-08 10:00:00', NULL, NULL, 2, 0, 'Work', NULL, 0),
+    (5, 'wait on review', 'blocked', '2025-10-08 10:05:00', NULL, NULL, 2, 1, 'Work', NULL, 0),
+    (6, 'buy milk', 'cancelled', '2025-10-08 10:10:00', NULL, NULL, 2, 2, 'Home', NULL, 0),
+    (7, 'tag release v2', 'done', '2025-10-10 11:00:00', '2025-10-10 15:00:00', NULL, 3, 0, NULL, NULL, 0),
+    (8, 'diary entry', 'todo', '2025-10-10 11:05:00', NULL, NULL, 3, 1, NULL, NULL, 1),
+    (9, 'retro notes', 'todo', '2025-10-13 09:00:00', NULL, NULL, 4, 0, NULL, NULL, 0);
+INSERT INTO note_tag (note_id, tag) VALUES (1, 'planning'), (7, 'release');
+INSERT INTO project (id, name) VALUES (1, 'work');
+UPDATE note SET project_key = 1 WHERE id = 1;
+"#;
+
+/// A home directory holding a seeded `~/.fuckhead/db.db`.
+async fn seeded_home() -> TempDir {
+    let home = tempfile::tempdir().unwrap();
+    let dir = home.path().join(".fuckhead");
+    std::fs::create_dir_all(&dir).unwrap();
+    let options = SqliteConnectOptions::new()
+        .filename(dir.join("db.db"))
+        .create_if_missing(true);
+    let pool = SqlitePool::connect_with(options).await.unwrap();
+    migrate!().run(&pool).await.unwrap();
+    sqlx::raw_sql(SEED).execute(&pool).await.unwrap();
+    pool.close().await;
+    home
+}
+
+fn fh(home: &Path, args: &[&str]) -> String {
+    // The golden files keep the terminal styling, which is off when piped.
+    let out = Command::new(env!("CARGO_BIN_EXE_fh"))
+        .args(["--color", "always"])
+        .args(args)
+        .env("HOME", home)
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "fh {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap()
+}
+
+/// Compare `actual` to `test/golden/<name>`, or write it when `UPDATE_GOLDEN` is set.
+fn check(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test/golden")
+        .join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Missing {}, run with UPDATE_GOLDEN=1.", path.display()));
+    assert_eq!(
+        actual, expected,
+        "{} changed, run with UPDATE_GOLDEN=1 if intended.",
+        name
+    );
+}
+
+#[tokio::test]
+async fn test_golden_output() {
+    let home = seeded_home().await;
+    let home = home.path();
+    let cases: [(&str, &[&str]); 7] = [
+        (
+            "show_range.txt",
+            &["show", "--from", "2025-10-06", "--to", "2025-10-12"],
+        ),
+        ("show_week.txt", &["show", "--week", "2025-W41"]),
+        (
+            "show_summary.txt",
+            &[
+                "show",
+                "--from",
+                "2025-10-06",
+                "--to",
+                "2025-10-13",
+                "--summary",
+                "--skip-empty",
+            ],
+        ),
+        ("list.json", &["--format", "json", "list", "--all"]),
+        (
+            "export.md",
+            &["export", "--from", "2025-10-06", "--to", "2025-10-13"],
+        ),
+        (
+            "export.html",
+            &[
+                "export",
+                "--from",
+                "2025-10-06",
+                "--to",
+                "2025-10-13",
+                "--format",
+                "html",
+            ],
+        ),
+        (
+            "export_private.md",
+            &[
+                "export",
+                "--from",
+                "2025-10-10",
+                "--to",
+                "2025-10-10",
+                "--include-private",
+            ],
+        ),
+    ];
+    for (name, args) in cases {
+        check(name, &fh(home, args));
+    }
+}